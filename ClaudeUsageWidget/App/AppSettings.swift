@@ -0,0 +1,663 @@
+import Foundation
+
+enum SettingsError: Error, Equatable {
+    case outOfRange(key: String, value: Int, allowed: ClosedRange<Int>)
+    case invalidProxyURL(String)
+    case invalidAPIBaseURL(String)
+    case unknownProfile(String)
+    case invalidTimeZone(String)
+    case invalidQuietHours(String)
+}
+
+extension SettingsError {
+    /// The `AppSettings.Key` the error is about, so Settings can point at the field.
+    var settingKey: String {
+        switch self {
+        case .outOfRange(let key, _, _):
+            // Icon threshold errors name the tier too, as `iconThresholds.high`.
+            return key.split(separator: ".").first.map(String.init) ?? key
+        case .invalidProxyURL: return AppSettings.Key.proxyURL
+        case .invalidAPIBaseURL: return AppSettings.Key.apiBaseURL
+        case .unknownProfile: return AppSettings.Key.activeProfile
+        case .invalidTimeZone: return AppSettings.Key.dayBoundaryTimeZone
+        case .invalidQuietHours: return AppSettings.Key.quietHours
+        }
+    }
+}
+
+extension SettingsError: LocalizedError {
+    var errorDescription: String? {
+        switch self {
+        case let .outOfRange(key, value, allowed):
+            return "\(key) must be between \(allowed.lowerBound) and \(allowed.upperBound) (got \(value))."
+        case .invalidProxyURL(let value):
+            return ProxyError.invalidURL(value).errorDescription
+        case .invalidAPIBaseURL(let value):
+            return "Invalid API base URL \"\(value)\". Use an absolute https:// URL."
+        case .unknownProfile(let name):
+            return "No profile named \"\(name)\" in \(Profile.defaultURL.path)."
+        case .invalidTimeZone(let value):
+            return "Unknown time zone \"\(value)\". Use Local, UTC, or a name like Europe/Berlin."
+        case .invalidQuietHours(let value):
+            return "Invalid quiet hours \"\(value)\". Use 24-hour times such as 22:00 and 07:00."
+        }
+    }
+}
+
+/// Typed access to user-configurable values stored in UserDefaults.
+/// Views bind to the same keys through `@AppStorage`.
+struct AppSettings {
+    enum Key {
+        static let refreshInterval = "refreshInterval"
+        static let maxRetries = "maxRetries"
+        static let retryBaseMs = "retryBaseMs"
+        static let renewRejectedTokens = "renewRejectedTokens"
+        static let tokenSource = "tokenSource"
+        static let tokenFilePath = "tokenFilePath"
+        static let statsCachePath = "statsCachePath"
+        static let notifyThresholdPercent = "notifyThresholdPercent"
+        static let notifyThresholds = "notifyThresholds"
+        static let notifyWindows = "notifyWindows"
+        static let notifyResetWindows = "notifyResetWindows"
+        static let quietHours = "quietHours"
+        static let selectedAccount = "selectedAccount"
+        static let proxyURL = "proxyURL"
+        static let apiBaseURL = "apiBaseURL"
+        static let cacheTTLSeconds = "cacheTTLSeconds"
+        static let tokenTTLSeconds = "tokenTTLSeconds"
+        static let requestTimeoutSeconds = "requestTimeoutSeconds"
+        static let ecoMode = "ecoMode"
+        static let demoMode = "demoMode"
+        static let activeProfile = "activeProfile"
+        static let weekDefinition = "weekDefinition"
+        static let menuBarTitle = "menuBarTitle"
+        static let iconThresholds = "iconThresholds"
+        static let warningPercent = "warningPercent"
+        static let criticalPercent = "criticalPercent"
+        static let dayBoundaryTimeZone = "dayBoundaryTimeZone"
+        static let displayTimeZone = "displayTimeZone"
+        static let historyRetentionDays = "historyRetentionDays"
+    }
+
+    /// Refresh intervals are clamped into this range (seconds)...
+    static let refreshIntervalRange = 10...3600
+    /// ...and anything outside this one is rejected outright.
+    static let refreshIntervalHardLimit = 1...86_400
+    static let defaultRefreshInterval = 300
+    static let requestTimeoutRange = 1...120
+
+    private let defaults: UserDefaults
+    private let profilesFileURL: URL
+    private let proxyPasswords: ProxyPasswordStoreProtocol
+
+    init(
+        defaults: UserDefaults = .standard,
+        profilesFileURL: URL = Profile.defaultURL,
+        proxyPasswords: ProxyPasswordStoreProtocol = ProxyPasswordStore()
+    ) {
+        self.defaults = defaults
+        self.profilesFileURL = profilesFileURL
+        self.proxyPasswords = proxyPasswords
+    }
+
+    /// Stored refresh interval, clamped so a hand-edited value can't cause a tight polling loop.
+    var refreshInterval: Int {
+        Self.clamp(integer(for: Key.refreshInterval, default: Self.defaultRefreshInterval), to: Self.refreshIntervalRange)
+    }
+
+    /// Validates, clamps and stores a new refresh interval, returning the value actually saved.
+    @discardableResult
+    func setRefreshInterval(_ seconds: Int) throws -> Int {
+        let validated = try Self.validateRefreshInterval(seconds)
+        defaults.set(validated, forKey: Key.refreshInterval)
+        return validated
+    }
+
+    static func validateRefreshInterval(_ seconds: Int) throws -> Int {
+        guard refreshIntervalHardLimit.contains(seconds) else {
+            throw SettingsError.outOfRange(key: Key.refreshInterval, value: seconds, allowed: refreshIntervalHardLimit)
+        }
+        return clamp(seconds, to: refreshIntervalRange)
+    }
+
+    private static func clamp(_ value: Int, to range: ClosedRange<Int>) -> Int {
+        min(max(value, range.lowerBound), range.upperBound)
+    }
+
+    var retryPolicy: RetryPolicy {
+        RetryPolicy(
+            maxRetries: max(0, integer(for: Key.maxRetries, default: RetryPolicy.default.maxRetries)),
+            baseDelay: TimeInterval(max(0, integer(for: Key.retryBaseMs, default: 500))) / 1000
+        )
+    }
+
+    /// Renew a token the API still rejects after re-reading the store, and
+    /// save the renewed tokens back to the Keychain item or credentials file
+    /// they were read from. Off by default: Claude Code owns those.
+    ///
+    /// Renewal rotates the refresh token, so it is only done together with
+    /// the write-back; otherwise Claude Code would be left holding a spent
+    /// one. With this off a rejected token is reported as an auth error.
+    var renewsRejectedTokens: Bool {
+        defaults.bool(forKey: Key.renewRejectedTokens)
+    }
+
+    /// Only the choice of source and the file path are stored; tokens never are.
+    var tokenSource: TokenSource {
+        defaults.string(forKey: Key.tokenSource).flatMap(TokenSource.init(rawValue:)) ?? .keychain
+    }
+
+    var tokenFilePath: String? {
+        guard let path = defaults.string(forKey: Key.tokenFilePath), !path.isEmpty else { return nil }
+        return path
+    }
+
+    /// Keychain account to read credentials from; nil means the login user name.
+    var selectedAccount: String? {
+        get {
+            guard let account = defaults.string(forKey: Key.selectedAccount), !account.isEmpty else { return nil }
+            return account
+        }
+        nonmutating set {
+            defaults.set(newValue, forKey: Key.selectedAccount)
+        }
+    }
+
+    /// Profiles defined in `profiles.json`, as of the last `reloadProfiles()`.
+    var profiles: [Profile] {
+        ProfileCache.profiles(from: profilesFileURL)
+    }
+
+    /// Re-reads `profiles.json` so edits apply without a relaunch.
+    @discardableResult
+    func reloadProfiles() -> [Profile] {
+        ProfileCache.reload(from: profilesFileURL)
+    }
+
+    var activeProfileName: String? {
+        get {
+            guard let name = defaults.string(forKey: Key.activeProfile), !name.isEmpty else { return nil }
+            return name
+        }
+        nonmutating set {
+            defaults.set(newValue, forKey: Key.activeProfile)
+        }
+    }
+
+    /// Nil when no profile is selected or the selected one was removed from the file.
+    var activeProfile: Profile? {
+        guard let activeProfileName else { return nil }
+        return profiles.first { $0.name == activeProfileName }
+    }
+
+    /// Selects a profile by name; nil returns to the default credentials.
+    func setActiveProfile(_ name: String?) throws {
+        if let name, !profiles.contains(where: { $0.name == name }) {
+            throw SettingsError.unknownProfile(name)
+        }
+        activeProfileName = name
+    }
+
+    /// The active profile's stats path wins over `statsCachePath`.
+    var statsFileURL: URL {
+        activeProfile?.statsFileURL ?? statsCacheURL ?? StatsService.defaultStatsFileURL
+    }
+
+    /// Stats cache to read instead of `~/.claude/stats-cache.json`, as entered
+    /// in Settings; nil or empty uses the default.
+    var statsCachePath: String? {
+        get {
+            guard let path = defaults.string(forKey: Key.statsCachePath)?.trimmingCharacters(in: .whitespaces),
+                  !path.isEmpty else { return nil }
+            return path
+        }
+        nonmutating set { defaults.set(newValue, forKey: Key.statsCachePath) }
+    }
+
+    /// `statsCachePath` with `~` and environment variables expanded.
+    var statsCacheURL: URL? {
+        statsCachePath.map { URL(fileURLWithPath: Self.expandPath($0)) }
+    }
+
+    /// Expands a leading `~` and `$NAME` / `${NAME}` references. Unset
+    /// variables are left as written.
+    static func expandPath(_ path: String, environment: [String: String] = ProcessInfo.processInfo.environment) -> String {
+        let pattern = #"\$\{([A-Za-z_][A-Za-z0-9_]*)\}|\$([A-Za-z_][A-Za-z0-9_]*)"#
+        guard let regex = try? NSRegularExpression(pattern: pattern) else { return path }
+        var expanded = path
+        for match in regex.matches(in: path, range: NSRange(path.startIndex..., in: path)).reversed() {
+            let nameRange = match.range(at: 1).location != NSNotFound ? match.range(at: 1) : match.range(at: 2)
+            guard let name = Range(nameRange, in: path).map({ String(path[$0]) }),
+                  let value = environment[name],
+                  let range = Range(match.range, in: expanded) else { continue }
+            expanded.replaceSubrange(range, with: value)
+        }
+        return (expanded as NSString).expandingTildeInPath
+    }
+
+    var keychainServiceName: String {
+        activeProfile?.keychainService ?? KeychainService.keychainServiceName
+    }
+
+    /// The active profile's account wins over the account picker.
+    var keychainAccount: String? {
+        if let profile = activeProfile {
+            return profile.keychainAccount
+        }
+        return selectedAccount
+    }
+
+    /// Text shown next to the menu bar icon; none by default.
+    var menuBarTitle: MenuBarTitleMetric {
+        defaults.string(forKey: Key.menuBarTitle).flatMap(MenuBarTitleMetric.init(rawValue:)) ?? .off
+    }
+
+    /// Where the menu bar icon turns moderate, high and critical; falls back
+    /// to the standard 40/70/90 when unset or invalid.
+    var iconThresholds: IconThresholds {
+        guard let values = defaults.array(forKey: Key.iconThresholds) as? [Int], values.count == 3,
+              (try? Self.validateIconThresholds(values[0], values[1], values[2])) != nil else {
+            return .standard
+        }
+        return IconThresholds(moderate: Double(values[0]), high: Double(values[1]), critical: Double(values[2]))
+    }
+
+    /// Each level must be above the previous one, all within 1...100.
+    func setIconThresholds(moderate: Int, high: Int, critical: Int) throws {
+        try Self.validateIconThresholds(moderate, high, critical)
+        defaults.set([moderate, high, critical], forKey: Key.iconThresholds)
+    }
+
+    private static func validateIconThresholds(_ moderate: Int, _ high: Int, _ critical: Int) throws {
+        let levels = [("moderate", moderate), ("high", high), ("critical", critical)]
+        var lowest = 1
+        for (index, (name, value)) in levels.enumerated() {
+            let allowed = lowest...(100 - (levels.count - 1 - index))
+            guard allowed.contains(value) else {
+                throw SettingsError.outOfRange(key: "\(Key.iconThresholds).\(name)", value: value, allowed: allowed)
+            }
+            lowest = value + 1
+        }
+    }
+
+    /// Where bars turn to warning and critical colors, 75% and 90% by default.
+    var statusThresholds: UsageStatusThresholds {
+        let warning = integer(for: Key.warningPercent, default: Int(UsageStatusThresholds.standard.warning))
+        let critical = integer(for: Key.criticalPercent, default: Int(UsageStatusThresholds.standard.critical))
+        guard (try? Self.validateStatusThresholds(warning: warning, critical: critical)) != nil else { return .standard }
+        return UsageStatusThresholds(warning: Double(warning), critical: Double(critical))
+    }
+
+    /// The warning level must be below the critical one, both within 1...100.
+    func setStatusThresholds(warning: Int, critical: Int) throws {
+        try Self.validateStatusThresholds(warning: warning, critical: critical)
+        defaults.set(warning, forKey: Key.warningPercent)
+        defaults.set(critical, forKey: Key.criticalPercent)
+    }
+
+    private static func validateStatusThresholds(warning: Int, critical: Int) throws {
+        guard (1...99).contains(warning) else {
+            throw SettingsError.outOfRange(key: Key.warningPercent, value: warning, allowed: 1...99)
+        }
+        guard ((warning + 1)...100).contains(critical) else {
+            throw SettingsError.outOfRange(key: Key.criticalPercent, value: critical, allowed: (warning + 1)...100)
+        }
+    }
+
+    var weekDefinition: WeekDefinition {
+        defaults.string(forKey: Key.weekDefinition).flatMap(WeekDefinition.init(rawValue:)) ?? .rolling
+    }
+
+    /// Where "today" starts: "Local" (the default), "UTC", or an IANA name.
+    /// A hand-edited name that isn't a known zone falls back to local time.
+    var dayBoundaryTimeZone: TimeZone {
+        Self.timeZone(named: dayBoundaryTimeZoneName) ?? .current
+    }
+
+    /// The stored name, as typed.
+    var dayBoundaryTimeZoneName: String {
+        defaults.string(forKey: Key.dayBoundaryTimeZone) ?? ""
+    }
+
+    func setDayBoundaryTimeZone(_ value: String) throws {
+        let trimmed = value.trimmingCharacters(in: .whitespaces)
+        guard Self.timeZone(named: trimmed) != nil else {
+            throw SettingsError.invalidTimeZone(value)
+        }
+        defaults.set(trimmed, forKey: Key.dayBoundaryTimeZone)
+    }
+
+    /// Zone update times are shown in: blank or "Local" for the Mac's own,
+    /// "UTC", or an IANA name. A name that isn't a known zone shows UTC.
+    var displayTimeZone: TimeZone {
+        Self.displayTimeZone(named: displayTimeZoneName)
+    }
+
+    /// The stored name, as typed.
+    var displayTimeZoneName: String {
+        defaults.string(forKey: Key.displayTimeZone) ?? ""
+    }
+
+    /// Unlike the day boundary, an unknown name is stored rather than
+    /// rejected: times then show in UTC and a warning is logged. Returns the
+    /// zone times will be shown in.
+    @discardableResult
+    func setDisplayTimeZone(_ value: String) -> TimeZone {
+        let trimmed = value.trimmingCharacters(in: .whitespaces)
+        defaults.set(trimmed, forKey: Key.displayTimeZone)
+        if !Self.isKnownTimeZone(trimmed) {
+            DebugLogger.shared.log("Unknown display time zone \"\(trimmed)\"; showing times in UTC", level: .warning, source: "App")
+        }
+        return displayTimeZone
+    }
+
+    static func isKnownTimeZone(_ value: String) -> Bool {
+        timeZone(named: value.trimmingCharacters(in: .whitespaces)) != nil
+    }
+
+    static func displayTimeZone(named value: String) -> TimeZone {
+        timeZone(named: value.trimmingCharacters(in: .whitespaces)) ?? TimeZone(identifier: "UTC")!
+    }
+
+    private static func timeZone(named value: String) -> TimeZone? {
+        switch value.lowercased() {
+        case "", "local": return .current
+        case "utc": return TimeZone(identifier: "UTC")
+        default: return TimeZone(identifier: value)
+        }
+    }
+
+    /// Stretch the refresh interval while the popover is closed or Low Power Mode is on.
+    var ecoMode: Bool {
+        defaults.bool(forKey: Key.ecoMode)
+    }
+
+    /// Show made-up usage instead of calling the API. `CLAUDE_WIDGET_DEMO=1`
+    /// turns it on without touching the stored setting.
+    func isDemoMode(environment: [String: String] = ProcessInfo.processInfo.environment) -> Bool {
+        defaults.bool(forKey: Key.demoMode) || environment[DemoUsage.environmentKey] == "1"
+    }
+
+    /// 5-hour utilization that triggers an alert, or nil when alerts are off (stored as 0).
+    var notifyThresholdPercent: Double? {
+        let value = integer(for: Key.notifyThresholdPercent, default: 0)
+        guard value > 0 else { return nil }
+        return Double(min(value, 100))
+    }
+
+    /// Utilization levels that each trigger one alert per reset cycle,
+    /// ascending. Falls back to the single `notifyThresholdPercent` from
+    /// older versions; empty means alerts are off.
+    var notifyThresholds: [Double] {
+        guard let stored = defaults.array(forKey: Key.notifyThresholds) as? [Int] else {
+            return notifyThresholdPercent.map { [$0] } ?? []
+        }
+        return Set(stored.filter { Self.notifyThresholdRange.contains($0) }).sorted().map(Double.init)
+    }
+
+    static let notifyThresholdRange = 1...100
+
+    /// Throws when a value is outside `notifyThresholdRange`; an empty list turns alerts off.
+    func setNotifyThresholds(_ values: [Int]) throws {
+        if let invalid = values.first(where: { !Self.notifyThresholdRange.contains($0) }) {
+            throw SettingsError.outOfRange(key: Key.notifyThresholds, value: invalid, allowed: Self.notifyThresholdRange)
+        }
+        defaults.set(Array(Set(values)).sorted(), forKey: Key.notifyThresholds)
+    }
+
+    /// Windows whose threshold crossings alert; only the 5-hour window by default.
+    var notifyWindows: Set<MetricKey> {
+        guard let stored = defaults.stringArray(forKey: Key.notifyWindows) else { return [.fiveHour] }
+        return Set(stored.compactMap(MetricKey.init(rawValue:)))
+    }
+
+    func setNotifyWindows(_ windows: Set<MetricKey>) {
+        defaults.set(windows.map(\.rawValue).sorted(), forKey: Key.notifyWindows)
+    }
+
+    /// Windows that alert when they reset; none by default.
+    var notifyResetWindows: Set<MetricKey> {
+        Set((defaults.stringArray(forKey: Key.notifyResetWindows) ?? []).compactMap(MetricKey.init(rawValue:)))
+    }
+
+    func setNotifyResetWindows(_ windows: Set<MetricKey>) {
+        defaults.set(windows.map(\.rawValue).sorted(), forKey: Key.notifyResetWindows)
+    }
+
+    /// Window in which alerts are held back, evaluated in the day-boundary
+    /// time zone; nil when not set.
+    var quietHours: QuietHours? {
+        guard let data = defaults.data(forKey: Key.quietHours) else { return nil }
+        return try? JSONDecoder().decode(QuietHours.self, from: data)
+    }
+
+    /// Empty `start` and `end` turn quiet hours off.
+    func setQuietHours(start: String, end: String, days: Set<Int> = []) throws {
+        if start.trimmingCharacters(in: .whitespaces).isEmpty && end.trimmingCharacters(in: .whitespaces).isEmpty {
+            defaults.removeObject(forKey: Key.quietHours)
+            return
+        }
+        guard let quietHours = QuietHours(start: start, end: end, days: days) else {
+            throw SettingsError.invalidQuietHours("\(start)–\(end)")
+        }
+        defaults.set(try? JSONEncoder().encode(quietHours), forKey: Key.quietHours)
+    }
+
+    /// Proxy URL entered in Settings; overrides `HTTPS_PROXY` / `HTTP_PROXY` when set.
+    /// Never includes the password, which is kept in the Keychain.
+    var proxyURL: String? {
+        guard let value = defaults.string(forKey: Key.proxyURL)?.trimmingCharacters(in: .whitespaces), !value.isEmpty else {
+            return nil
+        }
+        return value
+    }
+
+    /// Rejects malformed URLs before they're stored, so a typo can't break
+    /// every later request. An empty string clears the override.
+    ///
+    /// A password, given separately or inside the URL, goes to the Keychain
+    /// and only the rest of the URL is stored. A nil or empty `password`
+    /// keeps the one already saved for that user.
+    func setProxyURL(_ value: String, password: String? = nil) throws {
+        let trimmed = value.trimmingCharacters(in: .whitespaces)
+        let previous = proxyURL.flatMap { try? ProxyConfiguration(urlString: $0) }
+        var proxy: ProxyConfiguration?
+        if !trimmed.isEmpty {
+            do {
+                proxy = try ProxyConfiguration(urlString: trimmed)
+            } catch {
+                throw SettingsError.invalidProxyURL(ProxyConfiguration.removingPassword(from: value))
+            }
+        }
+        if let proxy, proxy.username != nil,
+           let secret = password.flatMap({ $0.isEmpty ? nil : $0 }) ?? proxy.password {
+            try proxyPasswords.setPassword(secret, for: proxy)
+        }
+        if let previous, previous.username != nil, previous.account != proxy?.account {
+            try? proxyPasswords.setPassword(nil, for: previous)
+        }
+        defaults.set(ProxyConfiguration.removingPassword(from: trimmed), forKey: Key.proxyURL)
+    }
+
+    /// Throws `ProxyError` when the stored or environment proxy is malformed.
+    func proxyConfiguration(environment: [String: String] = ProcessInfo.processInfo.environment) throws -> ProxyConfiguration? {
+        guard let proxyURL else { return try .fromEnvironment(environment) }
+        var proxy = try ProxyConfiguration(urlString: proxyURL, exceptions: ProxyConfiguration.noProxyHosts(environment))
+        if proxy.password != nil {
+            // Saved before passwords moved to the Keychain.
+            try? setProxyURL(proxyURL)
+        } else if proxy.username != nil {
+            proxy.password = proxyPasswords.password(for: proxy)
+        }
+        return proxy
+    }
+
+    /// How long usage history is kept; at least a day.
+    var historyRetention: TimeInterval {
+        let defaultDays = Int(UsageHistoryService.defaultRetention / 86400)
+        return TimeInterval(max(1, integer(for: Key.historyRetentionDays, default: defaultDays))) * 86400
+    }
+
+    /// How long a usage response without validators is reused; 0 disables it.
+    var cacheTTL: TimeInterval {
+        TimeInterval(max(0, integer(for: Key.cacheTTLSeconds, default: Int(APIService.defaultCacheTTL))))
+    }
+
+    /// How long a token read from the keychain is used before reading it
+    /// again, so one renewed by Claude Code is picked up without waiting for
+    /// a 401; 0 disables it.
+    var tokenTTL: TimeInterval {
+        TimeInterval(max(0, integer(for: Key.tokenTTLSeconds, default: 3600)))
+    }
+
+    /// Per-request network timeout in seconds, clamped to `requestTimeoutRange`.
+    var requestTimeout: TimeInterval {
+        let seconds = integer(for: Key.requestTimeoutSeconds, default: Int(URLSessionConfiguration.defaultRequestTimeout))
+        return TimeInterval(Self.clamp(seconds, to: Self.requestTimeoutRange))
+    }
+
+    /// The gateway URL entered in Settings, if any.
+    var apiBaseURLOverride: String? {
+        guard let value = defaults.string(forKey: Key.apiBaseURL)?.trimmingCharacters(in: .whitespaces), !value.isEmpty else {
+            return nil
+        }
+        return value
+    }
+
+    /// Where usage requests go; defaults to api.anthropic.com. A stored value
+    /// that doesn't validate (e.g. hand-edited) throws rather than quietly
+    /// sending the token to the default host instead of the gateway.
+    var apiBaseURL: URL {
+        get throws {
+            guard let value = apiBaseURLOverride else { return APIService.defaultBaseURL }
+            do {
+                return try Self.validateAPIBaseURL(value)
+            } catch {
+                throw APIError.invalidBaseURL(value)
+            }
+        }
+    }
+
+    /// Stores a gateway base URL; an empty string restores the default.
+    func setAPIBaseURL(_ value: String) throws {
+        let trimmed = value.trimmingCharacters(in: .whitespaces)
+        if !trimmed.isEmpty {
+            _ = try Self.validateAPIBaseURL(trimmed)
+        }
+        defaults.set(trimmed, forKey: Key.apiBaseURL)
+    }
+
+    /// Absolute https URLs only, with no query or fragment to mangle the endpoint path.
+    static func validateAPIBaseURL(_ value: String) throws -> URL {
+        let trimmed = value.trimmingCharacters(in: .whitespaces)
+        guard let components = URLComponents(string: trimmed),
+              components.scheme?.lowercased() == "https",
+              let host = components.host, !host.isEmpty,
+              components.query == nil, components.fragment == nil,
+              let url = components.url else {
+            throw SettingsError.invalidAPIBaseURL(value)
+        }
+        return url
+    }
+
+    /// Every stored value the getters would have to clamp or ignore, e.g.
+    /// after a hand edit. Getters keep working either way; this only reports.
+    func validate() -> [SettingsError] {
+        var problems: [SettingsError] = []
+        func check(_ work: () throws -> Void) {
+            do {
+                try work()
+            } catch let error as SettingsError {
+                problems.append(error)
+            } catch {}
+        }
+
+        if defaults.object(forKey: Key.refreshInterval) != nil {
+            let seconds = defaults.integer(forKey: Key.refreshInterval)
+            if !Self.refreshIntervalRange.contains(seconds) {
+                problems.append(.outOfRange(key: Key.refreshInterval, value: seconds, allowed: Self.refreshIntervalRange))
+            }
+        }
+        if defaults.object(forKey: Key.requestTimeoutSeconds) != nil {
+            let seconds = defaults.integer(forKey: Key.requestTimeoutSeconds)
+            if !Self.requestTimeoutRange.contains(seconds) {
+                problems.append(.outOfRange(key: Key.requestTimeoutSeconds, value: seconds, allowed: Self.requestTimeoutRange))
+            }
+        }
+        if let values = defaults.array(forKey: Key.iconThresholds) as? [Int] {
+            check {
+                guard values.count == 3 else {
+                    throw SettingsError.outOfRange(key: Key.iconThresholds, value: values.count, allowed: 3...3)
+                }
+                try Self.validateIconThresholds(values[0], values[1], values[2])
+            }
+        }
+        check {
+            try Self.validateStatusThresholds(
+                warning: integer(for: Key.warningPercent, default: Int(UsageStatusThresholds.standard.warning)),
+                critical: integer(for: Key.criticalPercent, default: Int(UsageStatusThresholds.standard.critical))
+            )
+        }
+        if let values = defaults.array(forKey: Key.notifyThresholds) as? [Int],
+           let invalid = values.first(where: { !Self.notifyThresholdRange.contains($0) }) {
+            problems.append(.outOfRange(key: Key.notifyThresholds, value: invalid, allowed: Self.notifyThresholdRange))
+        }
+        if defaults.data(forKey: Key.quietHours) != nil, quietHours == nil {
+            problems.append(.invalidQuietHours("(unreadable)"))
+        }
+        if let proxyURL, (try? ProxyConfiguration(urlString: proxyURL)) == nil {
+            problems.append(.invalidProxyURL(proxyURL))
+        }
+        if let apiBaseURLOverride {
+            check { _ = try Self.validateAPIBaseURL(apiBaseURLOverride) }
+        }
+        if let activeProfileName, activeProfile == nil {
+            problems.append(.unknownProfile(activeProfileName))
+        }
+        if Self.timeZone(named: dayBoundaryTimeZoneName) == nil {
+            problems.append(.invalidTimeZone(dayBoundaryTimeZoneName))
+        }
+        return problems
+    }
+
+    private func integer(for key: String, default value: Int) -> Int {
+        defaults.object(forKey: key) == nil ? value : defaults.integer(forKey: key)
+    }
+}
+
+extension APIService {
+    convenience init(settings: AppSettings) {
+        self.init(baseURL: { try settings.apiBaseURL }, retryPolicy: { settings.retryPolicy }, cacheTTL: { settings.cacheTTL })
+    }
+}
+
+extension KeychainService {
+    convenience init(settings: AppSettings) {
+        self.init(
+            tokenSource: { settings.tokenSource },
+            tokenFilePath: { settings.tokenFilePath },
+            selectedAccount: { settings.keychainAccount },
+            serviceName: { settings.keychainServiceName }
+        )
+    }
+}
+
+extension StatsService {
+    convenience init(settings: AppSettings) {
+        self.init(
+            statsFileOverride: { settings.activeProfile?.statsFileURL },
+            statsCacheOverride: { settings.statsCacheURL },
+            weekDefinition: { settings.weekDefinition },
+            dayBoundaryTimeZone: { settings.dayBoundaryTimeZone }
+        )
+    }
+}
+
+extension CodexStatsService {
+    convenience init(settings: AppSettings) {
+        self.init(
+            weekDefinition: { settings.weekDefinition },
+            dayBoundaryTimeZone: { settings.dayBoundaryTimeZone }
+        )
+    }
+}
@@ -2,8 +2,15 @@ import SwiftUI
 
 @main
 struct ClaudeUsageWidgetApp: App {
-    @StateObject private var manager = UsageManager()
+    @StateObject private var manager: UsageManager
     @AppStorage("refreshInterval") private var refreshInterval: Int = 300
+    @AppStorage(AppSettings.Key.menuBarTitle) private var menuBarTitle: MenuBarTitleMetric = .off
+
+    init() {
+        let manager = UsageManager()
+        manager.startBackgroundRefresh()
+        _manager = StateObject(wrappedValue: manager)
+    }
 
     var body: some Scene {
         MenuBarExtra {
@@ -13,8 +20,17 @@ struct ClaudeUsageWidgetApp: App {
             )
         } label: {
             let image = manager.iconTier.menuBarImage()
-            Image(nsImage: image)
-                .accessibilityLabel(manager.iconTier.accessibilityLabel)
+            HStack(spacing: 4) {
+                Image(nsImage: image)
+                if let title = menuBarTitle.title(for: manager.snapshot) {
+                    Text(title)
+                        .monospacedDigit()
+                }
+            }
+            .opacity(manager.iconTier.menuBarOpacity)
+            .help(MenuBarTooltip.text(for: manager.snapshot))
+            .accessibilityElement(children: .ignore)
+            .accessibilityLabel(manager.iconTier.accessibilityLabel)
         }
         .menuBarExtraStyle(.window)
     }
@@ -34,9 +50,11 @@ struct MenuBarContentView: View {
         .frame(width: navigation.panel.size.width, height: navigation.panel.size.height)
         .background(AnthropicColors.charcoal.opacity(0.95))
         .animation(.easeInOut(duration: 0.15), value: navigation.panel)
-        .task {
-            manager.startTimer(interval: TimeInterval(refreshInterval))
-            await manager.refresh()
+        .onAppear {
+            Task { await manager.setPopoverVisible(true) }
+        }
+        .onReceive(NotificationCenter.default.publisher(for: NSApplication.willTerminateNotification)) { _ in
+            manager.stopWatchingStats()
         }
         .onOpenURL { url in
             // Handle claudeusage://open — app is already activated by macOS
@@ -45,6 +63,7 @@ struct MenuBarContentView: View {
             }
         }
         .onChange(of: scenePhase) { _, newPhase in
+            Task { await manager.setPopoverVisible(newPhase == .active) }
             if MenuBarClosePolicy.shouldDismiss(for: newPhase) {
                 dismiss()
             }
@@ -57,7 +76,7 @@ struct MenuBarContentView: View {
         case .usage:
             PopoverView(
                 manager: manager,
-                onRefresh: { await manager.refresh() },
+                onRefresh: { await manager.refresh(userInitiated: true) },
                 onOpenSettings: { navigation.openSettings() }
             )
         case .settings:
@@ -66,11 +85,25 @@ struct MenuBarContentView: View {
                 onBack: { navigation.goBack() },
                 onOpenDebugger: { navigation.openDebugger() },
                 onIntervalChanged: { interval in
-                    manager.startTimer(interval: TimeInterval(interval))
+                    let settings = AppSettings()
+                    let applied: Int
+                    do {
+                        applied = try settings.setRefreshInterval(interval)
+                    } catch {
+                        DebugLogger.shared.log("REFRESH INTERVAL WRITE FAILED: \(error)", level: .error, source: "App")
+                        applied = settings.refreshInterval
+                    }
+                    if applied != interval {
+                        refreshInterval = applied
+                    }
+                    manager.startTimer(interval: TimeInterval(applied))
                 }
             )
         case .debugger:
-            DebugLogView(onBack: { navigation.goBack() })
+            DebugLogView(
+                onBack: { navigation.goBack() },
+                onRunDiagnostics: { await manager.runDiagnostics() }
+            )
         }
     }
 }
@@ -0,0 +1,45 @@
+import Foundation
+
+/// Made-up usage for demos, screenshots and UI work, so neither a token nor
+/// real quota is needed. Values follow the clock: each window fills up over
+/// its length and drops back at its reset, like real usage would.
+enum DemoUsage {
+    /// Set to `1` to force demo mode regardless of the stored setting.
+    static let environmentKey = "CLAUDE_WIDGET_DEMO"
+
+    static func snapshot(now: Date = Date()) -> UsageSnapshot {
+        let fiveHour = metric(.fiveHour, now: now, low: 8, high: 96)
+        let sevenDay = metric(.sevenDay, now: now, low: 5, high: 82)
+        let weekFraction = progress(through: .sevenDay, now: now)
+
+        return UsageSnapshot(
+            fiveHour: fiveHour,
+            sevenDay: sevenDay,
+            sevenDaySonnet: UsageMetric(percent: (sevenDay.percent * 0.6).rounded(), resetsAt: sevenDay.resetsAt),
+            sevenDayOpus: UsageMetric(percent: (sevenDay.percent * 0.35).rounded(), resetsAt: sevenDay.resetsAt),
+            tokenStats: TokenStats(
+                todayTokens: Int(fiveHour.percent * 48_000),
+                weekTokens: Int(weekFraction * 9_600_000) + 250_000,
+                todayMessages: Int(fiveHour.percent * 3),
+                weekMessages: Int(weekFraction * 1_900) + 40
+            ),
+            lastUpdated: now,
+            lastSuccessfulUpdate: now,
+            error: nil,
+            plan: PlanInfo(name: "Demo", organization: "Demo data, not your real usage"),
+            isDemo: true
+        )
+    }
+
+    private static func metric(_ key: MetricKey, now: Date, low: Double, high: Double) -> UsageMetric {
+        let duration = key.windowDuration
+        let elapsed = now.timeIntervalSince1970.truncatingRemainder(dividingBy: duration)
+        let percent = low + (high - low) * elapsed / duration
+        return UsageMetric(percent: percent.rounded(), resetsAt: now.addingTimeInterval(duration - elapsed))
+    }
+
+    private static func progress(through key: MetricKey, now: Date) -> Double {
+        let duration = key.windowDuration
+        return now.timeIntervalSince1970.truncatingRemainder(dividingBy: duration) / duration
+    }
+}
@@ -0,0 +1,64 @@
+import Foundation
+
+/// One line of the diagnostics report. Messages never include secrets.
+struct DiagnosticCheck: Equatable, Identifiable {
+    let name: String
+    let passed: Bool
+    let message: String
+
+    var id: String { name }
+}
+
+/// Result of `UsageManager.runDiagnostics()`, covering each thing a refresh
+/// depends on so a failure can be traced without reading the debug log.
+struct DiagnosticsReport: Equatable {
+    let checks: [DiagnosticCheck]
+    let generatedAt: Date
+
+    var allPassed: Bool {
+        checks.allSatisfy(\.passed)
+    }
+
+    /// Plain-text form for the debug log and the clipboard.
+    var summary: String {
+        checks
+            .map { "[\($0.passed ? "OK" : "FAIL")] \($0.name): \($0.message)" }
+            .joined(separator: "\n")
+    }
+}
+
+enum Diagnostics {
+    static let homeDirectoryCheck = "Home directory"
+    static let statsCacheCheck = "Stats cache"
+    static let credentialsCheck = "Credentials"
+    static let apiReachabilityCheck = "API reachability"
+
+    static func checkHomeDirectory(_ url: URL, fileManager: FileManager = .default) -> DiagnosticCheck {
+        var isDirectory: ObjCBool = false
+        let exists = fileManager.fileExists(atPath: url.path, isDirectory: &isDirectory) && isDirectory.boolValue
+        return DiagnosticCheck(
+            name: homeDirectoryCheck,
+            passed: exists,
+            message: exists ? url.path : "\(url.path) is not a readable directory"
+        )
+    }
+
+    /// Where a token was found and how long it is; never the token itself.
+    static func credentialsSummary(_ credentials: OAuthCredentials, now: Date = Date()) -> String {
+        let expiry = credentials.isExpired(now: now) ? " (expired)" : ""
+        return "Token found in \(credentials.source?.displayName ?? "unknown source"), \(credentials.accessToken.count) characters\(expiry)"
+    }
+
+    static func checkStatsCache(at url: URL) -> DiagnosticCheck {
+        guard let data = FileManager.default.contents(atPath: url.path) else {
+            return DiagnosticCheck(name: statsCacheCheck, passed: false, message: "Not found at \(url.path)")
+        }
+        do {
+            let cache = try JSONDecoder().decode(StatsCache.self, from: data)
+            let days = cache.dailyModelTokens?.count ?? 0
+            return DiagnosticCheck(name: statsCacheCheck, passed: true, message: "Parsed \(days) day\(days == 1 ? "" : "s") of token data")
+        } catch {
+            return DiagnosticCheck(name: statsCacheCheck, passed: false, message: "Unreadable: \(error.localizedDescription)")
+        }
+    }
+}
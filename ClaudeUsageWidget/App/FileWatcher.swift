@@ -0,0 +1,111 @@
+import Foundation
+
+/// Calls `onChange` when a file is rewritten, coalescing bursts of writes into
+/// one callback after `debounce` seconds of quiet. Watches the parent
+/// directory rather than the file itself, because atomic saves replace the
+/// file and would orphan a descriptor opened on the old one.
+///
+/// All mutable state is confined to `queue`; `onChange` runs there too.
+final class FileWatcher {
+    /// Runs `work` after the given delay. Tests pass one they fire by hand.
+    typealias Scheduler = (TimeInterval, @escaping () -> Void) -> Void
+
+    private let fileURL: URL
+    private let debounce: TimeInterval
+    private let queue: DispatchQueue
+    private let queueKey = DispatchSpecificKey<Void>()
+    private let schedule: Scheduler
+    private let onChange: () -> Void
+    private var source: DispatchSourceFileSystemObject?
+    /// Bumped for every change and on stop; a scheduled callback only fires
+    /// if it's still the latest.
+    private var generation = 0
+    private var lastModified: Date?
+
+    init(fileURL: URL, debounce: TimeInterval = 2, schedule: Scheduler? = nil, onChange: @escaping () -> Void) {
+        self.fileURL = fileURL
+        self.debounce = debounce
+        self.onChange = onChange
+        let queue = DispatchQueue(label: "FileWatcher")
+        self.queue = queue
+        self.schedule = schedule ?? { delay, work in
+            queue.asyncAfter(deadline: .now() + delay, execute: work)
+        }
+        queue.setSpecific(key: queueKey, value: ())
+    }
+
+    deinit {
+        stop()
+    }
+
+    var isWatching: Bool {
+        onQueue { source != nil }
+    }
+
+    /// Returns false when the directory can't be opened (e.g. it doesn't exist yet).
+    @discardableResult
+    func start() -> Bool {
+        onQueue {
+            stopOnQueue()
+            let descriptor = open(fileURL.deletingLastPathComponent().path, O_EVTONLY)
+            guard descriptor >= 0 else {
+                DebugLogger.shared.log("Cannot watch \(fileURL.deletingLastPathComponent().path)", level: .debug, source: "Watcher")
+                return false
+            }
+
+            lastModified = modificationDate()
+            let source = DispatchSource.makeFileSystemObjectSource(fileDescriptor: descriptor, eventMask: .write, queue: queue)
+            source.setEventHandler { [weak self] in
+                self?.scheduleIfChanged()
+            }
+            source.setCancelHandler {
+                close(descriptor)
+            }
+            self.source = source
+            source.resume()
+            return true
+        }
+    }
+
+    func stop() {
+        onQueue { stopOnQueue() }
+    }
+
+    /// What the directory source calls on each event; tests call it directly
+    /// instead of waiting for one.
+    func directoryDidChange() {
+        onQueue { scheduleIfChanged() }
+    }
+
+    private func stopOnQueue() {
+        generation += 1
+        source?.cancel()
+        source = nil
+    }
+
+    /// Other files in the directory change too; only react when ours did.
+    private func scheduleIfChanged() {
+        let modified = modificationDate()
+        guard modified != lastModified else { return }
+        lastModified = modified
+
+        generation += 1
+        let scheduled = generation
+        schedule(debounce) { [weak self] in
+            guard let self else { return }
+            self.onQueue {
+                guard self.generation == scheduled, self.source != nil else { return }
+                self.onChange()
+            }
+        }
+    }
+
+    /// Runs `body` on `queue`, directly when already there.
+    private func onQueue<T>(_ body: () -> T) -> T {
+        DispatchQueue.getSpecific(key: queueKey) != nil ? body() : queue.sync(execute: body)
+    }
+
+    private func modificationDate() -> Date? {
+        (try? fileURL.resourceValues(forKeys: [.contentModificationDateKey]))?.contentModificationDate
+    }
+}
@@ -0,0 +1,75 @@
+import Foundation
+
+/// Which utilization the menu bar shows as text next to the icon.
+enum MenuBarTitleMetric: String, CaseIterable {
+    case off
+    case fiveHour
+    case sevenDay
+    /// Whichever Claude window is closest to its limit.
+    case worst
+
+    var displayName: String {
+        switch self {
+        case .off: return "Icon only"
+        case .fiveHour: return "5-hour"
+        case .sevenDay: return "Weekly"
+        case .worst: return "Highest"
+        }
+    }
+
+    /// Text such as "5h: 42%", or nil for an icon-only menu bar item,
+    /// including when the chosen window has no data yet.
+    func title(for snapshot: UsageSnapshot?) -> String? {
+        guard let snapshot else { return nil }
+        let entry: (key: MetricKey, metric: UsageMetric)?
+        switch self {
+        case .off:
+            return nil
+        case .fiveHour:
+            entry = snapshot.fiveHour.map { (.fiveHour, $0) }
+        case .sevenDay:
+            entry = snapshot.sevenDay.map { (.sevenDay, $0) }
+        case .worst:
+            // Ties go to the window listed first in `MetricKey`.
+            let metrics = snapshot.claudeMetrics
+            entry = MetricKey.allCases
+                .compactMap { key in metrics[key].map { (key, $0) } }
+                .max { $0.1.percent < $1.1.percent }
+        }
+        guard let entry else { return nil }
+        return "\(Self.shortName(entry.key)): \(Int(entry.metric.clampedPercent.rounded()))%"
+    }
+
+    private static func shortName(_ key: MetricKey) -> String {
+        switch key {
+        case .fiveHour: return "5h"
+        case .sevenDay: return "7d"
+        case .sevenDaySonnet: return "Sonnet"
+        case .sevenDayOpus: return "Opus"
+        }
+    }
+}
+
+/// Hover text for the menu bar item: each Claude window and, when the last
+/// refresh failed, the error.
+enum MenuBarTooltip {
+    static func text(for snapshot: UsageSnapshot?) -> String {
+        guard let snapshot else { return "Claude Usage: no data yet" }
+        let windows: [(String, UsageMetric?)] = [
+            ("5-hour", snapshot.fiveHour),
+            ("Weekly", snapshot.sevenDay),
+            ("Sonnet", snapshot.sevenDaySonnet),
+            ("Opus", snapshot.sevenDayOpus)
+        ]
+        var lines = windows.compactMap { name, metric in
+            metric.map { "\(name): \(Int($0.clampedPercent.rounded()))%" }
+        }
+        if lines.isEmpty {
+            lines.append("Claude Usage: no data yet")
+        }
+        if let error = snapshot.error {
+            lines.append("Last refresh failed: \(error)")
+        }
+        return lines.joined(separator: "\n")
+    }
+}
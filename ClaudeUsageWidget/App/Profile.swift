@@ -0,0 +1,84 @@
+import Foundation
+
+/// A named Claude account to monitor, read from `~/.claude-widget/profiles.json`:
+///
+///     [
+///       { "name": "Work", "keychainService": "Claude Code-credentials-work" },
+///       { "name": "Personal", "keychainAccount": "me", "statsFilePath": "~/.claude-personal/stats-cache.json" }
+///     ]
+///
+/// Omitted fields fall back to what the widget uses without a profile.
+struct Profile: Codable, Equatable, Identifiable {
+    let name: String
+    /// Service of the Keychain item holding the credentials.
+    let keychainService: String?
+    /// Account of the Keychain item; nil means the login user name.
+    let keychainAccount: String?
+    /// Stats cache to read instead of `~/.claude/stats-cache.json`. Transcripts
+    /// and session metadata are read from the same directory.
+    let statsFilePath: String?
+
+    var id: String { name }
+
+    init(name: String, keychainService: String? = nil, keychainAccount: String? = nil, statsFilePath: String? = nil) {
+        self.name = name
+        self.keychainService = keychainService
+        self.keychainAccount = keychainAccount
+        self.statsFilePath = statsFilePath
+    }
+
+    static var defaultURL: URL {
+        FileManager.default.homeDirectoryForCurrentUser.appendingPathComponent(".claude-widget/profiles.json")
+    }
+
+    var statsFileURL: URL? {
+        guard let statsFilePath, !statsFilePath.isEmpty else { return nil }
+        return URL(fileURLWithPath: (statsFilePath as NSString).expandingTildeInPath)
+    }
+
+    /// Empty when the file is missing or malformed. Profiles without a name,
+    /// and repeats of an earlier name, are skipped.
+    static func loadAll(from url: URL = defaultURL) -> [Profile] {
+        guard let data = try? Data(contentsOf: url) else { return [] }
+        let profiles: [Profile]
+        do {
+            profiles = try JSONDecoder().decode([Profile].self, from: data)
+        } catch {
+            DebugLogger.shared.log("Ignoring unreadable profiles file: \(error)", level: .warning, source: "App")
+            return []
+        }
+
+        var seen = Set<String>()
+        return profiles.filter { profile in
+            !profile.name.trimmingCharacters(in: .whitespaces).isEmpty && seen.insert(profile.name).inserted
+        }
+    }
+}
+
+/// Decoded `profiles.json` contents, shared by every `AppSettings` reading the
+/// same file so the active profile's fields don't each decode it again.
+/// Edits are picked up by `reload(from:)`.
+enum ProfileCache {
+    private static let lock = NSLock()
+    private static var profilesByFile: [URL: [Profile]] = [:]
+
+    static func profiles(from url: URL) -> [Profile] {
+        lock.lock()
+        defer { lock.unlock() }
+        if let profiles = profilesByFile[url] {
+            return profiles
+        }
+        let profiles = Profile.loadAll(from: url)
+        profilesByFile[url] = profiles
+        return profiles
+    }
+
+    @discardableResult
+    static func reload(from url: URL) -> [Profile] {
+        let profiles = Profile.loadAll(from: url)
+        lock.lock()
+        profilesByFile[url] = profiles
+        lock.unlock()
+        return profiles
+    }
+}
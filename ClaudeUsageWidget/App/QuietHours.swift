@@ -0,0 +1,71 @@
+import Foundation
+
+/// A daily window in which notifications are held back, e.g. 22:00–07:00.
+/// A window whose end is before its start runs past midnight.
+struct QuietHours: Codable, Equatable {
+    /// Minutes after midnight.
+    let startMinute: Int
+    let endMinute: Int
+    /// Weekdays the window starts on, as `Calendar` numbers them (1 is
+    /// Sunday). Empty means every day.
+    let days: Set<Int>
+
+    init(startMinute: Int, endMinute: Int, days: Set<Int> = []) {
+        self.startMinute = startMinute
+        self.endMinute = endMinute
+        self.days = days
+    }
+
+    /// Nil unless `start` and `end` are `HH:mm` and every day is 1...7.
+    init?(start: String, end: String, days: Set<Int> = []) {
+        guard let startMinute = Self.parseTime(start),
+              let endMinute = Self.parseTime(end),
+              days.allSatisfy({ (1...7).contains($0) }) else {
+            return nil
+        }
+        self.init(startMinute: startMinute, endMinute: endMinute, days: days)
+    }
+
+    var start: String { Self.formatTime(startMinute) }
+    var end: String { Self.formatTime(endMinute) }
+
+    /// True when `date` falls in the window. After midnight in a wrapping
+    /// window, `days` is checked against the day the window started.
+    func contains(_ date: Date, calendar: Calendar) -> Bool {
+        guard startMinute != endMinute else { return false }
+        let components = calendar.dateComponents([.hour, .minute, .weekday], from: date)
+        guard let hour = components.hour, let minute = components.minute, let weekday = components.weekday else {
+            return false
+        }
+        let now = hour * 60 + minute
+
+        if startMinute < endMinute {
+            return now >= startMinute && now < endMinute && appliesOn(weekday)
+        }
+        if now >= startMinute {
+            return appliesOn(weekday)
+        }
+        if now < endMinute {
+            return appliesOn(weekday == 1 ? 7 : weekday - 1)
+        }
+        return false
+    }
+
+    private func appliesOn(_ weekday: Int) -> Bool {
+        days.isEmpty || days.contains(weekday)
+    }
+
+    static func parseTime(_ text: String) -> Int? {
+        let parts = text.trimmingCharacters(in: .whitespaces).split(separator: ":")
+        guard parts.count == 2, parts[1].count == 2,
+              let hour = Int(parts[0]), let minute = Int(parts[1]),
+              (0..<24).contains(hour), (0..<60).contains(minute) else {
+            return nil
+        }
+        return hour * 60 + minute
+    }
+
+    private static func formatTime(_ minutes: Int) -> String {
+        String(format: "%02d:%02d", minutes / 60, minutes % 60)
+    }
+}
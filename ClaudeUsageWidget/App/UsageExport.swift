@@ -0,0 +1,236 @@
+import Foundation
+
+enum ExportFormat: String, CaseIterable {
+    case csv
+    case json
+
+    var displayName: String {
+        rawValue.uppercased()
+    }
+}
+
+enum ExportError: Error, Equatable {
+    case invalidRange
+    case noSnapshot
+    case writeFailed(String)
+}
+
+extension ExportError: LocalizedError {
+    var errorDescription: String? {
+        switch self {
+        case .invalidRange:
+            return "The export range must end on or after its start date."
+        case .noSnapshot:
+            return "There is no usage data to export yet."
+        case .writeFailed(let message):
+            return "Export failed: \(message)"
+        }
+    }
+}
+
+/// One exported day: activity and per-model tokens from the stats cache,
+/// plus the highest utilization recorded in the history database that day.
+struct ExportDay: Equatable {
+    let date: String
+    let messages: Int
+    let sessions: Int
+    let toolCalls: Int
+    let tokensByModel: [String: Int]
+    let peakFiveHourPercent: Double?
+    let peakSevenDayPercent: Double?
+
+    var totalTokens: Int {
+        tokensByModel.values.reduce(0, +)
+    }
+}
+
+/// Writes daily usage for a date range as CSV or JSON, one row at a time so
+/// long ranges don't build the whole file in memory.
+enum UsageExport {
+    /// Days between `start` and `end` (inclusive, by calendar day) that have
+    /// any stats or history, oldest first.
+    static func days(
+        from start: Date,
+        to end: Date,
+        cache: StatsCache?,
+        history: [UsageHistoryPoint],
+        calendar: Calendar = .current
+    ) throws -> [ExportDay] {
+        guard start <= end else { throw ExportError.invalidRange }
+
+        let formatter = StatsService.dayFormatter(calendar: calendar)
+        let firstDay = formatter.string(from: start)
+        let lastDay = formatter.string(from: end)
+        func inRange(_ day: String) -> Bool {
+            formatter.date(from: day) != nil && day >= firstDay && day <= lastDay
+        }
+
+        var activity: [String: DailyActivity] = [:]
+        for day in cache?.dailyActivity ?? [] where inRange(day.date) {
+            activity[day.date] = day
+        }
+        var tokens: [String: [String: Int]] = [:]
+        for day in cache?.dailyModelTokens ?? [] where inRange(day.date) {
+            tokens[day.date, default: [:]].merge(day.tokensByModel, uniquingKeysWith: +)
+        }
+        var peaks: [String: (fiveHour: Double?, sevenDay: Double?)] = [:]
+        for point in history {
+            let day = formatter.string(from: point.recordedAt)
+            guard inRange(day) else { continue }
+            let peak = peaks[day]
+            peaks[day] = (
+                [peak?.fiveHour, point.fiveHourPercent].compactMap { $0 }.max(),
+                [peak?.sevenDay, point.sevenDayPercent].compactMap { $0 }.max()
+            )
+        }
+
+        return Set(activity.keys).union(tokens.keys).union(peaks.keys).sorted().map { day in
+            ExportDay(
+                date: day,
+                messages: activity[day]?.messageCount ?? 0,
+                sessions: activity[day]?.sessionCount ?? 0,
+                toolCalls: activity[day]?.toolCallCount ?? 0,
+                tokensByModel: tokens[day] ?? [:],
+                peakFiveHourPercent: peaks[day]?.fiveHour,
+                peakSevenDayPercent: peaks[day]?.sevenDay
+            )
+        }
+    }
+
+    /// Writes `days` to `url`, replacing any existing file, and returns the
+    /// number of rows written. CSV has one token column per model plus a total.
+    @discardableResult
+    static func write(_ days: [ExportDay], format: ExportFormat, to url: URL) throws -> Int {
+        guard FileManager.default.createFile(atPath: url.path, contents: nil),
+              let handle = try? FileHandle(forWritingTo: url) else {
+            throw ExportError.writeFailed("cannot write to \(url.path)")
+        }
+        defer { try? handle.close() }
+
+        let models = Set(days.flatMap(\.tokensByModel.keys)).sorted()
+        do {
+            switch format {
+            case .csv:
+                let header = ["date", "messages", "sessions", "tool_calls"] + models +
+                    ["total_tokens", "peak_five_hour_percent", "peak_seven_day_percent"]
+                try handle.write(contentsOf: Data((csvLine(header) + "\n").utf8))
+                for day in days {
+                    let fields = [day.date, String(day.messages), String(day.sessions), String(day.toolCalls)] +
+                        models.map { String(day.tokensByModel[$0] ?? 0) } +
+                        [String(day.totalTokens), percentField(day.peakFiveHourPercent), percentField(day.peakSevenDayPercent)]
+                    try handle.write(contentsOf: Data((csvLine(fields) + "\n").utf8))
+                }
+            case .json:
+                let encoder = JSONEncoder()
+                encoder.outputFormatting = [.sortedKeys]
+                try handle.write(contentsOf: Data("[\n".utf8))
+                for (index, day) in days.enumerated() {
+                    let row = try encoder.encode(JSONRow(day))
+                    try handle.write(contentsOf: row)
+                    try handle.write(contentsOf: Data((index == days.count - 1 ? "\n" : ",\n").utf8))
+                }
+                try handle.write(contentsOf: Data("]\n".utf8))
+            }
+        } catch let error as ExportError {
+            throw error
+        } catch {
+            throw ExportError.writeFailed(error.localizedDescription)
+        }
+        return days.count
+    }
+
+    /// Writes `snapshot` as pretty-printed JSON for attaching to bug reports.
+    /// Snapshots hold no credentials, but any of `secrets` or anything shaped
+    /// like a token is masked before writing, just in case. Returns true when
+    /// something had to be masked.
+    @discardableResult
+    static func writeSnapshot(_ snapshot: UsageSnapshot, secrets: [String] = [], to url: URL) throws -> Bool {
+        let encoder = UsageSnapshot.makeEncoder()
+        encoder.outputFormatting = [.prettyPrinted, .sortedKeys]
+        let json: String
+        do {
+            json = String(decoding: try encoder.encode(snapshot), as: UTF8.self)
+        } catch {
+            throw ExportError.writeFailed(error.localizedDescription)
+        }
+
+        let masked = DebugLogger.redact(secrets.filter { !$0.isEmpty }.reduce(json) { text, secret in
+            text.replacingOccurrences(of: secret, with: "<redacted>")
+        })
+        do {
+            try Data((masked + "\n").utf8).write(to: url, options: .atomic)
+        } catch {
+            throw ExportError.writeFailed(error.localizedDescription)
+        }
+        return masked != json
+    }
+
+    static let historyColumns = ["timestamp", "five_hour_percent", "seven_day_percent", "today_tokens", "week_tokens"]
+
+    /// Writes raw history rows as CSV, one line per row handed to `write` by
+    /// `produce`, and returns the number of rows. Timestamps are ISO 8601 UTC.
+    @discardableResult
+    static func writeHistory(to url: URL, _ produce: (_ write: (UsageHistoryPoint) throws -> Void) throws -> Void) throws -> Int {
+        guard FileManager.default.createFile(atPath: url.path, contents: nil),
+              let handle = try? FileHandle(forWritingTo: url) else {
+            throw ExportError.writeFailed("cannot write to \(url.path)")
+        }
+        defer { try? handle.close() }
+
+        let formatter = ISO8601DateFormatter()
+        var rows = 0
+        do {
+            try handle.write(contentsOf: Data((csvLine(historyColumns) + "\n").utf8))
+            try produce { point in
+                let fields = [
+                    formatter.string(from: point.recordedAt),
+                    percentField(point.fiveHourPercent),
+                    percentField(point.sevenDayPercent),
+                    String(point.todayTokens),
+                    point.weekTokens.map(String.init) ?? ""
+                ]
+                try handle.write(contentsOf: Data((csvLine(fields) + "\n").utf8))
+                rows += 1
+            }
+        } catch let error as ExportError {
+            throw error
+        } catch {
+            throw ExportError.writeFailed(error.localizedDescription)
+        }
+        return rows
+    }
+
+    private static func csvLine(_ fields: [String]) -> String {
+        fields.map { field in
+            guard field.contains(where: { $0 == "," || $0 == "\"" || $0 == "\n" }) else { return field }
+            return "\"" + field.replacingOccurrences(of: "\"", with: "\"\"") + "\""
+        }
+        .joined(separator: ",")
+    }
+
+    private static func percentField(_ value: Double?) -> String {
+        value.map { String(format: "%.1f", $0) } ?? ""
+    }
+
+    private struct JSONRow: Encodable {
+        let date: String
+        let messages: Int
+        let sessions: Int
+        let toolCalls: Int
+        let tokensByModel: [String: Int]
+        let totalTokens: Int
+        let peakFiveHourPercent: Double?
+        let peakSevenDayPercent: Double?
+
+        init(_ day: ExportDay) {
+            date = day.date
+            messages = day.messages
+            sessions = day.sessions
+            toolCalls = day.toolCalls
+            tokensByModel = day.tokensByModel
+            totalTokens = day.totalTokens
+            peakFiveHourPercent = day.peakFiveHourPercent
+            peakSevenDayPercent = day.peakSevenDayPercent
+        }
+    }
+}
@@ -1,3 +1,4 @@
+import AppKit
 import Dispatch
 import Foundation
 import WidgetKit
@@ -5,53 +6,121 @@ import WidgetKit
 @MainActor
 final class UsageManager: ObservableObject {
     @Published var snapshot: UsageSnapshot? {
-        didSet {
-            if let percent = snapshot?.maxUsagePercent {
-                iconTier = MenuBarIconTier.from(percent: percent)
-            } else {
-                iconTier = .idle
-            }
-        }
+        didSet { updateIconTier() }
     }
     @Published var iconTier: MenuBarIconTier = .idle
     @Published var isLoading = false
     @Published var paceSettings: PaceSettings = .allEnabled
+    @Published private(set) var credentialSource: CredentialSource?
+    @Published private(set) var availableAccounts: [String] = []
+    @Published private(set) var profiles: [Profile] = []
+    /// Set once the user denies the Keychain prompt; automatic refreshes stop
+    /// reading the Keychain until `retryKeychainAccess()` is called.
+    @Published private(set) var keychainDenied = false
+    /// Set when a manual refresh goes ahead despite a server-mandated
+    /// `Retry-After`; cleared by the next refresh.
+    @Published private(set) var manualRefreshWarning: String?
+    /// Scheduled refreshes are suspended; manual refreshes still work.
+    @Published private(set) var isRefreshPaused = false {
+        didSet { updateIconTier() }
+    }
+    /// `.reduced` while eco mode is stretching the refresh interval.
+    @Published private(set) var refreshCadence: RefreshCadence = .normal
 
     private let keychainService: KeychainServiceProtocol
     private let apiService: APIServiceProtocol
+    private let tokenRefreshService: TokenRefreshServiceProtocol
     private let statsService: StatsServiceProtocol
     private let codexAuthService: CodexAuthServiceProtocol
     private let codexAPIService: CodexAPIServiceProtocol
     private let codexStatsService: StatsServiceProtocol
     private let containerService: SharedContainerServiceProtocol
+    private let historyService: UsageHistoryServiceProtocol
+    private let notifier: UsageNotifierProtocol
+    private let settings: AppSettings
     private let widgetReloader: () -> Void
-    private var cachedToken: String?
+    private let tokenExpiryGrace: TimeInterval
+    private let credentialRetryBackoff: TimeInterval
+    private var cachedCredentials: OAuthCredentials?
+    /// When `cachedCredentials` was read or renewed, for `AppSettings.tokenTTL`.
+    private var credentialsFetchedAt: Date?
+    /// Tokens this app renewed but didn't save back. Kept across account
+    /// switches: it only applies while the store still holds the refresh
+    /// token it replaced.
+    private var unsavedRenewal: UnsavedRenewal?
+    private var backoff = RefreshBackoff()
+    private var lastTransientError: Error?
+    /// The launch-time prune and rollup, which run after `init` returns; kept
+    /// so tests can wait for it.
+    private(set) var launchHistoryMaintenance: Task<Void, Never>?
+    /// Per-window alert bookkeeping: thresholds alerted this reset cycle and
+    /// the last reading, for spotting resets.
+    private var usageAlerts: [MetricKey: UsageAlertState] = [:]
+    /// Highest threshold crossed per window during quiet hours, delivered on
+    /// the first refresh after they end if usage is still above it.
+    private var heldThresholdAlerts: [MetricKey: Double] = [:]
+    /// Recent successful readings per Claude window, oldest first, for burn projections.
+    private var burnSamples: [MetricKey: [UsageSample]] = [:]
+    static let burnSampleLimit = 12
+    /// Day (in the day-boundary zone) daily peaks were last rolled up on.
+    private var lastPeakRollupDay: String?
+    private var credentialFailure: CredentialFailure?
+    private var cachedPlan: CachedPlan?
     private var timer: Timer?
+    private var refreshInterval: TimeInterval = TimeInterval(AppSettings.defaultRefreshInterval)
+    private var isPopoverVisible = false
+    private let isLowPowerMode: () -> Bool
+    /// Times the credential cache against `tokenTTL`.
+    private let now: () -> Date
+    private var powerStateObserver: NSObjectProtocol?
+    private var wakeObserver: NSObjectProtocol?
+    private var lastWake: Date?
+    private var statsWatcher: FileWatcher?
 
     init(
-        keychainService: KeychainServiceProtocol = KeychainService(),
-        apiService: APIServiceProtocol = APIService(),
-        statsService: StatsServiceProtocol = StatsService(),
+        keychainService: KeychainServiceProtocol = KeychainService(settings: AppSettings()),
+        apiService: APIServiceProtocol = APIService(settings: AppSettings()),
+        tokenRefreshService: TokenRefreshServiceProtocol = TokenRefreshService(),
+        statsService: StatsServiceProtocol = StatsService(settings: AppSettings()),
         codexAuthService: CodexAuthServiceProtocol = CodexAuthService(),
         codexAPIService: CodexAPIServiceProtocol = CodexAPIService(),
-        codexStatsService: StatsServiceProtocol = CodexStatsService(),
+        codexStatsService: StatsServiceProtocol = CodexStatsService(settings: AppSettings()),
         containerService: SharedContainerServiceProtocol = SharedContainerService(),
-        widgetReloader: @escaping () -> Void = { WidgetCenter.shared.reloadTimelines(ofKind: "ClaudeUsageWidget") }
+        historyService: UsageHistoryServiceProtocol = UsageHistoryService(),
+        notifier: UsageNotifierProtocol = UsageNotifier(),
+        settings: AppSettings = AppSettings(),
+        widgetReloader: @escaping () -> Void = { WidgetCenter.shared.reloadTimelines(ofKind: "ClaudeUsageWidget") },
+        tokenExpiryGrace: TimeInterval = 60,
+        credentialRetryBackoff: TimeInterval = 300,
+        isLowPowerMode: @escaping () -> Bool = { ProcessInfo.processInfo.isLowPowerModeEnabled },
+        now: @escaping () -> Date = Date.init
     ) {
         self.keychainService = keychainService
         self.apiService = apiService
+        self.tokenRefreshService = tokenRefreshService
         self.statsService = statsService
         self.codexAuthService = codexAuthService
         self.codexAPIService = codexAPIService
         self.codexStatsService = codexStatsService
         self.containerService = containerService
+        self.historyService = historyService
+        self.notifier = notifier
+        self.settings = settings
         self.widgetReloader = widgetReloader
+        self.tokenExpiryGrace = tokenExpiryGrace
+        self.credentialRetryBackoff = credentialRetryBackoff
+        self.isLowPowerMode = isLowPowerMode
+        self.now = now
         self.paceSettings = containerService.readPaceSettings()
         self.snapshot = containerService.readSnapshot()
-        if let percent = snapshot?.maxUsagePercent {
-            self.iconTier = MenuBarIconTier.from(percent: percent)
-        } else {
-            self.iconTier = .idle
+        updateIconTier()
+        if let snapshot, snapshot.isDemo != true {
+            // Levels the cached data already passed were alerted last run, and a
+            // reset while the app wasn't running shows up on the first refresh.
+            updateUsageAlerts(snapshot)
+        }
+        launchHistoryMaintenance = Task { [weak self] in
+            await self?.maintainHistoryAtLaunch()
         }
     }
 
@@ -60,13 +129,143 @@ final class UsageManager: ObservableObject {
         do {
             try containerService.writePaceSettings(settings)
         } catch {
-            DebugLogger.shared.log("PACE WRITE FAILED: \(error)", source: "App")
+            DebugLogger.shared.log("PACE WRITE FAILED: \(error)", level: .error, source: "App")
         }
         widgetReloader()
     }
 
+    /// Called once at launch, before any window is shown, so refreshes run on
+    /// schedule whether or not the popover has ever been opened.
+    func startBackgroundRefresh() {
+        applyNetworkSettings()
+        startWatchingStats(fileURL: settings.statsFileURL)
+        powerStateObserver = NotificationCenter.default.addObserver(
+            forName: .NSProcessInfoPowerStateDidChange, object: nil, queue: .main
+        ) { [weak self] _ in
+            Task { @MainActor [weak self] in
+                self?.updateRefreshCadence()
+            }
+        }
+        wakeObserver = NSWorkspace.shared.notificationCenter.addObserver(
+            forName: NSWorkspace.didWakeNotification, object: nil, queue: .main
+        ) { [weak self] _ in
+            Task { @MainActor [weak self] in
+                await self?.handleWake()
+            }
+        }
+        startTimer(interval: TimeInterval(settings.refreshInterval))
+        Task { await refresh() }
+    }
+
+    /// Wake notifications closer together than this trigger one refresh.
+    static let wakeDebounce: TimeInterval = 30
+
+    /// Refreshes right after the Mac wakes instead of showing hours-old data
+    /// until the next tick. A credential read that failed before sleep (e.g.
+    /// on a locked Keychain) is retried rather than waiting out its backoff.
+    @discardableResult
+    func handleWake(now: Date = Date()) async -> Bool {
+        if let lastWake, now.timeIntervalSince(lastWake) < Self.wakeDebounce {
+            return false
+        }
+        lastWake = now
+        DebugLogger.shared.log("System woke; refreshing", source: "App")
+        credentialFailure = nil
+        if timer != nil {
+            scheduleTimer()
+        }
+        await refresh()
+        return true
+    }
+
+    /// Starts the refresh schedule, or changes its interval. While paused the
+    /// interval is only remembered and takes effect on resume.
     func startTimer(interval: TimeInterval = 300) {
+        refreshInterval = interval
+        guard !isRefreshPaused else { return }
+        scheduleTimer()
+    }
+
+    func stopTimer() {
         timer?.invalidate()
+        timer = nil
+    }
+
+    /// When the next scheduled refresh fires; nil while stopped or paused.
+    var nextScheduledRefresh: Date? {
+        timer?.fireDate
+    }
+
+    /// Re-colors the current snapshot after the warning/critical levels change
+    /// and passes them on to the widget.
+    func applyStatusThresholds() {
+        guard let current = snapshot else { return }
+        let updated = current.withStatusThresholds(settings.statusThresholds)
+        snapshot = updated
+        do {
+            try containerService.writeSnapshot(updated)
+        } catch {
+            DebugLogger.shared.log("WRITE FAILED: \(error)", level: .error, source: "App")
+        }
+        widgetReloader()
+    }
+
+    func updateIconTier() {
+        iconTier = MenuBarIconTier.current(snapshot: snapshot, isPaused: isRefreshPaused, thresholds: settings.iconThresholds)
+    }
+
+    func pauseRefresh() {
+        guard !isRefreshPaused else { return }
+        isRefreshPaused = true
+        stopTimer()
+        DebugLogger.shared.log("Scheduled refresh paused", source: "App")
+    }
+
+    /// Restarts the schedule and refreshes right away, since data may have
+    /// gone stale while paused.
+    func resumeRefresh() async {
+        guard isRefreshPaused else { return }
+        isRefreshPaused = false
+        scheduleTimer()
+        DebugLogger.shared.log("Scheduled refresh resumed (every \(Int(refreshInterval))s)", source: "App")
+        await refresh()
+    }
+
+    /// Eco mode multiplies the interval by this while the popover is closed.
+    static let ecoIntervalMultiplier: TimeInterval = 5
+
+    /// Tracks whether the popover is open. Opening it while eco mode has
+    /// slowed the schedule refreshes at once, so the numbers shown are current.
+    func setPopoverVisible(_ visible: Bool) async {
+        guard visible != isPopoverVisible else { return }
+        isPopoverVisible = visible
+        let wasReduced = refreshCadence == .reduced
+        updateRefreshCadence()
+        if visible, wasReduced {
+            await refresh()
+        }
+    }
+
+    /// Re-evaluates eco mode, e.g. after the setting or power state changes,
+    /// and reschedules a running timer when the cadence changes.
+    func updateRefreshCadence() {
+        let cadence = currentCadence()
+        guard cadence != refreshCadence else { return }
+        refreshCadence = cadence
+        DebugLogger.shared.log("Refresh cadence: \(cadence)", level: .debug, source: "App")
+        if timer != nil {
+            scheduleTimer()
+        }
+    }
+
+    private func currentCadence() -> RefreshCadence {
+        settings.ecoMode && (!isPopoverVisible || isLowPowerMode()) ? .reduced : .normal
+    }
+
+    private func scheduleTimer() {
+        timer?.invalidate()
+        refreshCadence = currentCadence()
+        let interval = refreshCadence == .reduced ? refreshInterval * Self.ecoIntervalMultiplier : refreshInterval
         timer = Timer.scheduledTimer(withTimeInterval: interval, repeats: true) { [weak self] _ in
             Task { @MainActor [weak self] in
                 await self?.refresh()
@@ -74,27 +273,197 @@ final class UsageManager: ObservableObject {
         }
     }
 
-    func stopTimer() {
-        timer?.invalidate()
-        timer = nil
+    /// Recomputes token stats whenever Claude Code rewrites its stats cache,
+    /// so counts don't wait for the next API refresh. If `~/.claude` doesn't
+    /// exist yet, stats are read on refresh as before and each refresh tries
+    /// to start the watcher again.
+    func startWatchingStats(fileURL: URL = StatsService.defaultStatsFileURL, debounce: TimeInterval = 2) {
+        let watcher = FileWatcher(fileURL: fileURL, debounce: debounce) { [weak self] in
+            Task { @MainActor [weak self] in
+                await self?.refreshTokenStats()
+            }
+        }
+        if !watcher.start() {
+            DebugLogger.shared.log("Stats watcher unavailable; reading stats on refresh", level: .debug, source: "App")
+        }
+        statsWatcher = watcher
+    }
+
+    var isWatchingStats: Bool {
+        statsWatcher?.isWatching ?? false
+    }
+
+    func stopWatchingStats() {
+        statsWatcher?.stop()
+        statsWatcher = nil
+    }
+
+    /// Re-reads local token stats into the current snapshot without calling the API.
+    func refreshTokenStats() async {
+        let statsService = UnsafeSendableBox(value: self.statsService)
+        let stats = await runBlocking {
+            statsService.value.readStats()
+        }
+        guard let current = snapshot, current.isDemo != true, current.tokenStats != stats else { return }
+
+        let updated = current.withTokenStats(stats)
+        snapshot = updated
+        do {
+            try containerService.writeSnapshot(updated)
+        } catch {
+            DebugLogger.shared.log("WRITE FAILED: \(error)", level: .error, source: "App")
+        }
+        widgetReloader()
+        DebugLogger.shared.log("Token stats updated from file change: todayTokens=\(stats.todayTokens)", level: .debug, source: "App")
+    }
+
+    /// Drops everything cached for the current account so the next refresh
+    /// starts from a fresh credential read.
+    func resetAuthState() {
+        cachedCredentials = nil
+        cachedPlan = nil
+        credentialSource = nil
+        backoff.reset()
+        lastTransientError = nil
+        credentialFailure = nil
+        keychainDenied = false
+    }
+
+    /// Resets auth state and reads credentials again right away.
+    /// Returns whether a token was found.
+    @discardableResult
+    func reloadCredentials() async -> Bool {
+        resetAuthState()
+        do {
+            _ = try await loadCredentials()
+            DebugLogger.shared.log("Credentials reloaded", source: "App")
+            return true
+        } catch {
+            DebugLogger.shared.log("Credential reload failed: \(describeError(error))", level: .warning, source: "App")
+            return false
+        }
+    }
+
+    /// Called from the "Grant access" button: prompts for Keychain access again
+    /// and refetches if a token is now readable.
+    @discardableResult
+    func retryKeychainAccess() async -> Bool {
+        let found = await reloadCredentials()
+        if found {
+            await refresh(userInitiated: true)
+        }
+        return found
     }
 
-    func refresh() async {
+    var selectedAccount: String? {
+        settings.selectedAccount
+    }
+
+    func loadAccounts() async {
+        let keychainService = UnsafeSendableBox(value: self.keychainService)
+        availableAccounts = await runBlocking {
+            keychainService.value.listAccounts()
+        }
+    }
+
+    /// Switches the Keychain account to monitor and refetches with its token.
+    /// The previous account's usage is dropped first, so a failed fetch can't
+    /// leave it on screen as last-known data for the new account.
+    func selectAccount(_ account: String?) async {
+        guard account != settings.selectedAccount else { return }
+        settings.selectedAccount = account
+        await switchCredentials()
+    }
+
+    var activeProfileName: String? {
+        settings.activeProfile?.name
+    }
+
+    func loadProfiles() {
+        profiles = settings.reloadProfiles()
+    }
+
+    /// Switches to the named profile's credentials and stats (nil for the
+    /// defaults), dropping the cached token and the previous usage.
+    func setActiveProfile(_ name: String?) async throws {
+        guard name != activeProfileName else { return }
+        try settings.setActiveProfile(name)
+        if statsWatcher != nil {
+            startWatchingStats(fileURL: settings.statsFileURL)
+        }
+        await switchCredentials()
+    }
+
+    /// Saves the stats cache path from Settings (empty for the default) and
+    /// re-reads token stats from it.
+    func applyStatsCachePath(_ path: String) async {
+        settings.statsCachePath = path
+        if statsWatcher != nil {
+            startWatchingStats(fileURL: settings.statsFileURL)
+        }
+        await refreshTokenStats()
+    }
+
+    /// Applies a new token source or token file right away: the next read
+    /// uses it, and the previous source's usage isn't kept as last-known data.
+    func applyCredentialSettings() async {
+        DebugLogger.shared.log("Token source changed to \(settings.tokenSource.displayName)", source: "App")
+        await switchCredentials()
+    }
+
+    private func switchCredentials() async {
+        resetAuthState()
+        burnSamples = [:]
+        if let cleared = snapshot?.withoutClaudeUsage() {
+            snapshot = cleared
+            do {
+                try containerService.writeSnapshot(cleared)
+            } catch {
+                DebugLogger.shared.log("WRITE FAILED: \(error)", level: .error, source: "App")
+            }
+        }
+        await refresh()
+    }
+
+    /// `userInitiated` refreshes skip the credential-read backoff and the
+    /// refresh-cycle backoff so a manual refresh always retries. Overriding a
+    /// server-mandated `Retry-After` sets `manualRefreshWarning`.
+    func refresh(userInitiated: Bool = false) async {
         let debug = DebugLogger.shared
-        debug.log("refresh() started", source: "App")
+        debug.log("refresh() started", level: .debug, source: "App")
+        manualRefreshWarning = nil
+        if userInitiated {
+            credentialFailure = nil
+            // Count the next scheduled refresh from now, not from the last tick.
+            if timer != nil {
+                scheduleTimer()
+            }
+        }
+        if settings.isDemoMode() {
+            showDemoUsage()
+            return
+        }
         debug.dumpContainerDiagnostics(source: "App-refresh")
+        if let statsWatcher, !statsWatcher.isWatching, statsWatcher.start() {
+            debug.log("Stats watcher started", level: .debug, source: "App")
+        }
 
         isLoading = true
         defer { isLoading = false }
 
-        let prepared = await loadRefreshPreparation()
-        let existing = prepared.existing
+        // Local stats are read off the main thread while the usage request is
+        // in flight, so a slow disk and a slow network don't add up.
+        async let preparation = loadRefreshPreparation()
+        let claudeOutcome = await fetchClaude(userInitiated: userInitiated)
+        let prepared = await preparation
+        // Demo numbers are never kept as last-known data for a real refresh.
+        let existing = prepared.existing?.isDemo == true ? nil : prepared.existing
         let claudeStats = prepared.claudeStats
         let codexStats = prepared.codexStats
-        debug.log("Stats: todayTokens=\(claudeStats.todayTokens), weekTokens=\(claudeStats.weekTokens)", source: "App")
-        debug.log("Codex stats: todayTokens=\(codexStats.todayTokens), weekTokens=\(codexStats.weekTokens)", source: "App")
+        debug.log("Stats: todayTokens=\(claudeStats.todayTokens), weekTokens=\(claudeStats.weekTokens)", level: .debug, source: "App")
+        debug.log("Codex stats: todayTokens=\(codexStats.todayTokens), weekTokens=\(codexStats.weekTokens)", level: .debug, source: "App")
 
-        let claudeResult = await refreshClaude(existing: existing, stats: claudeStats)
+        let claudeResult = makeClaudeResult(claudeOutcome, existing: existing, stats: claudeStats)
         let codexResult = await refreshCodex(existing: existing?.codex, stats: codexStats)
 
         let mergedSnapshot = mergeSnapshots(claude: claudeResult.snapshot, codex: codexResult.snapshot)
@@ -103,18 +472,408 @@ final class UsageManager: ObservableObject {
         if claudeResult.shouldPersist || codexResult.shouldPersist {
             do {
                 try containerService.writeSnapshot(mergedSnapshot)
-                debug.log("Snapshot written to shared container", source: "App")
+                debug.log("Snapshot written to shared container", level: .debug, source: "App")
             } catch {
-                debug.log("WRITE FAILED: \(error)", source: "App")
+                debug.log("WRITE FAILED: \(error)", level: .error, source: "App")
             }
             widgetReloader()
-            debug.log("Widget reload requested", source: "App")
+            debug.log("Widget reload requested", level: .debug, source: "App")
+        }
+
+        if claudeResult.snapshot.error == nil {
+            notifyUsageAlerts(claudeResult.snapshot)
+            await recordHistory(mergedSnapshot)
+        }
+    }
+
+    /// Shows made-up usage without reading credentials or calling the API.
+    /// Alerts and history are skipped so demo numbers never mix with real ones.
+    private func showDemoUsage() {
+        let demo = DemoUsage.snapshot().withStatusThresholds(settings.statusThresholds)
+        snapshot = demo
+        do {
+            try containerService.writeSnapshot(demo)
+        } catch {
+            DebugLogger.shared.log("WRITE FAILED: \(error)", level: .error, source: "App")
+        }
+        widgetReloader()
+        DebugLogger.shared.log("Demo mode: showing made-up usage", level: .debug, source: "App")
+    }
+
+    /// The raw usage response, without reading local stats, fetching the plan
+    /// or touching the snapshot. A rejected token is dropped from the cache
+    /// (after the 401 handling in `fetchUsageHandlingAuth`) so the next call
+    /// re-reads credentials.
+    func fetchUsageOnly() async throws -> UsageApiResponse {
+        let token: String
+        if let cached = validCachedToken() {
+            token = cached
+        } else {
+            token = try await loadCredentials().accessToken
+        }
+        do {
+            return try await fetchUsageHandlingAuth(token: token).response
+        } catch {
+            if UsageErrorKind(error) == .auth { cachedCredentials = nil }
+            DebugLogger.shared.log("Usage-only fetch failed: \(describeError(error))", level: .warning, source: "App")
+            throw error
+        }
+    }
+
+    /// Snapshots recorded between `start` and `end`, oldest first.
+    func usageHistory(from start: Date, to end: Date) async -> [UsageSnapshot] {
+        let historyService = UnsafeSendableBox(value: self.historyService)
+        do {
+            return try await runBlockingThrowing {
+                try historyService.value.snapshots(from: start, to: end)
+            }
+        } catch {
+            DebugLogger.shared.log("History query failed: \(error)", level: .error, source: "App")
+            return []
+        }
+    }
+
+    /// Peak usage over the last `window` seconds in `resolution`-second
+    /// buckets, oldest first, for charting.
+    func usageSeries(window: TimeInterval, resolution: TimeInterval) async -> [UsageHistoryPoint] {
+        let historyService = UnsafeSendableBox(value: self.historyService)
+        let end = Date()
+        do {
+            return try await runBlockingThrowing {
+                try historyService.value.series(from: end.addingTimeInterval(-window), to: end, resolution: resolution)
+            }
+        } catch {
+            DebugLogger.shared.log("History query failed: \(error)", level: .error, source: "App")
+            return []
+        }
+    }
+
+    /// Validates and stores a proxy URL (empty clears it) and its password,
+    /// then rebuilds the shared network session so the next refresh goes
+    /// through it.
+    func applyProxy(_ urlString: String, password: String? = nil) throws {
+        try settings.setProxyURL(urlString, password: password)
+        applyNetworkSettings()
+    }
+
+    /// Validates and stores an API gateway base URL (empty restores the
+    /// default). The API service reads it per request, so no rebuild is needed.
+    func applyAPIBaseURL(_ urlString: String) throws {
+        try settings.setAPIBaseURL(urlString)
+    }
+
+    /// Validates and stores the zone whose midnight starts "today", then
+    /// recomputes the token totals against the new boundary.
+    func applyDayBoundaryTimeZone(_ name: String) async throws {
+        try settings.setDayBoundaryTimeZone(name)
+        await refreshTokenStats()
+    }
+
+    func applyNetworkSettings() {
+        do {
+            SharedSession.shared.reconfigure(proxy: try settings.proxyConfiguration(), requestTimeout: settings.requestTimeout)
+        } catch let error as ProxyError {
+            SharedSession.shared.reject(error)
+        } catch {
+            DebugLogger.shared.log("Proxy configuration failed: \(error)", level: .warning, source: "App")
+        }
+    }
+
+    /// Daily Claude token totals for the last `days` days, oldest first, with
+    /// zero-filled gaps.
+    func tokenHistory(days: Int) async -> [DailyTokens] {
+        let statsService = UnsafeSendableBox(value: self.statsService)
+        return await runBlocking {
+            statsService.value.tokenHistory(days: days)
+        }
+    }
+
+    /// Claude token usage per project for today and the week, busiest first.
+    func projectStats() async -> [ProjectUsage] {
+        let statsService = UnsafeSendableBox(value: self.statsService)
+        return await runBlocking {
+            statsService.value.projectStats()
         }
     }
 
-    private func handleError(_ msg: String, stats: TokenStats, source: String, existing: UsageSnapshot?) -> ClaudeRefreshResult {
+    /// Writes daily Claude usage between `start` and `end` to `url` and
+    /// returns the number of rows. Utilization peaks come from the history
+    /// database when it has any.
+    func exportUsage(format: ExportFormat, from start: Date, to end: Date, to url: URL) async throws -> Int {
+        guard start <= end else { throw ExportError.invalidRange }
+        let statsService = UnsafeSendableBox(value: self.statsService)
+        let historyService = UnsafeSendableBox(value: self.historyService)
+        let calendar = StatsService.calendar(in: settings.dayBoundaryTimeZone)
+        let rangeEnd = StatsService.endOfDay(now: end, calendar: calendar)
+
+        let rows = try await runBlockingThrowing {
+            let history: [UsageHistoryPoint]
+            do {
+                history = try historyService.value.series(
+                    from: calendar.startOfDay(for: start),
+                    to: rangeEnd,
+                    resolution: 3600
+                )
+            } catch {
+                DebugLogger.shared.log("Export skipped history: \(error)", level: .warning, source: "App")
+                history = []
+            }
+            let days = try UsageExport.days(
+                from: start,
+                to: end,
+                cache: statsService.value.statsCache(),
+                history: history,
+                calendar: calendar
+            )
+            return try UsageExport.write(days, format: format, to: url)
+        }
+        DebugLogger.shared.log("Exported \(rows) days to \(url.lastPathComponent)", source: "App")
+        return rows
+    }
+
+    /// Writes the current snapshot as JSON to `url`, masking the cached
+    /// credentials should they ever appear in it.
+    func exportSnapshot(to url: URL) async throws {
+        guard let snapshot else { throw ExportError.noSnapshot }
+        let secrets = [cachedCredentials?.accessToken, cachedCredentials?.refreshToken].compactMap { $0 }
+        let masked = try await runBlockingThrowing {
+            try UsageExport.writeSnapshot(snapshot, secrets: secrets, to: url)
+        }
+        if masked {
+            DebugLogger.shared.log("Masked credential-like text in exported snapshot", level: .warning, source: "App")
+        }
+        DebugLogger.shared.log("Exported snapshot to \(url.lastPathComponent)", source: "App")
+    }
+
+    /// Writes every history row recorded between the start of `start`'s day
+    /// and the end of `end`'s day to a CSV at `url`; returns the row count.
+    func exportHistory(from start: Date, to end: Date, to url: URL) async throws -> Int {
+        guard start <= end else { throw ExportError.invalidRange }
+        let historyService = UnsafeSendableBox(value: self.historyService)
+        let calendar = StatsService.calendar(in: settings.dayBoundaryTimeZone)
+        let rangeStart = calendar.startOfDay(for: start)
+        let rangeEnd = StatsService.endOfDay(now: end, calendar: calendar)
+
+        let rows = try await runBlockingThrowing {
+            try UsageExport.writeHistory(to: url) { write in
+                try historyService.value.forEachPoint(from: rangeStart, to: rangeEnd, write)
+            }
+        }
+        DebugLogger.shared.log("Exported \(rows) history rows to \(url.lastPathComponent)", source: "App")
+        return rows
+    }
+
+    /// Reads credentials without caching them or calling the API, so a
+    /// Keychain or file problem can be told apart from a network one. Only a
+    /// summary leaves the background read; the token itself is dropped there.
+    func checkCredentials() async -> DiagnosticCheck {
+        let keychainService = UnsafeSendableBox(value: self.keychainService)
+        do {
+            let summary = try await runBlockingThrowing {
+                Diagnostics.credentialsSummary(try keychainService.value.readCredentials())
+            }
+            return DiagnosticCheck(name: Diagnostics.credentialsCheck, passed: true, message: summary)
+        } catch {
+            if case KeychainError.accessDenied = error { keychainDenied = true }
+            return DiagnosticCheck(name: Diagnostics.credentialsCheck, passed: false, message: describeError(error))
+        }
+    }
+
+    /// Checks each dependency of a refresh in turn. The credentials check
+    /// reports only where a token was found, never the token itself, and
+    /// doesn't replace the credentials the next refresh uses. The stats cache
+    /// checked is the one refreshes read unless `statsFileURL` is given.
+    func runDiagnostics(
+        homeDirectory: URL = FileManager.default.homeDirectoryForCurrentUser,
+        statsFileURL: URL? = nil
+    ) async -> DiagnosticsReport {
+        let statsFileURL = statsFileURL ?? settings.statsFileURL
+        let statsCheck = await runBlocking { Diagnostics.checkStatsCache(at: statsFileURL) }
+        var checks = [Diagnostics.checkHomeDirectory(homeDirectory), statsCheck]
+
+        checks.append(await checkCredentials())
+
+        do {
+            let status = try await apiService.probeReachability()
+            checks.append(DiagnosticCheck(name: Diagnostics.apiReachabilityCheck, passed: true, message: "HTTP \(status)"))
+        } catch {
+            checks.append(DiagnosticCheck(name: Diagnostics.apiReachabilityCheck, passed: false, message: describeError(error)))
+        }
+
+        let report = DiagnosticsReport(checks: checks, generatedAt: Date())
+        DebugLogger.shared.log(
+            "Diagnostics:\n\(report.summary)",
+            level: report.allPassed ? .info : .warning,
+            source: "App"
+        )
+        return report
+    }
+
+    /// Alerts once per threshold per reset cycle for each window in
+    /// `notifyWindows`. Sitting above a level doesn't alert again; a new
+    /// `resetsAt`, or falling well below the level, re-arms it. Windows in
+    /// `notifyResetWindows` also alert when they reset. Inside quiet hours
+    /// alerts are only logged; threshold alerts are held until they end.
+    private func notifyUsageAlerts(_ snapshot: UsageSnapshot, now: Date = Date()) {
+        let update = updateUsageAlerts(snapshot, now: now)
+        var crossings = update.crossings
+        let resets = update.resets
+        let quiet = settings.quietHours?.contains(now, calendar: StatsService.calendar(in: settings.dayBoundaryTimeZone)) ?? false
+        if quiet {
+            for (key, threshold) in crossings where settings.notifyWindows.contains(key) {
+                heldThresholdAlerts[key] = max(heldThresholdAlerts[key] ?? 0, threshold)
+                DebugLogger.shared.log("Threshold notification held for quiet hours (\(key.rawValue) \(Int(threshold))%)", source: "App")
+            }
+            for key in resets where settings.notifyResetWindows.contains(key) {
+                DebugLogger.shared.log("Reset notification suppressed in quiet hours (\(key.rawValue))", source: "App")
+            }
+            return
+        }
+
+        for (key, threshold) in heldThresholdAlerts {
+            // Only still relevant while usage stays above the level in the same cycle.
+            guard let metric = snapshot.claudeMetrics[key], metric.percent >= threshold,
+                  usageAlerts[key]?.notified.contains(threshold) == true else { continue }
+            crossings[key] = max(crossings[key] ?? 0, threshold)
+        }
+        heldThresholdAlerts = [:]
+
+        let resetWindows = settings.notifyResetWindows
+        for key in resets.sorted(by: { $0.rawValue < $1.rawValue }) where resetWindows.contains(key) {
+            guard let metric = snapshot.claudeMetrics[key] else { continue }
+            notifier.post(
+                title: "Your \(Self.alertName(for: key)) has reset",
+                body: "Usage is back to \(Int(metric.percent))%.",
+                identifier: "reset-\(key.rawValue)"
+            )
+            DebugLogger.shared.log("Reset notification sent (\(key.rawValue))", source: "App")
+        }
+
+        let windows = settings.notifyWindows
+        for (key, threshold) in crossings.sorted(by: { $0.key.rawValue < $1.key.rawValue }) where windows.contains(key) {
+            guard let metric = snapshot.claudeMetrics[key] else { continue }
+            let resetTime = metric.resetsAt.formatted(date: .omitted, time: .shortened)
+            let resetSentence = metric.hasResetTime ? " It resets at \(resetTime)." : ""
+            notifier.post(
+                title: "Claude usage at \(Int(metric.percent))%",
+                body: "Your \(Self.alertName(for: key)) passed \(Int(threshold))%.\(resetSentence)",
+                identifier: "threshold-\(key.rawValue)"
+            )
+            DebugLogger.shared.log("Threshold notification sent (\(key.rawValue) \(Int(threshold))%)", source: "App")
+        }
+    }
+
+    /// Records newly passed thresholds and resets; returns the highest
+    /// threshold crossed per window and the windows that reset.
+    @discardableResult
+    private func updateUsageAlerts(_ snapshot: UsageSnapshot, now: Date = Date()) -> (crossings: [MetricKey: Double], resets: Set<MetricKey>) {
+        let thresholds = settings.notifyThresholds
+        var crossings: [MetricKey: Double] = [:]
+        var resets = Set<MetricKey>()
+        for (key, metric) in snapshot.claudeMetrics {
+            var state = usageAlerts[key] ?? UsageAlertState(resetsAt: metric.resetsAt)
+            if state.detectReset(metric: metric, now: now) {
+                resets.insert(key)
+            }
+            let crossed = state.update(metric: metric, thresholds: thresholds)
+            usageAlerts[key] = state
+            crossings[key] = crossed.max()
+        }
+        return (crossings, resets)
+    }
+
+    private static func alertName(for key: MetricKey) -> String {
+        switch key {
+        case .fiveHour: return "5-hour limit"
+        case .sevenDay: return "weekly limit"
+        case .sevenDaySonnet: return "weekly Sonnet limit"
+        case .sevenDayOpus: return "weekly Opus limit"
+        }
+    }
+
+    /// Posts a sample alert so the user can check notification permissions.
+    func sendTestNotification() {
+        notifier.post(
+            title: "Claude usage at 80%",
+            body: "This is a test alert. Real alerts name the window and when it resets.",
+            identifier: "threshold-test"
+        )
+    }
+
+    private func recordHistory(_ snapshot: UsageSnapshot) async {
+        let historyService = UnsafeSendableBox(value: self.historyService)
+        let snapshot = UnsafeSendableBox(value: snapshot)
+        do {
+            try await runBlockingThrowing {
+                try historyService.value.record(snapshot.value)
+            }
+        } catch {
+            DebugLogger.shared.log("History write failed: \(error)", level: .error, source: "App")
+        }
+        await rollUpDailyPeaks(now: Date())
+    }
+
+    /// Rolls finished days into daily peaks at most once per day; the query
+    /// only touches rows after the last rolled-up day, so it stays cheap.
+    private func rollUpDailyPeaks(now: Date) async {
+        let calendar = StatsService.calendar(in: settings.dayBoundaryTimeZone)
+        let today = StatsService.dayFormatter(calendar: calendar).string(from: now)
+        guard today != lastPeakRollupDay else { return }
+        // Claimed before the query so a refresh finishing meanwhile doesn't start a second one.
+        let previousDay = lastPeakRollupDay
+        lastPeakRollupDay = today
+        let historyService = UnsafeSendableBox(value: self.historyService)
+        do {
+            let days = try await runBlockingThrowing {
+                try historyService.value.rollUpDailyPeaks(now: now, calendar: calendar)
+            }
+            if days > 0 {
+                DebugLogger.shared.log("Rolled up daily peaks for \(days) day(s)", source: "App")
+            }
+        } catch {
+            lastPeakRollupDay = previousDay
+            DebugLogger.shared.log("Daily peak rollup failed: \(error)", level: .error, source: "App")
+        }
+    }
+
+    /// Peak utilization for each of the last `days` days that had data, oldest first.
+    func dailyPeaks(days: Int) async -> [DailyPeak] {
+        let historyService = UnsafeSendableBox(value: self.historyService)
+        do {
+            return try await runBlockingThrowing {
+                try historyService.value.dailyPeaks(days: days)
+            }
+        } catch {
+            DebugLogger.shared.log("Daily peaks query failed: \(error)", level: .error, source: "App")
+            return []
+        }
+    }
+
+    /// Drops history past the retention window, then rolls up any days
+    /// finished since the last run.
+    private func maintainHistoryAtLaunch() async {
+        let historyService = UnsafeSendableBox(value: self.historyService)
+        let cutoff = Date().addingTimeInterval(-settings.historyRetention)
+        do {
+            try await runBlockingThrowing {
+                try historyService.value.prune(olderThan: cutoff)
+            }
+        } catch {
+            DebugLogger.shared.log("History prune failed: \(error)", level: .error, source: "App")
+        }
+        await rollUpDailyPeaks(now: Date())
+    }
+
+    private func handleError(
+        _ msg: String,
+        kind: UsageErrorKind,
+        stats: TokenStats,
+        source: String,
+        existing: UsageSnapshot?,
+        nextRetryAt: Date? = nil
+    ) -> ClaudeRefreshResult {
         if let existing, existing.hasUsageData {
-            return ClaudeRefreshResult(snapshot: existing.withError(msg, tokenStats: stats), shouldPersist: true)
+            return ClaudeRefreshResult(snapshot: existing.withError(msg, kind: kind, tokenStats: stats, nextRetryAt: nextRetryAt), shouldPersist: true)
         } else {
             return ClaudeRefreshResult(snapshot: UsageSnapshot(
                 fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil,
@@ -122,7 +881,9 @@ final class UsageManager: ObservableObject {
                 tokenStats: stats,
                 lastUpdated: Date(),
                 lastSuccessfulUpdate: nil,
-                error: msg
+                error: msg,
+                errorKind: kind,
+                nextRetryAt: nextRetryAt
             ), shouldPersist: false)
         }
     }
@@ -130,24 +891,42 @@ final class UsageManager: ObservableObject {
     private func describeError(_ error: Error) -> String {
         switch error {
         case KeychainError.notFound:
-            return "No credentials found. Please sign in to Claude Code first."
+            return "No Claude Code credentials found in the Keychain. Please sign in to Claude Code first."
+        case KeychainError.noCredentials(let searched):
+            return "No credentials found. Checked: \(searched.joined(separator: ", ")). Please sign in to Claude Code first."
         case KeychainError.accessDenied:
-            return "Keychain access denied. Please allow access when prompted."
+            return "Keychain access denied. Click Grant access to be asked again."
+        case KeychainError.needsPermission:
+            return "Keychain access needs permission. Unlock your Mac and click Grant access."
         case KeychainError.invalidData(let msg):
             return "Invalid credentials: \(msg)"
         case APIError.unauthorized:
             return "Authentication failed. Token may have expired."
         case APIError.forbidden:
             return "Access forbidden."
+        case APIError.rateLimited(let retryAfter):
+            guard let retryAfter else { return "Rate limited. Will retry later." }
+            return "Rate limited. Retrying in \(Self.formatWait(retryAfter))."
         case APIError.serverError(let code):
             return "Server error (\(code))."
         case APIError.networkError(let msg):
             return "Network error: \(msg)"
+        case APIError.invalidProxy(let msg):
+            return "\(msg) Requests are paused until it's fixed in Settings or in HTTPS_PROXY / ALL_PROXY."
+        case APIError.invalidBaseURL(let value):
+            return "Invalid API base URL \"\(value)\". Fix it in Settings."
         default:
             return "Error: \(error.localizedDescription)"
         }
     }
 
+    private static func formatWait(_ seconds: TimeInterval) -> String {
+        if seconds < 60 {
+            return "\(Int(seconds.rounded(.up)))s"
+        }
+        return "\(Int((seconds / 60).rounded(.up))) min"
+    }
+
     private func describeCodexError(_ error: Error) -> String {
         switch error {
         case CodexAuthError.notConfigured:
@@ -160,6 +939,8 @@ final class UsageManager: ObservableObject {
             return "Codex access forbidden."
         case APIError.serverError(let code):
             return "Codex server error (\(code))."
+        case APIError.rateLimited:
+            return "Codex rate limited."
         case APIError.networkError(let msg):
             return "Codex network error: \(msg)"
         default:
@@ -167,40 +948,210 @@ final class UsageManager: ObservableObject {
         }
     }
 
-    private func refreshClaude(existing: UsageSnapshot?, stats: TokenStats) async -> ClaudeRefreshResult {
+    /// Fetches Claude usage without touching local stats, so `refresh()` can
+    /// run it alongside the stats read.
+    private func fetchClaude(userInitiated: Bool) async -> ClaudeFetchOutcome {
         let debug = DebugLogger.shared
 
         let token: String
         do {
-            if let cached = cachedToken {
+            if let cached = validCachedToken() {
                 token = cached
-                debug.log("Using cached token (\(token.prefix(8))...)", source: "App")
+                debug.log("Using cached token", level: .debug, source: "App")
             } else {
-                let keychainService = UnsafeSendableBox(value: self.keychainService)
-                token = try await runBlockingThrowing {
-                    try keychainService.value.readToken()
+                if keychainDenied {
+                    return .failure(describeError(KeychainError.accessDenied), kind: .auth, source: "token")
+                }
+                if let credentialFailure, Date().timeIntervalSince(credentialFailure.date) < credentialRetryBackoff {
+                    debug.log("Skipping credential read: last attempt failed \(Int(Date().timeIntervalSince(credentialFailure.date)))s ago", level: .warning, source: "App")
+                    return .failure(credentialFailure.message, kind: .auth, source: "token")
+                }
+                if cachedCredentials != nil {
+                    debug.log("Cached token expired, expiring soon or past its TTL — re-reading keychain", source: "App")
                 }
-                cachedToken = token
-                debug.log("Read token from keychain (\(token.prefix(8))...)", source: "App")
+                let credentials = try await loadCredentials()
+                token = credentials.accessToken
+                debug.log("Read token from \(credentials.source?.displayName ?? "keychain")", source: "App")
             }
         } catch {
             let msg = describeError(error)
-            debug.log("Token error: \(msg)", source: "App")
-            return handleError(msg, stats: stats, source: "token", existing: existing)
+            credentialFailure = CredentialFailure(date: Date(), message: msg)
+            debug.log("Token error: \(msg)", level: .error, source: "App")
+            return .failure(msg, kind: UsageErrorKind(error), source: "token")
+        }
+
+        if backoff.isBackingOff(), let retryAt = backoff.retryAt, userInitiated, backoff.isServerMandated {
+            let until = retryAt.formatted(date: .omitted, time: .shortened)
+            manualRefreshWarning = "Rate limited until \(until). Refreshing early may extend the limit."
+            debug.log("Manual refresh overriding Retry-After (until \(until))", level: .warning, source: "App")
+        }
+        if backoff.isBackingOff(), let retryAt = backoff.retryAt, !userInitiated {
+            let error = backoff.isServerMandated
+                ? APIError.rateLimited(retryAfter: retryAt.timeIntervalSinceNow)
+                : lastTransientError ?? APIError.networkError("Unavailable")
+            let msg = describeError(error)
+            debug.log("Skipping API call (\(backoff.consecutiveFailures) consecutive failures): \(msg)", level: .warning, source: "App")
+            return .failure(msg, kind: UsageErrorKind(error), source: "API", nextRetryAt: retryAt)
         }
 
         do {
-            let response = try await apiService.fetchUsage(token: token)
-            let newSnapshot = response.toSnapshot(tokenStats: stats)
-            debug.log("API success: fiveHour=\(newSnapshot.fiveHour?.percent ?? -1)%, sevenDay=\(newSnapshot.sevenDay?.percent ?? -1)%", source: "App")
-            return ClaudeRefreshResult(snapshot: newSnapshot, shouldPersist: true)
+            // A click on Refresh wants the API's current numbers, not a reused response.
+            let (response, usedToken) = try await fetchUsageHandlingAuth(token: token, allowingCached: !userInitiated)
+            let plan = await planInfo(token: usedToken, existing: snapshot?.plan)
+            backoff.reset()
+            lastTransientError = nil
+            return .success(response, plan: plan)
         } catch {
-            if case APIError.unauthorized = error { cachedToken = nil }
-            if case APIError.forbidden = error { cachedToken = nil }
+            let kind = UsageErrorKind(error)
+            if kind == .auth { cachedCredentials = nil }
+            backoff.recordFailure(error)
+            if RefreshBackoff.isTransient(error) { lastTransientError = error }
 
             let msg = describeError(error)
-            debug.log("API error: \(msg)", source: "App")
-            return handleError(msg, stats: stats, source: "API", existing: existing)
+            debug.log("API error: \(msg)", level: .error, source: "App")
+            return .failure(msg, kind: kind, source: "API", nextRetryAt: backoff.retryAt)
+        }
+    }
+
+    private func makeClaudeResult(_ outcome: ClaudeFetchOutcome, existing: UsageSnapshot?, stats: TokenStats) -> ClaudeRefreshResult {
+        switch outcome {
+        case let .success(response, plan):
+            let newSnapshot = withBurnProjections(response.toSnapshot(tokenStats: stats, plan: plan))
+            DebugLogger.shared.log("API success: fiveHour=\(newSnapshot.fiveHour?.percent ?? -1)%, sevenDay=\(newSnapshot.sevenDay?.percent ?? -1)%", source: "App")
+            return ClaudeRefreshResult(snapshot: newSnapshot, shouldPersist: true)
+        case let .failure(msg, kind, source, nextRetryAt):
+            return handleError(msg, kind: kind, stats: stats, source: source, existing: existing, nextRetryAt: nextRetryAt)
+        }
+    }
+
+    /// Adds the snapshot's readings to `burnSamples` and projects each window from them.
+    private func withBurnProjections(_ snapshot: UsageSnapshot) -> UsageSnapshot {
+        var projections: [MetricKey: BurnProjection] = [:]
+        for (key, metric) in snapshot.claudeMetrics {
+            let samples = Array(((burnSamples[key] ?? []) + [UsageSample(date: snapshot.lastUpdated, percent: metric.percent)])
+                .suffix(Self.burnSampleLimit))
+            burnSamples[key] = samples
+            projections[key] = computeBurnProjection(samples: samples, metric: metric, now: snapshot.lastUpdated)
+        }
+        return snapshot.withBurnProjections(projections)
+    }
+
+    /// The cached access token if it's still outside the expiry grace period.
+    /// Synchronous, so the credential cache can't change between the check
+    /// and the read.
+    private func validCachedToken() -> String? {
+        guard let cached = cachedCredentials, !cached.isExpired(grace: tokenExpiryGrace) else { return nil }
+        let ttl = settings.tokenTTL
+        if ttl > 0, let credentialsFetchedAt, now().timeIntervalSince(credentialsFetchedAt) >= ttl {
+            return nil
+        }
+        return cached.accessToken
+    }
+
+    /// Fetches the plan once per token. A failed lookup isn't cached, so it's
+    /// tried again next refresh; until then the last known plan is kept.
+    private func planInfo(token: String, existing: PlanInfo?) async -> PlanInfo? {
+        if let cachedPlan, cachedPlan.token == token {
+            return cachedPlan.plan
+        }
+        do {
+            let plan = try await apiService.fetchProfile(token: token)
+            cachedPlan = CachedPlan(token: token, plan: plan)
+            DebugLogger.shared.log("Plan: \(plan?.name ?? "unknown")", source: "App")
+            return plan
+        } catch {
+            DebugLogger.shared.log("Profile fetch failed: \(describeError(error))", level: .warning, source: "App")
+            return existing
+        }
+    }
+
+    private func loadCredentials() async throws -> OAuthCredentials {
+        let keychainService = UnsafeSendableBox(value: self.keychainService)
+        var credentials: OAuthCredentials
+        do {
+            credentials = try await runBlockingThrowing {
+                try keychainService.value.readCredentials()
+            }
+        } catch {
+            if case KeychainError.accessDenied = error { keychainDenied = true }
+            throw error
+        }
+        if let unsavedRenewal, credentials.refreshToken == unsavedRenewal.staleRefreshToken {
+            DebugLogger.shared.log("Stored refresh token was already used; keeping the renewed token", level: .debug, source: "App")
+            credentials = credentials.source.map(unsavedRenewal.credentials.withSource) ?? unsavedRenewal.credentials
+        } else {
+            unsavedRenewal = nil
+        }
+        cachedCredentials = credentials
+        credentialsFetchedAt = now()
+        credentialSource = credentials.source
+        credentialFailure = nil
+        return credentials
+    }
+
+    /// Fetches usage, handling a 401 by first re-reading the credential store:
+    /// Claude Code renews its own token, so the new one is usually already
+    /// there. Renewing here is the last resort, because it rotates the
+    /// refresh token Claude Code has stored (see `renewsRejectedTokens`).
+    private func fetchUsageHandlingAuth(
+        token: String,
+        allowingCached: Bool = true
+    ) async throws -> (response: UsageApiResponse, token: String) {
+        do {
+            return (try await apiService.fetchUsage(token: token, allowingCached: allowingCached), token)
+        } catch APIError.unauthorized {
+            if let reread = try? await loadCredentials(), reread.accessToken != token {
+                DebugLogger.shared.log("Token rejected; retrying with the one now in \(reread.source?.displayName ?? "keychain")", source: "App")
+                do {
+                    return (try await apiService.fetchUsage(token: reread.accessToken, allowingCached: allowingCached), reread.accessToken)
+                } catch APIError.unauthorized {}
+            }
+            guard let renewed = await renewCredentials() else { throw APIError.unauthorized }
+            return (try await apiService.fetchUsage(token: renewed.accessToken, allowingCached: allowingCached), renewed.accessToken)
+        }
+    }
+
+    /// One attempt to trade the cached refresh token for a new access token
+    /// after the API rejected the current one. Returns nil when the user
+    /// hasn't opted in, there is no refresh token or the exchange fails.
+    private func renewCredentials() async -> OAuthCredentials? {
+        let debug = DebugLogger.shared
+        guard settings.renewsRejectedTokens,
+              let refreshToken = cachedCredentials?.refreshToken else { return nil }
+
+        do {
+            let renewed = try await tokenRefreshService.refresh(refreshToken: refreshToken)
+            cachedCredentials = renewed
+            credentialsFetchedAt = now()
+            debug.log("Access token renewed with refresh token", source: "App")
+            let saved = await saveRenewedCredentials(renewed)
+            // The store keeps the refresh token it had before any unsaved renewal.
+            unsavedRenewal = saved ? nil : UnsavedRenewal(
+                staleRefreshToken: unsavedRenewal?.staleRefreshToken ?? refreshToken,
+                credentials: renewed
+            )
+            return renewed
+        } catch {
+            debug.log("Token refresh failed: \(describeError(error))", level: .warning, source: "App")
+            return nil
+        }
+    }
+
+    /// Hands renewed tokens back to the store the originals were read from.
+    /// A failed write is logged and the renewal kept in memory instead.
+    private func saveRenewedCredentials(_ renewed: OAuthCredentials) async -> Bool {
+        guard let credentialSource else { return false }
+        let credentials = renewed.withSource(credentialSource)
+        let keychainService = UnsafeSendableBox(value: self.keychainService)
+        do {
+            try await runBlockingThrowing {
+                try keychainService.value.writeCredentials(credentials)
+            }
+            DebugLogger.shared.log("Renewed token saved to \(credentialSource.displayName)", source: "App")
+            return true
+        } catch {
+            DebugLogger.shared.log("Saving renewed token failed: \(describeError(error))", level: .warning, source: "App")
+            return false
         }
     }
 
@@ -218,9 +1169,9 @@ final class UsageManager: ObservableObject {
             return CodexRefreshResult(snapshot: newSnapshot, shouldPersist: true)
         } catch {
             let msg = describeCodexError(error)
-            debug.log("Codex API error: \(msg)", source: "App")
+            debug.log("Codex API error: \(msg)", level: .error, source: "App")
             if let existing {
-                return CodexRefreshResult(snapshot: existing.withError(msg, tokenStats: stats), shouldPersist: true)
+                return CodexRefreshResult(snapshot: existing.withError(msg, kind: UsageErrorKind(error), tokenStats: stats), shouldPersist: true)
             }
             if case CodexAuthError.notConfigured = error {
                 return CodexRefreshResult(snapshot: nil, shouldPersist: false)
@@ -239,7 +1190,11 @@ final class UsageManager: ObservableObject {
             tokenStats: claude.tokenStats,
             lastUpdated: max(claude.lastUpdated, codex?.lastUpdated ?? claude.lastUpdated),
             lastSuccessfulUpdate: [claude.lastSuccessfulUpdate, codex?.lastSuccessfulUpdate].compactMap { $0 }.max(),
-            error: claude.error
+            error: claude.error,
+            errorKind: claude.errorKind,
+            nextRetryAt: claude.nextRetryAt,
+            plan: claude.plan,
+            statusThresholds: settings.statusThresholds
         )
     }
 
@@ -278,6 +1233,16 @@ final class UsageManager: ObservableObject {
     }
 }
 
+private enum ClaudeFetchOutcome {
+    case success(UsageApiResponse, plan: PlanInfo?)
+    case failure(String, kind: UsageErrorKind, source: String, nextRetryAt: Date? = nil)
+}
+
+enum RefreshCadence: Equatable {
+    case normal
+    case reduced
+}
+
 private struct ClaudeRefreshResult {
     let snapshot: UsageSnapshot
     let shouldPersist: Bool
@@ -288,12 +1253,81 @@ private struct CodexRefreshResult {
     let shouldPersist: Bool
 }
 
+private struct CredentialFailure {
+    let date: Date
+    let message: String
+}
+
+/// Renewed tokens that exist only in memory, and the spent refresh token the
+/// credential store still holds.
+private struct UnsavedRenewal {
+    let staleRefreshToken: String
+    let credentials: OAuthCredentials
+}
+
+private struct CachedPlan {
+    let token: String
+    let plan: PlanInfo?
+}
+
 private struct RefreshPreparation {
     let existing: UsageSnapshot?
     let claudeStats: TokenStats
     let codexStats: TokenStats
 }
 
+/// Alert bookkeeping for one window's current reset cycle.
+struct UsageAlertState: Equatable {
+    /// How far usage must fall below a level before it can alert again
+    /// within the same cycle.
+    static let rearmMargin = 5.0
+    /// A `resetsAt` moving by more than this starts a new cycle.
+    static let resetTolerance: TimeInterval = 60
+    /// Usage falling from at least `resetDropFrom` to at most `resetDropTo`
+    /// counts as a reset even when `resetsAt` didn't move.
+    static let resetDropFrom = 50.0
+    static let resetDropTo = 5.0
+    /// A second reset within this long of the last one isn't alerted, so a
+    /// flapping `resetsAt` doesn't alert twice.
+    static let resetDebounce: TimeInterval = 15 * 60
+
+    var resetsAt: Date
+    var notified: Set<Double> = []
+    var lastPercent: Double?
+    var lastResetAlertAt: Date?
+
+    /// Compares the latest reading with the previous one; call before
+    /// `update(metric:thresholds:)`, which moves `resetsAt`.
+    mutating func detectReset(metric: UsageMetric, now: Date) -> Bool {
+        defer { lastPercent = metric.percent }
+        guard let lastPercent else { return false }
+
+        let moved = metric.hasResetTime && metric.resetsAt.timeIntervalSince(resetsAt) > Self.resetTolerance
+        let dropped = lastPercent >= Self.resetDropFrom && metric.percent <= Self.resetDropTo
+        guard moved || dropped else { return false }
+
+        if let lastResetAlertAt, now.timeIntervalSince(lastResetAlertAt) < Self.resetDebounce {
+            return false
+        }
+        lastResetAlertAt = now
+        return true
+    }
+
+    /// Applies the latest reading and returns thresholds crossed for the
+    /// first time this cycle.
+    mutating func update(metric: UsageMetric, thresholds: [Double]) -> [Double] {
+        // Without a real reset time `resetsAt` is just the fetch time.
+        if metric.hasResetTime, abs(metric.resetsAt.timeIntervalSince(resetsAt)) > Self.resetTolerance {
+            resetsAt = metric.resetsAt
+            notified = []
+        }
+        notified = notified.filter { metric.percent > $0 - Self.rearmMargin }
+        let crossed = thresholds.filter { metric.percent >= $0 && !notified.contains($0) }
+        notified.formUnion(crossed)
+        return crossed
+    }
+}
+
 private struct UnsafeSendableBox<Value>: @unchecked Sendable {
     let value: Value
 }
@@ -0,0 +1,34 @@
+import Foundation
+import UserNotifications
+
+/// Posts local notifications through the user notification center, asking
+/// for permission the first time one is sent.
+final class UsageNotifier: UsageNotifierProtocol {
+    private let center: UNUserNotificationCenter
+
+    init(center: UNUserNotificationCenter = .current()) {
+        self.center = center
+    }
+
+    func post(title: String, body: String, identifier: String) {
+        let center = self.center
+        center.requestAuthorization(options: [.alert, .sound]) { granted, error in
+            guard granted else {
+                DebugLogger.shared.log("Notification not authorized: \(error.map { "\($0)" } ?? "denied")", level: .warning, source: "Notify")
+                return
+            }
+
+            let content = UNMutableNotificationContent()
+            content.title = title
+            content.body = body
+            content.sound = .default
+
+            let request = UNNotificationRequest(identifier: identifier, content: content, trigger: nil)
+            center.add(request) { error in
+                if let error {
+                    DebugLogger.shared.log("Notification failed: \(error)", level: .warning, source: "Notify")
+                }
+            }
+        }
+    }
+}
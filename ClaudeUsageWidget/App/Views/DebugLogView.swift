@@ -2,7 +2,10 @@ import SwiftUI
 
 struct DebugLogView: View {
     let onBack: () -> Void
+    let onRunDiagnostics: () async -> DiagnosticsReport
     @State private var debugLogText = ""
+    @State private var report: DiagnosticsReport?
+    @State private var isRunningDiagnostics = false
 
     var body: some View {
         VStack(spacing: 0) {
@@ -11,6 +14,10 @@ struct DebugLogView: View {
             VStack(spacing: 8) {
                 actionRow
 
+                if let report {
+                    reportView(report)
+                }
+
                 ScrollView {
                     Text(debugLogText.isEmpty ? "(no logs - tap Refresh or Run Diagnostics)" : debugLogText)
                         .font(.system(size: 9, design: .monospaced))
@@ -59,10 +66,16 @@ struct DebugLogView: View {
                 .font(.system(size: 10))
 
             Button("Run Diagnostics") {
-                DebugLogger.shared.dumpContainerDiagnostics(source: "App-Manual")
-                refreshLogs()
+                Task {
+                    isRunningDiagnostics = true
+                    report = await onRunDiagnostics()
+                    isRunningDiagnostics = false
+                    DebugLogger.shared.dumpContainerDiagnostics(source: "App-Manual")
+                    refreshLogs()
+                }
             }
             .font(.system(size: 10))
+            .disabled(isRunningDiagnostics)
 
             Button("Clear") {
                 DebugLogger.shared.clearLogs()
@@ -80,6 +93,25 @@ struct DebugLogView: View {
         }
     }
 
+    private func reportView(_ report: DiagnosticsReport) -> some View {
+        VStack(alignment: .leading, spacing: 3) {
+            ForEach(report.checks) { check in
+                HStack(alignment: .firstTextBaseline, spacing: 6) {
+                    Image(systemName: check.passed ? "checkmark.circle.fill" : "xmark.octagon.fill")
+                        .foregroundStyle(check.passed ? AnthropicColors.paceGreen : AnthropicColors.coral)
+                    Text(check.name)
+                        .foregroundStyle(AnthropicColors.tan)
+                    Text(check.message)
+                        .foregroundStyle(AnthropicColors.cream)
+                        .lineLimit(2)
+                        .textSelection(.enabled)
+                }
+                .font(.system(size: 10))
+            }
+        }
+        .frame(maxWidth: .infinity, alignment: .leading)
+    }
+
     private func refreshLogs() {
         debugLogText = DebugLogger.shared.readLogs()
     }
@@ -4,6 +4,10 @@ struct PopoverView: View {
     @ObservedObject var manager: UsageManager
     let onRefresh: () async -> Void
     let onOpenSettings: () -> Void
+    @AppStorage(AppSettings.Key.displayTimeZone) private var displayTimeZoneName = ""
+
+    /// Bars showing last-known data after a failed refresh are dimmed.
+    static let staleOpacity = 0.5
 
     var body: some View {
         VStack(spacing: 0) {
@@ -26,6 +30,16 @@ struct PopoverView: View {
             Text(manager.snapshot?.displayTitle ?? "Claude Code Usage")
                 .font(.system(size: 13, weight: .semibold))
                 .foregroundStyle(AnthropicColors.tan)
+            if let plan = manager.snapshot?.plan {
+                Text(plan.name)
+                    .font(.system(size: 9, weight: .medium))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                    .padding(.horizontal, 5)
+                    .padding(.vertical, 1)
+                    .background(AnthropicColors.tan.opacity(0.15))
+                    .cornerRadius(4)
+                    .help(plan.organization ?? plan.name)
+            }
             Spacer()
             Button(action: onOpenSettings) {
                 Image(systemName: "gearshape")
@@ -90,45 +104,72 @@ struct PopoverView: View {
     @ViewBuilder
     private func legacyContent(_ snapshot: UsageSnapshot) -> some View {
         let paceSettings = manager.paceSettings
+        let barOpacity = snapshot.staleSince == nil ? 1 : Self.staleOpacity
 
         UsageBarView(
             label: "5-Hour Window",
             metric: snapshot.fiveHour,
             paceInfo: paceSettings.enabledMetrics.contains(.fiveHour)
                 ? snapshot.fiveHour.flatMap { computePace(metric: $0, windowDuration: MetricKey.fiveHour.windowDuration) }
-                : nil
+                : nil,
+            thresholds: snapshot.statusThresholds ?? .standard
         )
+        .opacity(barOpacity)
         UsageBarView(
             label: "Weekly (All Models)",
             metric: snapshot.sevenDay,
             paceInfo: paceSettings.enabledMetrics.contains(.sevenDay)
                 ? snapshot.sevenDay.flatMap { computePace(metric: $0, windowDuration: MetricKey.sevenDay.windowDuration) }
-                : nil
-        )
-        UsageBarView(
-            label: "Weekly (Sonnet)",
-            metric: snapshot.sevenDaySonnet,
-            paceInfo: paceSettings.enabledMetrics.contains(.sevenDaySonnet)
-                ? snapshot.sevenDaySonnet.flatMap { computePace(metric: $0, windowDuration: MetricKey.sevenDaySonnet.windowDuration) }
-                : nil
-        )
-        UsageBarView(
-            label: "Weekly (Opus)",
-            metric: snapshot.sevenDayOpus,
-            isOpus: true,
-            paceInfo: paceSettings.enabledMetrics.contains(.sevenDayOpus)
-                ? snapshot.sevenDayOpus.flatMap { computePace(metric: $0, windowDuration: MetricKey.sevenDayOpus.windowDuration) }
-                : nil
+                : nil,
+            thresholds: snapshot.statusThresholds ?? .standard
         )
+        .opacity(barOpacity)
+        if snapshot.reportsModelWindows {
+            UsageBarView(
+                label: "Weekly (Sonnet)",
+                metric: snapshot.sevenDaySonnet,
+                paceInfo: paceSettings.enabledMetrics.contains(.sevenDaySonnet)
+                    ? snapshot.sevenDaySonnet.flatMap { computePace(metric: $0, windowDuration: MetricKey.sevenDaySonnet.windowDuration) }
+                    : nil,
+                thresholds: snapshot.statusThresholds ?? .standard
+            )
+            .opacity(barOpacity)
+            UsageBarView(
+                label: "Weekly (Opus)",
+                metric: snapshot.sevenDayOpus,
+                isOpus: true,
+                paceInfo: paceSettings.enabledMetrics.contains(.sevenDayOpus)
+                    ? snapshot.sevenDayOpus.flatMap { computePace(metric: $0, windowDuration: MetricKey.sevenDayOpus.windowDuration) }
+                    : nil,
+                thresholds: snapshot.statusThresholds ?? .standard
+            )
+            .opacity(barOpacity)
+        }
 
         divider
 
         TokenStatsView(stats: snapshot.tokenStats)
 
+        if let warning = manager.manualRefreshWarning {
+            errorBanner(warning)
+        }
+
+        if manager.refreshCadence == .reduced {
+            Text("Eco mode: refreshing less often")
+                .font(.system(size: 9))
+                .foregroundStyle(AnthropicColors.creamMuted)
+        }
+
         if let error = snapshot.error {
-            errorBanner(error)
-            if let lastSuccess = snapshot.lastSuccessfulUpdate {
-                lastSuccessView(lastSuccess)
+            if snapshot.errorKind == .auth {
+                authBanner(error)
+            } else if snapshot.errorKind != .rateLimited, let retryStatus = snapshot.retryStatus() {
+                errorBanner("\(error) \(retryStatus).")
+            } else {
+                errorBanner(error)
+            }
+            if let staleSince = snapshot.staleSince {
+                lastSuccessView(staleSince)
             }
         }
     }
@@ -143,13 +184,18 @@ struct PopoverView: View {
                 claudePaceInfo: claudePaceInfo(for: section, paceSettings: paceSettings),
                 codexPaceInfo: codexPaceInfo(for: section, paceSettings: paceSettings)
             )
+            .opacity(snapshot.staleSince == nil ? 1 : Self.staleOpacity)
         }
 
         divider
 
         CompareTokenStatsView(claudeStats: snapshot.tokenStats, codexStats: snapshot.codex?.tokenStats)
 
-        ForEach(snapshot.compareErrorMessages, id: \.self) { message in
+        if snapshot.errorKind == .auth, let error = snapshot.error {
+            authBanner("Claude: \(error)")
+        }
+
+        ForEach(snapshot.compareErrorMessages.filter { snapshot.errorKind != .auth || !$0.hasPrefix("Claude:") }, id: \.self) { message in
             errorBanner(message)
         }
 
@@ -185,13 +231,26 @@ struct PopoverView: View {
         .cornerRadius(6)
     }
 
+    /// Auth failures won't clear on their own, so this banner stays put and
+    /// offers a way to re-read credentials after signing in again.
+    private func authBanner(_ message: String) -> some View {
+        VStack(alignment: .leading, spacing: 6) {
+            errorBanner(message)
+            Button(manager.keychainDenied ? "Grant access" : "Reload credentials") {
+                Task { await manager.retryKeychainAccess() }
+            }
+            .font(.system(size: 10))
+        }
+    }
+
     private func lastSuccessView(_ lastSuccess: Date) -> some View {
         HStack(spacing: 4) {
             Image(systemName: "clock")
                 .font(.system(size: 9))
-            Text(lastSuccess, style: .relative)
-                .font(.system(size: 9))
+            Text(UsageSnapshot.displayTime(lastSuccess, in: AppSettings.displayTimeZone(named: displayTimeZoneName)))
+                + Text(" (") + Text(lastSuccess, style: .relative) + Text(" ago)")
         }
+        .font(.system(size: 9))
         .foregroundStyle(AnthropicColors.creamMuted)
     }
 
@@ -329,6 +388,7 @@ private struct CompareUsageBarRowView: View {
 
     private var resetText: String {
         guard let metric else { return "No data" }
+        guard metric.hasResetTime else { return "Reset time unknown" }
         let formatter = RelativeDateTimeFormatter()
         formatter.unitsStyle = .short
         return "Resets \(formatter.localizedString(for: metric.resetsAt, relativeTo: Date()))"
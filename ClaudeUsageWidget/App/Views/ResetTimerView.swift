@@ -1,7 +1,7 @@
 import SwiftUI
 
 struct ResetTimerView: View {
-    let resetsAt: Date
+    let metric: UsageMetric
 
     var body: some View {
         TimelineView(.periodic(from: .now, by: 60)) { context in
@@ -12,10 +12,10 @@ struct ResetTimerView: View {
     }
 
     func timerText(at now: Date) -> String {
-        let remaining = resetsAt.timeIntervalSince(now)
-        guard remaining > 0 else { return "Resetting..." }
+        guard let remaining = metric.secondsUntilReset(now: now) else { return "Reset time unknown" }
+        guard !metric.hasResetPassed(now: now) else { return "Resetting..." }
 
-        let totalMinutes = Int(remaining) / 60
+        let totalMinutes = remaining / 60
         let days = totalMinutes / (24 * 60)
         let hours = (totalMinutes % (24 * 60)) / 60
         let minutes = totalMinutes % 60
@@ -1,9 +1,56 @@
 import SwiftUI
 import ServiceManagement
+import UniformTypeIdentifiers
 
 struct SettingsView: View {
     @AppStorage("refreshInterval") private var refreshInterval: Int = 300
+    @AppStorage(AppSettings.Key.tokenSource) private var tokenSource: TokenSource = .keychain
+    @AppStorage(AppSettings.Key.tokenFilePath) private var tokenFilePath: String = ""
+    @AppStorage(AppSettings.Key.ecoMode) private var ecoMode: Bool = false
+    @AppStorage(AppSettings.Key.demoMode) private var demoMode: Bool = false
+    @AppStorage(AppSettings.Key.renewRejectedTokens) private var renewRejectedTokens: Bool = false
+    @AppStorage(AppSettings.Key.weekDefinition) private var weekDefinition: WeekDefinition = .rolling
+    @AppStorage(AppSettings.Key.menuBarTitle) private var menuBarTitle: MenuBarTitleMetric = .off
+    @AppStorage(DebugLogger.levelKey, store: UserDefaults(suiteName: SharedContainerService.appGroupID))
+    private var logLevel: LogLevel = DebugLogger.defaultLevel
+    @AppStorage(AppSettings.Key.requestTimeoutSeconds) private var requestTimeoutSeconds: Int = Int(URLSessionConfiguration.defaultRequestTimeout)
     @State private var launchAtLogin: Bool = false
+    @State private var credentialReloadResult: Bool?
+    @State private var credentialCheck: DiagnosticCheck?
+    @State private var storedSettingsProblems: [SettingsError] = []
+    @State private var proxyDraft: String = AppSettings().proxyURL ?? ""
+    @State private var profileError: String?
+    @State private var proxyError: String?
+    @State private var proxyPasswordDraft: String = ""
+    @State private var apiBaseURLDraft: String = AppSettings().apiBaseURLOverride ?? ""
+    @State private var apiBaseURLError: String?
+    @State private var thresholdsDraft: String = AppSettings().notifyThresholds.map { String(Int($0)) }.joined(separator: ", ")
+    @State private var thresholdsError: String?
+    @State private var iconThresholdsDraft: String = {
+        let thresholds = AppSettings().iconThresholds
+        return [thresholds.moderate, thresholds.high, thresholds.critical].map { String(Int($0)) }.joined(separator: ", ")
+    }()
+    @State private var iconThresholdsError: String?
+    @State private var statusThresholdsDraft: String = {
+        let thresholds = AppSettings().statusThresholds
+        return "\(Int(thresholds.warning)), \(Int(thresholds.critical))"
+    }()
+    @State private var statusThresholdsError: String?
+    @State private var quietStartDraft: String = AppSettings().quietHours?.start ?? ""
+    @State private var quietEndDraft: String = AppSettings().quietHours?.end ?? ""
+    @State private var quietDays: Set<Int> = AppSettings().quietHours?.days ?? []
+    @State private var quietHoursError: String?
+    @State private var notifyWindows: Set<MetricKey> = AppSettings().notifyWindows
+    @State private var notifyResetWindows: Set<MetricKey> = AppSettings().notifyResetWindows
+    @State private var statsCachePathDraft: String = AppSettings().statsCachePath ?? ""
+    @State private var timeZoneDraft: String = AppSettings().dayBoundaryTimeZoneName
+    @State private var timeZoneError: String?
+    @State private var displayTimeZoneDraft: String = AppSettings().displayTimeZoneName
+    @State private var displayTimeZoneNote: String?
+    @State private var exportStart = Calendar.current.date(byAdding: .month, value: -1, to: Date()) ?? Date()
+    @State private var exportEnd = Date()
+    @State private var exportFormat: ExportFormat = .csv
+    @State private var exportStatus: String?
     @ObservedObject var manager: UsageManager
 
     var onIntervalChanged: ((Int) -> Void)?
@@ -17,8 +64,34 @@ struct SettingsView: View {
         ("15 min", 900),
     ]
 
+    private let timeoutOptions: [(String, Int)] = [
+        ("10 sec", 10),
+        ("15 sec", 15),
+        ("30 sec", 30),
+        ("60 sec", 60),
+    ]
+
+    private let windowOptions: [(String, MetricKey)] = [
+        ("5h", .fiveHour),
+        ("7d", .sevenDay),
+        ("Sonnet", .sevenDaySonnet),
+        ("Opus", .sevenDayOpus),
+    ]
+
     var body: some View {
         VStack(spacing: 10) {
+            if !storedSettingsProblems.isEmpty {
+                VStack(alignment: .leading, spacing: 2) {
+                    Text("Some saved settings are invalid and are being ignored:")
+                    ForEach(storedSettingsProblems.indices, id: \.self) { index in
+                        Text("• \(storedSettingsProblems[index].localizedDescription)")
+                    }
+                }
+                .font(.system(size: 9))
+                .foregroundStyle(AnthropicColors.coral)
+                .frame(maxWidth: .infinity, alignment: .leading)
+            }
+
             HStack {
                 Text("Refresh interval:")
                     .font(.system(size: 11))
@@ -36,6 +109,178 @@ struct SettingsView: View {
                 }
             }
 
+            HStack {
+                Text("Pause auto-refresh:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                Spacer()
+                Toggle("", isOn: Binding(
+                    get: { manager.isRefreshPaused },
+                    set: { paused in
+                        if paused {
+                            manager.pauseRefresh()
+                        } else {
+                            Task { await manager.resumeRefresh() }
+                        }
+                    }
+                ))
+                .labelsHidden()
+                .toggleStyle(.switch)
+                .controlSize(.mini)
+            }
+
+            HStack {
+                Text("Eco mode:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                    .help("Refresh \(Int(UsageManager.ecoIntervalMultiplier))x less often while this panel is closed or Low Power Mode is on")
+                Spacer()
+                Toggle("", isOn: $ecoMode)
+                    .labelsHidden()
+                    .toggleStyle(.switch)
+                    .controlSize(.mini)
+                    .onChange(of: ecoMode) { _, _ in
+                        manager.updateRefreshCadence()
+                    }
+            }
+
+            HStack {
+                Text("Demo data:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                    .help("Show made-up usage for screenshots and demos; no token or API calls needed")
+                Spacer()
+                Toggle("", isOn: $demoMode)
+                    .labelsHidden()
+                    .toggleStyle(.switch)
+                    .controlSize(.mini)
+                    .onChange(of: demoMode) { _, _ in
+                        Task { await manager.refresh(userInitiated: true) }
+                    }
+            }
+
+            HStack {
+                Text("Menu bar shows:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                Spacer()
+                Picker("", selection: $menuBarTitle) {
+                    ForEach(MenuBarTitleMetric.allCases, id: \.self) { metric in
+                        Text(metric.displayName).tag(metric)
+                    }
+                }
+                .labelsHidden()
+                .frame(width: 110)
+            }
+
+            VStack(alignment: .leading, spacing: 6) {
+                HStack {
+                    Text("Menu bar icon at %:")
+                        .font(.system(size: 11))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                    TextField("40, 70, 90", text: $iconThresholdsDraft)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .onSubmit(applyIconThresholds)
+                        .help("Moderate, high and critical for the menu bar icon only")
+                    Button("Apply", action: applyIconThresholds)
+                        .font(.system(size: 11))
+                }
+                if let iconThresholdsError {
+                    Text(iconThresholdsError)
+                        .font(.system(size: 9))
+                        .foregroundStyle(AnthropicColors.coral)
+                }
+                HStack {
+                    Text("Popover bars at %:")
+                        .font(.system(size: 11))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                    TextField("75, 90", text: $statusThresholdsDraft)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .onSubmit(applyStatusThresholds)
+                        .help("Warning and critical for the popover and widget bars, separate from the icon's levels")
+                    Button("Apply", action: applyStatusThresholds)
+                        .font(.system(size: 11))
+                }
+                if let statusThresholdsError {
+                    Text(statusThresholdsError)
+                        .font(.system(size: 9))
+                        .foregroundStyle(AnthropicColors.coral)
+                }
+                Text("The icon and the bars turn critical at their own levels.")
+                    .font(.system(size: 9))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+            }
+
+            HStack {
+                Text("Week:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                Spacer()
+                Picker("", selection: $weekDefinition) {
+                    ForEach(WeekDefinition.allCases, id: \.self) { week in
+                        Text(week.displayName).tag(week)
+                    }
+                }
+                .labelsHidden()
+                .frame(width: 110)
+                .onChange(of: weekDefinition) { _, _ in
+                    Task { await manager.refreshTokenStats() }
+                }
+            }
+
+            VStack(alignment: .leading, spacing: 6) {
+                HStack {
+                    Text("Day starts in:")
+                        .font(.system(size: 11))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                    Spacer()
+                    TextField("Local", text: $timeZoneDraft)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .frame(width: 110)
+                        .onSubmit(applyTimeZone)
+                    Button("Apply", action: applyTimeZone)
+                        .font(.system(size: 11))
+                }
+                if let timeZoneError {
+                    Text(timeZoneError)
+                        .font(.system(size: 9))
+                        .foregroundStyle(AnthropicColors.coral)
+                }
+                HStack {
+                    Text("Show times in:")
+                        .font(.system(size: 11))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                    Spacer()
+                    TextField("Local", text: $displayTimeZoneDraft)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .frame(width: 110)
+                        .onSubmit(applyDisplayTimeZone)
+                    Button("Apply", action: applyDisplayTimeZone)
+                        .font(.system(size: 11))
+                }
+                if let displayTimeZoneNote {
+                    Text(displayTimeZoneNote)
+                        .font(.system(size: 9))
+                        .foregroundStyle(AnthropicColors.coral)
+                }
+            }
+
+            HStack {
+                Text("Stats file:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                TextField("~/.claude/stats-cache.json", text: $statsCachePathDraft)
+                    .font(.system(size: 10, design: .monospaced))
+                    .textFieldStyle(.roundedBorder)
+                    .onSubmit(applyStatsCachePath)
+                Button("Apply", action: applyStatsCachePath)
+                    .font(.system(size: 11))
+            }
+
             HStack {
                 Text("Launch at login:")
                     .font(.system(size: 11))
@@ -50,6 +295,251 @@ struct SettingsView: View {
                     }
             }
 
+            VStack(alignment: .leading, spacing: 6) {
+                HStack {
+                    Text("Alert at %:")
+                        .font(.system(size: 11))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                    TextField("Off", text: $thresholdsDraft)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .onSubmit(applyThresholds)
+                    Button("Apply", action: applyThresholds)
+                        .font(.system(size: 11))
+                }
+                if let thresholdsError {
+                    Text(thresholdsError)
+                        .font(.system(size: 9))
+                        .foregroundStyle(AnthropicColors.coral)
+                }
+                HStack {
+                    ForEach(windowOptions, id: \.1) { label, key in
+                        Toggle(label, isOn: notifyWindowBinding(key))
+                            .font(.system(size: 10))
+                            .toggleStyle(.checkbox)
+                    }
+                    Spacer()
+                    Button("Send test") {
+                        manager.sendTestNotification()
+                    }
+                    .font(.system(size: 11))
+                }
+                HStack {
+                    Text("On reset:")
+                        .font(.system(size: 11))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                    ForEach(windowOptions, id: \.1) { label, key in
+                        Toggle(label, isOn: notifyResetWindowBinding(key))
+                            .font(.system(size: 10))
+                            .toggleStyle(.checkbox)
+                    }
+                    Spacer()
+                }
+                HStack {
+                    Text("Quiet hours:")
+                        .font(.system(size: 11))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                    TextField("22:00", text: $quietStartDraft)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .frame(width: 50)
+                    Text("–")
+                        .font(.system(size: 11))
+                    TextField("07:00", text: $quietEndDraft)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .frame(width: 50)
+                        .onSubmit(applyQuietHours)
+                    Spacer()
+                    Button("Apply", action: applyQuietHours)
+                        .font(.system(size: 11))
+                }
+                HStack(spacing: 4) {
+                    ForEach(Array(Calendar.current.veryShortWeekdaySymbols.enumerated()), id: \.offset) { index, symbol in
+                        Toggle(symbol, isOn: quietDayBinding(index + 1))
+                            .font(.system(size: 10))
+                            .toggleStyle(.checkbox)
+                    }
+                    Spacer()
+                }
+                .help("Days the quiet hours start on; none checked means every day")
+                if let quietHoursError {
+                    Text(quietHoursError)
+                        .font(.system(size: 9))
+                        .foregroundStyle(AnthropicColors.coral)
+                }
+            }
+
+            Divider()
+
+            VStack(alignment: .leading, spacing: 6) {
+                HStack {
+                    Text("Token source:")
+                        .font(.system(size: 11))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                    Spacer()
+                    Picker("", selection: $tokenSource) {
+                        ForEach(TokenSource.allCases, id: \.self) { source in
+                            Text(source.displayName).tag(source)
+                        }
+                    }
+                    .labelsHidden()
+                    .frame(width: 110)
+                    .onChange(of: tokenSource) { _, _ in
+                        Task { await manager.applyCredentialSettings() }
+                    }
+                }
+                if tokenSource == .file {
+                    TextField("Path to token file", text: $tokenFilePath)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .onSubmit {
+                            Task { await manager.applyCredentialSettings() }
+                        }
+                }
+                if tokenSource == .environment {
+                    Text("Reads $\(KeychainService.tokenEnvironmentVariable)")
+                        .font(.system(size: 9, design: .monospaced))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                }
+                HStack {
+                    Text("Renew rejected tokens:")
+                        .font(.system(size: 11))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                        .help("When Claude Code's stored token is rejected too, renew it and save the new tokens to Claude Code's login. Off: the app reports the auth error until Claude Code signs in again.")
+                    Spacer()
+                    Toggle("", isOn: $renewRejectedTokens)
+                        .labelsHidden()
+                        .toggleStyle(.switch)
+                        .controlSize(.mini)
+                }
+                if !manager.profiles.isEmpty {
+                    HStack {
+                        Text("Profile:")
+                            .font(.system(size: 11))
+                            .foregroundStyle(AnthropicColors.creamMuted)
+                        Spacer()
+                        Picker("", selection: profileBinding) {
+                            Text("Default").tag("")
+                            ForEach(manager.profiles) { profile in
+                                Text(profile.name).tag(profile.name)
+                            }
+                        }
+                        .labelsHidden()
+                        .frame(width: 140)
+                    }
+                    if let profileError {
+                        Text(profileError)
+                            .font(.system(size: 9))
+                            .foregroundStyle(AnthropicColors.coral)
+                    }
+                }
+                if manager.activeProfileName == nil && manager.availableAccounts.count > 1 {
+                    HStack {
+                        Text("Account:")
+                            .font(.system(size: 11))
+                            .foregroundStyle(AnthropicColors.creamMuted)
+                        Spacer()
+                        Picker("", selection: accountBinding) {
+                            Text("Default (\(NSUserName()))").tag("")
+                            ForEach(manager.availableAccounts, id: \.self) { account in
+                                Text(account).tag(account)
+                            }
+                        }
+                        .labelsHidden()
+                        .frame(width: 140)
+                    }
+                }
+                Text("In use: \(manager.credentialSource?.displayName ?? "—")")
+                    .font(.system(size: 9))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                HStack {
+                    Button("Reload credentials") {
+                        Task {
+                            credentialReloadResult = await manager.reloadCredentials()
+                            if credentialReloadResult == true {
+                                await manager.refresh()
+                            }
+                        }
+                    }
+                    .font(.system(size: 11))
+                    Button("Check access") {
+                        Task { credentialCheck = await manager.checkCredentials() }
+                    }
+                    .font(.system(size: 11))
+                    .help("Read credentials without fetching usage")
+                    if let credentialReloadResult {
+                        Text(credentialReloadResult ? "Token found" : "No token found")
+                            .font(.system(size: 9))
+                            .foregroundStyle(credentialReloadResult ? AnthropicColors.creamMuted : AnthropicColors.coral)
+                    }
+                }
+                if let credentialCheck {
+                    Text(credentialCheck.message)
+                        .font(.system(size: 9))
+                        .foregroundStyle(credentialCheck.passed ? AnthropicColors.creamMuted : AnthropicColors.coral)
+                        .lineLimit(2)
+                }
+            }
+
+            Divider()
+
+            HStack {
+                Text("Request timeout:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                Spacer()
+                Picker("", selection: $requestTimeoutSeconds) {
+                    ForEach(timeoutOptions, id: \.1) { label, value in
+                        Text(label).tag(value)
+                    }
+                }
+                .labelsHidden()
+                .frame(width: 80)
+                .onChange(of: requestTimeoutSeconds) { _, _ in
+                    manager.applyNetworkSettings()
+                }
+            }
+
+            VStack(alignment: .leading, spacing: 6) {
+                Text("Proxy (HTTP or SOCKS5):")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                HStack {
+                    TextField("System or $HTTPS_PROXY / $ALL_PROXY", text: $proxyDraft)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .onSubmit(applyProxy)
+                    Button("Apply", action: applyProxy)
+                        .font(.system(size: 11))
+                }
+                SecureField("Proxy password (kept in Keychain)", text: $proxyPasswordDraft)
+                    .font(.system(size: 10, design: .monospaced))
+                    .textFieldStyle(.roundedBorder)
+                    .onSubmit(applyProxy)
+                if let proxyError {
+                    Text(proxyError)
+                        .font(.system(size: 9))
+                        .foregroundStyle(AnthropicColors.coral)
+                }
+                Text("API base URL:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                HStack {
+                    TextField(APIService.defaultBaseURL.absoluteString, text: $apiBaseURLDraft)
+                        .font(.system(size: 10, design: .monospaced))
+                        .textFieldStyle(.roundedBorder)
+                        .onSubmit(applyAPIBaseURL)
+                    Button("Apply", action: applyAPIBaseURL)
+                        .font(.system(size: 11))
+                }
+                if let apiBaseURLError {
+                    Text(apiBaseURLError)
+                        .font(.system(size: 9))
+                        .foregroundStyle(AnthropicColors.coral)
+                }
+            }
+
             Divider()
 
             VStack(alignment: .leading, spacing: 6) {
@@ -76,6 +566,58 @@ struct SettingsView: View {
 
             Divider()
 
+            VStack(alignment: .leading, spacing: 6) {
+                Text("Export usage:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                DatePicker("From", selection: $exportStart, displayedComponents: .date)
+                    .font(.system(size: 11))
+                DatePicker("To", selection: $exportEnd, displayedComponents: .date)
+                    .font(.system(size: 11))
+                HStack {
+                    Picker("", selection: $exportFormat) {
+                        ForEach(ExportFormat.allCases, id: \.self) { format in
+                            Text(format.displayName).tag(format)
+                        }
+                    }
+                    .labelsHidden()
+                    .frame(width: 80)
+                    Spacer()
+                    Button("Export…", action: exportUsage)
+                        .font(.system(size: 11))
+                    Button("History CSV…", action: exportHistory)
+                        .font(.system(size: 11))
+                        .help("Every recorded refresh in the range, not daily totals")
+                }
+                HStack {
+                    Spacer()
+                    Button("Current data as JSON…", action: exportSnapshot)
+                        .font(.system(size: 11))
+                        .help("What the menu bar and widget show right now, for bug reports")
+                }
+                if let exportStatus {
+                    Text(exportStatus)
+                        .font(.system(size: 9))
+                        .foregroundStyle(AnthropicColors.creamMuted)
+                }
+            }
+
+            Divider()
+
+            HStack {
+                Text("Log level:")
+                    .font(.system(size: 11))
+                    .foregroundStyle(AnthropicColors.creamMuted)
+                Spacer()
+                Picker("", selection: $logLevel) {
+                    ForEach(LogLevel.allCases, id: \.self) { level in
+                        Text(level.rawValue.capitalized).tag(level)
+                    }
+                }
+                .labelsHidden()
+                .frame(width: 80)
+            }
+
             if let onOpenDebugger {
                 Button(action: onOpenDebugger) {
                     Label("Debugger", systemImage: "ladybug")
@@ -96,9 +638,44 @@ struct SettingsView: View {
         .padding(.vertical, 8)
         .onAppear {
             launchAtLogin = SMAppService.mainApp.status == .enabled
+            storedSettingsProblems = AppSettings().validate()
+        }
+        // Re-checked after every write, so a fixed value drops off the list.
+        .onReceive(NotificationCenter.default.publisher(for: UserDefaults.didChangeNotification).receive(on: DispatchQueue.main)) { _ in
+            storedSettingsProblems = AppSettings().validate()
+        }
+        .task {
+            manager.loadProfiles()
+            await manager.loadAccounts()
         }
     }
 
+    private var profileBinding: Binding<String> {
+        Binding(
+            get: { manager.activeProfileName ?? "" },
+            set: { name in
+                Task {
+                    do {
+                        try await manager.setActiveProfile(name.isEmpty ? nil : name)
+                        profileError = nil
+                    } catch {
+                        profileError = error.localizedDescription
+                    }
+                    await manager.loadAccounts()
+                }
+            }
+        )
+    }
+
+    private var accountBinding: Binding<String> {
+        Binding(
+            get: { manager.selectedAccount ?? "" },
+            set: { account in
+                Task { await manager.selectAccount(account.isEmpty ? nil : account) }
+            }
+        )
+    }
+
     private func paceBinding(for metric: MetricKey) -> Binding<Bool> {
         Binding(
             get: { manager.paceSettings.enabledMetrics.contains(metric) },
@@ -114,6 +691,199 @@ struct SettingsView: View {
         )
     }
 
+    private func applyProxy() {
+        do {
+            try manager.applyProxy(proxyDraft, password: proxyPasswordDraft)
+            proxyDraft = AppSettings().proxyURL ?? ""
+            proxyPasswordDraft = ""
+            proxyError = nil
+        } catch {
+            proxyError = error.localizedDescription
+        }
+    }
+
+    private func applyAPIBaseURL() {
+        do {
+            try manager.applyAPIBaseURL(apiBaseURLDraft)
+            apiBaseURLError = nil
+        } catch {
+            apiBaseURLError = error.localizedDescription
+        }
+    }
+
+    /// Parses a list such as "80, 95"; an empty field turns alerts off.
+    private func applyThresholds() {
+        let parts = thresholdsDraft.split(whereSeparator: { $0 == "," || $0 == " " })
+        let values = parts.compactMap { Int($0.trimmingCharacters(in: CharacterSet(charactersIn: "%"))) }
+        guard values.count == parts.count else {
+            thresholdsError = "Enter whole percentages separated by commas."
+            return
+        }
+        do {
+            let settings = AppSettings()
+            try settings.setNotifyThresholds(values)
+            thresholdsDraft = settings.notifyThresholds.map { String(Int($0)) }.joined(separator: ", ")
+            thresholdsError = nil
+        } catch {
+            thresholdsError = error.localizedDescription
+        }
+    }
+
+    private func applyIconThresholds() {
+        let values = iconThresholdsDraft
+            .split(whereSeparator: { $0 == "," || $0 == " " })
+            .compactMap { Int($0.trimmingCharacters(in: CharacterSet(charactersIn: "%"))) }
+        guard values.count == 3 else {
+            iconThresholdsError = "Enter three percentages, e.g. 40, 70, 90."
+            return
+        }
+        do {
+            try AppSettings().setIconThresholds(moderate: values[0], high: values[1], critical: values[2])
+            iconThresholdsError = nil
+            manager.updateIconTier()
+        } catch {
+            iconThresholdsError = error.localizedDescription
+        }
+    }
+
+    private func applyStatusThresholds() {
+        let values = statusThresholdsDraft
+            .split(whereSeparator: { $0 == "," || $0 == " " })
+            .compactMap { Int($0.trimmingCharacters(in: CharacterSet(charactersIn: "%"))) }
+        guard values.count == 2 else {
+            statusThresholdsError = "Enter two percentages, e.g. 75, 90."
+            return
+        }
+        do {
+            try AppSettings().setStatusThresholds(warning: values[0], critical: values[1])
+            statusThresholdsError = nil
+            manager.applyStatusThresholds()
+        } catch {
+            statusThresholdsError = error.localizedDescription
+        }
+    }
+
+    private func notifyWindowBinding(_ key: MetricKey) -> Binding<Bool> {
+        Binding(
+            get: { notifyWindows.contains(key) },
+            set: { enabled in
+                if enabled {
+                    notifyWindows.insert(key)
+                } else {
+                    notifyWindows.remove(key)
+                }
+                AppSettings().setNotifyWindows(notifyWindows)
+            }
+        )
+    }
+
+    private func notifyResetWindowBinding(_ key: MetricKey) -> Binding<Bool> {
+        Binding(
+            get: { notifyResetWindows.contains(key) },
+            set: { enabled in
+                if enabled {
+                    notifyResetWindows.insert(key)
+                } else {
+                    notifyResetWindows.remove(key)
+                }
+                AppSettings().setNotifyResetWindows(notifyResetWindows)
+            }
+        )
+    }
+
+    private func quietDayBinding(_ weekday: Int) -> Binding<Bool> {
+        Binding(
+            get: { quietDays.contains(weekday) },
+            set: { enabled in
+                if enabled {
+                    quietDays.insert(weekday)
+                } else {
+                    quietDays.remove(weekday)
+                }
+            }
+        )
+    }
+
+    /// Empty start and end fields turn quiet hours off.
+    private func applyQuietHours() {
+        do {
+            try AppSettings().setQuietHours(start: quietStartDraft, end: quietEndDraft, days: quietDays)
+            quietHoursError = nil
+        } catch {
+            quietHoursError = error.localizedDescription
+        }
+    }
+
+    private func applyStatsCachePath() {
+        Task { await manager.applyStatsCachePath(statsCachePathDraft) }
+    }
+
+    private func applyTimeZone() {
+        Task {
+            do {
+                try await manager.applyDayBoundaryTimeZone(timeZoneDraft)
+                timeZoneError = nil
+            } catch {
+                timeZoneError = error.localizedDescription
+            }
+        }
+    }
+
+    private func applyDisplayTimeZone() {
+        AppSettings().setDisplayTimeZone(displayTimeZoneDraft)
+        displayTimeZoneNote = AppSettings.isKnownTimeZone(displayTimeZoneDraft)
+            ? nil
+            : "Unknown time zone \"\(displayTimeZoneDraft)\"; showing times in UTC."
+    }
+
+    private func exportSnapshot() {
+        let panel = NSSavePanel()
+        panel.allowedContentTypes = [.json]
+        panel.nameFieldStringValue = "claude-usage-snapshot.json"
+        guard panel.runModal() == .OK, let url = panel.url else { return }
+
+        Task {
+            do {
+                try await manager.exportSnapshot(to: url)
+                exportStatus = "Wrote \(url.lastPathComponent)"
+            } catch {
+                exportStatus = error.localizedDescription
+            }
+        }
+    }
+
+    private func exportHistory() {
+        let panel = NSSavePanel()
+        panel.allowedContentTypes = [.commaSeparatedText]
+        panel.nameFieldStringValue = "claude-usage-history.csv"
+        guard panel.runModal() == .OK, let url = panel.url else { return }
+
+        Task {
+            do {
+                let rows = try await manager.exportHistory(from: exportStart, to: exportEnd, to: url)
+                exportStatus = "Wrote \(rows) row\(rows == 1 ? "" : "s") to \(url.lastPathComponent)"
+            } catch {
+                exportStatus = error.localizedDescription
+            }
+        }
+    }
+
+    private func exportUsage() {
+        let panel = NSSavePanel()
+        panel.allowedContentTypes = [exportFormat == .csv ? .commaSeparatedText : .json]
+        panel.nameFieldStringValue = "claude-usage.\(exportFormat.rawValue)"
+        guard panel.runModal() == .OK, let url = panel.url else { return }
+
+        Task {
+            do {
+                let rows = try await manager.exportUsage(format: exportFormat, from: exportStart, to: exportEnd, to: url)
+                exportStatus = "Wrote \(rows) day\(rows == 1 ? "" : "s") to \(url.lastPathComponent)"
+            } catch {
+                exportStatus = error.localizedDescription
+            }
+        }
+    }
+
     private func toggleLaunchAtLogin(_ enable: Bool) {
         do {
             if enable {
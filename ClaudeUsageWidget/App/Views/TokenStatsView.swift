@@ -5,9 +5,60 @@ struct TokenStatsView: View {
 
     var body: some View {
         VStack(spacing: 4) {
-            statsRow(label: "Today:", value: stats.formattedTodayTokens)
+            statsRow(label: "Today:", value: todaySummary)
+                .help(dayResetText)
             statsRow(label: "This week:", value: stats.formattedWeekTokens)
+
+            if let todayCost = stats.todayCost, let weekCost = stats.weekCost {
+                statsRow(label: "Est. cost:", value: costSummary(today: todayCost, week: weekCost))
+                    .help(stats.unknownModels.isEmpty
+                        ? "Estimated from list prices; override in ~/.claude-widget/pricing.json"
+                        : "Priced at the default rate: \(stats.unknownModels.joined(separator: ", "))")
+            }
+
+            ForEach(weekModels, id: \.name) { model in
+                modelRow(name: model.name, tokens: model.tokens)
+            }
+        }
+    }
+
+    /// Today's tokens with the change from yesterday, when there is one.
+    private var todaySummary: String {
+        guard let delta = stats.tokenDeltaPercent else { return stats.formattedTodayTokens }
+        return "\(stats.formattedTodayTokens) (\(delta >= 0 ? "+" : "")\(Int(delta.rounded()))%)"
+    }
+
+    private var dayResetText: String {
+        guard let dayEndsAt = stats.dayEndsAt else { return "" }
+        return "Day resets at \(dayEndsAt.formatted(date: .omitted, time: .shortened))"
+    }
+
+    /// Marked with an asterisk when some models fell back to the default rate.
+    private func costSummary(today: Double, week: Double) -> String {
+        let marker = stats.unknownModels.isEmpty ? "" : "*"
+        return "\(TokenStats.formatCost(today)) / \(TokenStats.formatCost(week))\(marker)"
+    }
+
+    private var weekModels: [(name: String, tokens: Int)] {
+        let families = stats.weekTokensByFamily
+        guard families.count > 1 else { return [] }
+        return families
+            .map { (name: $0.key.capitalized, tokens: $0.value) }
+            .sorted { $0.tokens > $1.tokens }
+    }
+
+    private func modelRow(name: String, tokens: Int) -> some View {
+        HStack {
+            Text(name)
+                .font(.system(size: 9))
+                .foregroundStyle(AnthropicColors.creamMuted)
+                .lineLimit(1)
+            Spacer()
+            Text(TokenStats.formatNumber(tokens))
+                .font(.system(size: 9, design: .monospaced))
+                .foregroundStyle(AnthropicColors.creamMuted)
         }
+        .padding(.leading, 8)
     }
 
     private func statsRow(label: String, value: String) -> some View {
@@ -5,6 +5,7 @@ struct UsageBarView: View {
     let metric: UsageMetric?
     var isOpus: Bool = false
     var paceInfo: PaceInfo? = nil
+    var thresholds: UsageStatusThresholds = .standard
 
     var body: some View {
         if let metric {
@@ -16,9 +17,17 @@ struct UsageBarView: View {
 
                     Spacer()
 
+                    if metric.willExhaustBeforeReset, let exhaustionAt = metric.projectedExhaustionAt {
+                        Text("100% by \(exhaustionAt.formatted(date: .omitted, time: .shortened))")
+                            .font(.system(size: 9, design: .monospaced))
+                            .foregroundStyle(AnthropicColors.coral)
+                            .help(metric.burnRatePerHour.map { String(format: "Rising %.1f points per hour", $0) } ?? "")
+                    }
+
                     Text("\(Int(metric.clampedPercent))%")
                         .font(.system(size: 11, weight: .semibold, design: .monospaced))
                         .foregroundStyle(AnthropicColors.cream)
+                        .help(metric.projectedPercentAtReset.map { "About \(Int(min($0, 100)))% by reset at the current rate" } ?? "")
                 }
 
                 GeometryReader { geo in
@@ -27,11 +36,11 @@ struct UsageBarView: View {
                             .fill(AnthropicColors.tan.opacity(0.2))
 
                         RoundedRectangle(cornerRadius: 4)
-                            .fill(gradient(for: metric.clampedPercent))
+                            .fill(gradient(for: metric.status(thresholds: thresholds)))
                             .frame(width: geo.size.width * metric.clampedPercent / 100)
-                            .opacity(metric.clampedPercent >= 90 ? pulseOpacity : 1.0)
+                            .opacity(metric.status(thresholds: thresholds) == .critical ? pulseOpacity : 1.0)
                             .onAppear {
-                                if metric.clampedPercent >= 90 {
+                                if metric.status(thresholds: thresholds) == .critical {
                                     withAnimation(.easeInOut(duration: 1.0).repeatForever(autoreverses: true)) {
                                         pulseOpacity = 0.7
                                     }
@@ -58,14 +67,14 @@ struct UsageBarView: View {
 
                 if let pace = paceInfo {
                     HStack {
-                        ResetTimerView(resetsAt: metric.resetsAt)
+                        ResetTimerView(metric: metric)
                         Spacer()
                         Text(pace.projectedPercent > 100 ? "→ 100%+" : "→ \(Int(min(pace.projectedPercent, 100)))%")
                             .font(.system(size: 10, weight: .semibold, design: .monospaced))
                             .foregroundStyle(AnthropicColors.paceColor(for: pace.status))
                     }
                 } else {
-                    ResetTimerView(resetsAt: metric.resetsAt)
+                    ResetTimerView(metric: metric)
                 }
             }
         }
@@ -73,16 +82,8 @@ struct UsageBarView: View {
 
     @State private var pulseOpacity: Double = 1.0
 
-    private func gradient(for percent: Double) -> LinearGradient {
-        if percent >= 90 {
-            return AnthropicColors.dangerGradient
-        } else if percent >= 70 {
-            return AnthropicColors.warningGradient
-        } else if isOpus {
-            return AnthropicColors.opusGradient
-        } else {
-            return AnthropicColors.normalGradient
-        }
+    private func gradient(for status: UsageStatus) -> LinearGradient {
+        AnthropicColors.statusGradient(for: status, isOpus: isOpus)
     }
 
 }
@@ -6,23 +6,33 @@ struct UsageApiResponse: Codable {
     let sevenDaySonnet: UsageWindow?
     let sevenDayOpus: UsageWindow?
 
-    func toSnapshot(tokenStats: TokenStats) -> UsageSnapshot {
+    func toSnapshot(tokenStats: TokenStats, plan: PlanInfo? = nil) -> UsageSnapshot {
         let isoFormatter = ISO8601DateFormatter()
         isoFormatter.formatOptions = [.withInternetDateTime, .withFractionalSeconds]
 
-        func parseDate(_ s: String) -> Date {
-            isoFormatter.date(from: s) ?? ISO8601DateFormatter().date(from: s) ?? Date()
+        func metric(_ window: UsageWindow) -> UsageMetric {
+            let resetsAt = isoFormatter.date(from: window.resetsAt) ?? ISO8601DateFormatter().date(from: window.resetsAt)
+            if resetsAt == nil {
+                DebugLogger.shared.log("Unparsable resets_at: \(window.resetsAt)", level: .warning, source: "API")
+            }
+            return UsageMetric(
+                percent: window.utilization,
+                resetsAt: resetsAt ?? Date(),
+                rawResetsAt: window.resetsAt,
+                hasResetTime: resetsAt != nil
+            )
         }
 
         return UsageSnapshot(
-            fiveHour: fiveHour.map { UsageMetric(percent: $0.utilization, resetsAt: parseDate($0.resetsAt)) },
-            sevenDay: sevenDay.map { UsageMetric(percent: $0.utilization, resetsAt: parseDate($0.resetsAt)) },
-            sevenDaySonnet: sevenDaySonnet.map { UsageMetric(percent: $0.utilization, resetsAt: parseDate($0.resetsAt)) },
-            sevenDayOpus: sevenDayOpus.map { UsageMetric(percent: $0.utilization, resetsAt: parseDate($0.resetsAt)) },
+            fiveHour: fiveHour.map(metric),
+            sevenDay: sevenDay.map(metric),
+            sevenDaySonnet: sevenDaySonnet.map(metric),
+            sevenDayOpus: sevenDayOpus.map(metric),
             tokenStats: tokenStats,
             lastUpdated: Date(),
             lastSuccessfulUpdate: Date(),
-            error: nil
+            error: nil,
+            plan: plan
         )
     }
 }
@@ -32,6 +42,164 @@ struct UsageWindow: Codable {
     let resetsAt: String
 }
 
+/// Response of the OAuth profile endpoint; only the fields behind the plan
+/// label are decoded.
+struct ProfileApiResponse: Codable {
+    let account: Account?
+    let organization: Organization?
+
+    struct Account: Codable {
+        let hasClaudeMax: Bool?
+        let hasClaudePro: Bool?
+    }
+
+    struct Organization: Codable {
+        let name: String?
+        let organizationType: String?
+        let rateLimitTier: String?
+    }
+
+    /// Nil when the profile doesn't identify a plan.
+    func toPlanInfo() -> PlanInfo? {
+        planName.map { PlanInfo(name: $0, organization: organization?.name) }
+    }
+
+    /// `rate_limit_tier` (e.g. `default_claude_max_20x`) is the most specific
+    /// field; the account flags and organization type cover older profiles.
+    private var planName: String? {
+        let tier = organization?.rateLimitTier?.lowercased() ?? ""
+        let type = organization?.organizationType?.lowercased() ?? ""
+        if tier.contains("max_20x") { return "Max 20x" }
+        if tier.contains("max_5x") { return "Max 5x" }
+        if account?.hasClaudeMax == true || type.contains("max") { return "Max" }
+        if account?.hasClaudePro == true || type.contains("pro") { return "Pro" }
+        if type.contains("enterprise") { return "Enterprise" }
+        if type.contains("team") { return "Team" }
+        return nil
+    }
+}
+
+/// Where the token currently in use was read from.
+enum CredentialSource: String, Codable, Equatable {
+    case keychain
+    case credentialsFile
+    case environment
+    case tokenFile
+
+    var displayName: String {
+        switch self {
+        case .keychain: return "Keychain"
+        case .credentialsFile: return "Credentials file"
+        case .environment: return "Environment variable"
+        case .tokenFile: return "Token file"
+        }
+    }
+}
+
+struct OAuthCredentials: Equatable {
+    let accessToken: String
+    let expiresAt: Date?
+    let refreshToken: String?
+    let source: CredentialSource?
+
+    init(accessToken: String, expiresAt: Date? = nil, refreshToken: String? = nil, source: CredentialSource? = nil) {
+        self.accessToken = accessToken
+        self.expiresAt = expiresAt
+        self.refreshToken = refreshToken
+        self.source = source
+    }
+
+    func withSource(_ source: CredentialSource) -> OAuthCredentials {
+        OAuthCredentials(accessToken: accessToken, expiresAt: expiresAt, refreshToken: refreshToken, source: source)
+    }
+
+    /// True when the token has expired or will within `grace` seconds.
+    /// Credentials without an expiry are treated as valid until the API rejects them.
+    func isExpired(now: Date = Date(), grace: TimeInterval = 0) -> Bool {
+        guard let expiresAt else { return false }
+        return expiresAt.timeIntervalSince(now) <= grace
+    }
+}
+
+extension OAuthCredentials {
+    /// Decodes the `claudeAiOauth` blob Claude Code stores in both the Keychain
+    /// item and `.credentials.json`, so every credential source parses identically.
+    init(credentialsJSON json: String) throws {
+        try self.init(credentialsData: Data(json.utf8))
+    }
+
+    init(credentialsData data: Data) throws {
+        let json: Any
+        do {
+            json = try JSONSerialization.jsonObject(with: data)
+        } catch {
+            throw KeychainError.invalidData("Failed to parse credentials JSON: \(error.localizedDescription)")
+        }
+
+        guard let dict = json as? [String: Any],
+              let oauth = dict["claudeAiOauth"] as? [String: Any],
+              let token = oauth["accessToken"] as? String,
+              !token.isEmpty else {
+            throw KeychainError.invalidData("No OAuth token found in credentials")
+        }
+
+        let refreshToken = (oauth["refreshToken"] as? String).flatMap { $0.isEmpty ? nil : $0 }
+        self.init(accessToken: token, expiresAt: Self.parseExpiry(oauth["expiresAt"]), refreshToken: refreshToken)
+    }
+
+    /// `data` (a `claudeAiOauth` blob) with this token, refresh token and
+    /// expiry swapped in. Every other field is kept as Claude Code wrote it.
+    func replacingTokens(in data: Data) throws -> Data {
+        guard var dict = (try? JSONSerialization.jsonObject(with: data)) as? [String: Any],
+              var oauth = dict["claudeAiOauth"] as? [String: Any] else {
+            throw KeychainError.invalidData("No OAuth token found in credentials")
+        }
+        oauth["accessToken"] = accessToken
+        if let refreshToken {
+            oauth["refreshToken"] = refreshToken
+        }
+        if let expiresAt {
+            oauth["expiresAt"] = Int64(expiresAt.timeIntervalSince1970 * 1000)
+        }
+        dict["claudeAiOauth"] = oauth
+        return try JSONSerialization.data(withJSONObject: dict)
+    }
+
+    /// Claude Code stores `expiresAt` as epoch milliseconds; accept seconds too.
+    private static func parseExpiry(_ value: Any?) -> Date? {
+        guard let number = value as? NSNumber else { return nil }
+        let raw = number.doubleValue
+        let seconds = raw > 100_000_000_000 ? raw / 1000 : raw
+        return Date(timeIntervalSince1970: seconds)
+    }
+}
+
+/// Keeps tokens out of string interpolation so credentials can't leak into the debug log.
+extension OAuthCredentials: CustomStringConvertible, CustomDebugStringConvertible {
+    var description: String {
+        "OAuthCredentials(accessToken: <redacted>, refreshToken: \(refreshToken == nil ? "nil" : "<redacted>"), expiresAt: \(expiresAt.map { "\($0)" } ?? "nil"))"
+    }
+
+    var debugDescription: String { description }
+}
+
+/// Response body of the OAuth token endpoint for a `refresh_token` grant.
+struct OAuthTokenResponse: Decodable {
+    let accessToken: String
+    let refreshToken: String?
+    let expiresIn: TimeInterval?
+
+    /// Servers may omit `refresh_token` when they don't rotate it, in which case
+    /// the one used for the request stays valid.
+    func toCredentials(previousRefreshToken: String, now: Date = Date()) -> OAuthCredentials {
+        OAuthCredentials(
+            accessToken: accessToken,
+            expiresAt: expiresIn.map { now.addingTimeInterval($0) },
+            refreshToken: refreshToken ?? previousRefreshToken
+        )
+    }
+}
+
 struct CodexAuthCredentials: Equatable {
     let accessToken: String
     let accountID: String
@@ -132,7 +300,11 @@ struct DailyActivity: Codable {
     let toolCallCount: Int
 }
 
-struct DailyTokens: Codable {
+struct DailyTokens: Codable, Equatable {
     let date: String
     let tokensByModel: [String: Int]
+
+    var totalTokens: Int {
+        tokensByModel.values.reduce(0, +)
+    }
 }
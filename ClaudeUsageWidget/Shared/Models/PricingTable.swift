@@ -0,0 +1,111 @@
+import Foundation
+
+/// Per-model token prices in USD per million tokens. `builtIn` covers the
+/// current Claude models; `~/.claude-widget/pricing.json` overrides or adds
+/// entries when prices change, and its `default` key sets the rate for
+/// models with no entry:
+///
+///     { "claude-sonnet-4-5": 6.0, "claude-opus-4-1": 30.0, "default": 6.0 }
+///
+/// A key also matches longer model IDs it prefixes, so `claude-sonnet-4-5`
+/// covers `claude-sonnet-4-5-20250929`. Stats only record one total per model,
+/// so each rate is a blended input/output price.
+struct PricingTable: Codable, Equatable {
+    static let defaultRateKey = "default"
+
+    let ratesPerMillion: [String: Double]
+    /// Applied to models without a rate; nil leaves them out of the estimate.
+    let defaultRate: Double?
+
+    static var defaultURL: URL {
+        FileManager.default.homeDirectoryForCurrentUser.appendingPathComponent(".claude-widget/pricing.json")
+    }
+
+    /// List prices as (input, output) per million tokens, blended at three
+    /// input tokens per output token. Unknown models are priced like Sonnet.
+    static let builtIn: PricingTable = {
+        let listPrices: [String: (input: Double, output: Double)] = [
+            "claude-opus-4-5": (5, 25),
+            "claude-opus-4": (15, 75),
+            "claude-3-opus": (15, 75),
+            "claude-sonnet-4": (3, 15),
+            "claude-3-7-sonnet": (3, 15),
+            "claude-3-5-sonnet": (3, 15),
+            "claude-haiku-4-5": (1, 5),
+            "claude-3-5-haiku": (0.8, 4),
+            "claude-3-haiku": (0.25, 1.25),
+        ]
+        return PricingTable(
+            ratesPerMillion: listPrices.mapValues { blendedRate(input: $0.input, output: $0.output) },
+            defaultRate: blendedRate(input: 3, output: 15)
+        )
+    }()
+
+    static func blendedRate(input: Double, output: Double) -> Double {
+        (input * 3 + output) / 4
+    }
+
+    init(ratesPerMillion: [String: Double], defaultRate: Double? = nil) {
+        self.ratesPerMillion = ratesPerMillion
+        self.defaultRate = defaultRate
+    }
+
+    init(from decoder: Decoder) throws {
+        var rates = try decoder.singleValueContainer().decode([String: Double].self)
+        defaultRate = rates.removeValue(forKey: Self.defaultRateKey)
+        ratesPerMillion = rates
+    }
+
+    func encode(to encoder: Encoder) throws {
+        var container = encoder.singleValueContainer()
+        var rates = ratesPerMillion
+        rates[Self.defaultRateKey] = defaultRate
+        try container.encode(rates)
+    }
+
+    /// `builtIn` with the entries from `url` layered on top. A missing or
+    /// malformed file leaves the built-in prices in effect.
+    static func load(from url: URL = defaultURL) -> PricingTable {
+        guard let data = try? Data(contentsOf: url) else { return builtIn }
+        do {
+            return builtIn.overridden(by: try JSONDecoder().decode(PricingTable.self, from: data))
+        } catch {
+            DebugLogger.shared.log("Ignoring unreadable pricing file: \(error)", level: .warning, source: "Stats")
+            return builtIn
+        }
+    }
+
+    func overridden(by other: PricingTable) -> PricingTable {
+        PricingTable(
+            ratesPerMillion: ratesPerMillion.merging(other.ratesPerMillion) { $1 },
+            defaultRate: other.defaultRate ?? defaultRate
+        )
+    }
+
+    /// Exact match first, then the longest key that prefixes `model`.
+    func rate(for model: String) -> Double? {
+        if let rate = ratesPerMillion[model] {
+            return rate
+        }
+        return ratesPerMillion
+            .filter { model.hasPrefix($0.key) }
+            .max { $0.key.count < $1.key.count }?
+            .value
+    }
+
+    /// Dollar estimate for `tokensByModel`. Models without a rate are priced
+    /// at `defaultRate` (or left out without one) and returned in
+    /// `unknownModels`, sorted.
+    func estimateCost(_ tokensByModel: [String: Int]) -> (cost: Double, unknownModels: [String]) {
+        var cost = 0.0
+        var unknownModels: [String] = []
+        for (model, tokens) in tokensByModel {
+            let rate = rate(for: model)
+            if rate == nil {
+                unknownModels.append(model)
+            }
+            cost += Double(tokens) / 1_000_000 * (rate ?? defaultRate ?? 0)
+        }
+        return (cost, unknownModels.sorted())
+    }
+}
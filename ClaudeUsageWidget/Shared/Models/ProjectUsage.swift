@@ -0,0 +1,38 @@
+import Foundation
+
+/// Token counts split by how the API billed them.
+struct TokenBreakdown: Codable, Equatable {
+    var input = 0
+    var output = 0
+    var cacheCreation = 0
+    var cacheRead = 0
+
+    static let zero = TokenBreakdown()
+
+    var total: Int {
+        input + output + cacheCreation + cacheRead
+    }
+
+    static func + (lhs: TokenBreakdown, rhs: TokenBreakdown) -> TokenBreakdown {
+        TokenBreakdown(
+            input: lhs.input + rhs.input,
+            output: lhs.output + rhs.output,
+            cacheCreation: lhs.cacheCreation + rhs.cacheCreation,
+            cacheRead: lhs.cacheRead + rhs.cacheRead
+        )
+    }
+
+    static func += (lhs: inout TokenBreakdown, rhs: TokenBreakdown) {
+        lhs = lhs + rhs
+    }
+}
+
+/// Usage for one directory under `~/.claude/projects`, which Claude Code
+/// names after the project path with separators replaced by dashes.
+struct ProjectUsage: Codable, Equatable {
+    let project: String
+    let today: TokenBreakdown
+    let week: TokenBreakdown
+    let todayByModel: [String: TokenBreakdown]
+    let weekByModel: [String: TokenBreakdown]
+}
@@ -11,23 +11,159 @@ enum MetricKey: String, Codable, CaseIterable {
     }
 }
 
+/// How close a window is to its limit, so the popover and widget color
+/// bars the same way.
+enum UsageStatus: String, Codable {
+    case ok, warning, critical
+
+    static func from(percent: Double, thresholds: UsageStatusThresholds) -> UsageStatus {
+        if percent >= thresholds.critical { return .critical }
+        if percent >= thresholds.warning { return .warning }
+        return .ok
+    }
+}
+
+/// Utilization at which a window becomes a warning and then critical.
+struct UsageStatusThresholds: Codable, Equatable {
+    let warning: Double
+    let critical: Double
+
+    static let standard = UsageStatusThresholds(warning: 75, critical: 90)
+}
+
 enum PaceStatus: Equatable {
     case under, on, over
 }
 
+/// Broad category of a refresh failure, so the UI can react to the kind of
+/// problem (e.g. show a persistent re-authenticate banner) without parsing messages.
+enum UsageErrorKind: String, Codable, Equatable {
+    case auth
+    case rateLimited
+    case network
+    case server
+    case parse
+    case other
+
+    init(_ error: Error) {
+        switch error {
+        case is KeychainError, is CodexAuthError, APIError.unauthorized, APIError.forbidden:
+            self = .auth
+        case APIError.rateLimited:
+            self = .rateLimited
+        case APIError.networkError:
+            self = .network
+        case APIError.serverError:
+            self = .server
+        case APIError.decodingError:
+            self = .parse
+        default:
+            self = .other
+        }
+    }
+}
+
 struct PaceInfo: Equatable {
     let projectedPercent: Double
     let status: PaceStatus
     var clampedProjectedPercent: Double { min(max(projectedPercent, 0), 100) }
 }
 
+/// Subscription plan of the signed-in account. The usage windows are
+/// percentages, so the plan says how large the underlying limits are.
+struct PlanInfo: Codable, Equatable {
+    /// Display name such as "Pro" or "Max 20x".
+    let name: String
+    let organization: String?
+}
+
 struct UsageMetric: Codable, Equatable {
     let percent: Double
     let resetsAt: Date
+    /// `resets_at` as the API sent it; nil for metrics from other sources.
+    let rawResetsAt: String?
+    /// False when `rawResetsAt` wasn't a valid timestamp, in which case
+    /// `resetsAt` is only the fetch time.
+    let hasResetTime: Bool
+    /// Percentage points per hour over recent refreshes; nil until there are
+    /// enough samples since the last reset.
+    let burnRatePerHour: Double?
+    /// When utilization reaches 100% at `burnRatePerHour`, if it's rising.
+    let projectedExhaustionAt: Date?
+    /// Expected utilization when the window resets at `burnRatePerHour`.
+    let projectedPercentAtReset: Double?
+
+    init(
+        percent: Double,
+        resetsAt: Date,
+        rawResetsAt: String? = nil,
+        hasResetTime: Bool = true,
+        burnRatePerHour: Double? = nil,
+        projectedExhaustionAt: Date? = nil,
+        projectedPercentAtReset: Double? = nil
+    ) {
+        self.percent = percent
+        self.resetsAt = resetsAt
+        self.rawResetsAt = rawResetsAt
+        self.hasResetTime = hasResetTime
+        self.burnRatePerHour = burnRatePerHour
+        self.projectedExhaustionAt = projectedExhaustionAt
+        self.projectedPercentAtReset = projectedPercentAtReset
+    }
+
+    init(from decoder: Decoder) throws {
+        let container = try decoder.container(keyedBy: CodingKeys.self)
+        percent = try container.decode(Double.self, forKey: .percent)
+        resetsAt = try container.decode(Date.self, forKey: .resetsAt)
+        rawResetsAt = try container.decodeIfPresent(String.self, forKey: .rawResetsAt)
+        hasResetTime = try container.decodeIfPresent(Bool.self, forKey: .hasResetTime) ?? true
+        burnRatePerHour = try container.decodeIfPresent(Double.self, forKey: .burnRatePerHour)
+        projectedExhaustionAt = try container.decodeIfPresent(Date.self, forKey: .projectedExhaustionAt)
+        projectedPercentAtReset = try container.decodeIfPresent(Double.self, forKey: .projectedPercentAtReset)
+    }
 
     var clampedPercent: Double {
         min(max(percent, 0.0), 100.0)
     }
+
+    /// Headroom left in the window, 0–100.
+    var remainingPercent: Double {
+        100.0 - clampedPercent
+    }
+
+    func status(thresholds: UsageStatusThresholds = .standard) -> UsageStatus {
+        UsageStatus.from(percent: clampedPercent, thresholds: thresholds)
+    }
+
+    /// Whole seconds until the window resets, zero once it has passed, and nil
+    /// when the reset time is unknown.
+    func secondsUntilReset(now: Date = Date()) -> Int? {
+        guard hasResetTime else { return nil }
+        return max(0, Int(resetsAt.timeIntervalSince(now)))
+    }
+
+    /// True once `resetsAt` is reached; false while the reset time is unknown.
+    func hasResetPassed(now: Date = Date()) -> Bool {
+        hasResetTime && resetsAt <= now
+    }
+
+    /// True when the projected exhaustion comes before the window resets.
+    var willExhaustBeforeReset: Bool {
+        guard let projectedExhaustionAt else { return false }
+        return !hasResetTime || projectedExhaustionAt < resetsAt
+    }
+
+    func withBurnProjection(_ projection: BurnProjection?) -> UsageMetric {
+        UsageMetric(
+            percent: percent,
+            resetsAt: resetsAt,
+            rawResetsAt: rawResetsAt,
+            hasResetTime: hasResetTime,
+            burnRatePerHour: projection?.ratePerHour,
+            projectedExhaustionAt: projection?.exhaustionAt,
+            projectedPercentAtReset: projection?.projectedAtReset
+        )
+    }
 }
 
 struct TokenStats: Codable, Equatable {
@@ -35,9 +171,126 @@ struct TokenStats: Codable, Equatable {
     let weekTokens: Int
     let todayMessages: Int
     let weekMessages: Int
+    /// Per-model totals keyed by model ID; they sum to `todayTokens` / `weekTokens`
+    /// when the source records a model for every entry.
+    let todayTokensByModel: [String: Int]
+    let weekTokensByModel: [String: Int]
+    /// Estimated USD cost; nil when no pricing table was applied.
+    let todayCost: Double?
+    let weekCost: Double?
+    /// Models with tokens this week but no price of their own, so they were
+    /// estimated at the default rate.
+    let unknownModels: [String]
+    /// When today's totals start over: the next midnight in the day-boundary
+    /// time zone. Nil when the source didn't say.
+    var dayEndsAt: Date?
+    /// Yesterday's totals; nil when the source has no entry for yesterday.
+    let yesterdayTokens: Int?
+    let yesterdayMessages: Int?
 
     static let zero = TokenStats(todayTokens: 0, weekTokens: 0, todayMessages: 0, weekMessages: 0)
 
+    init(
+        todayTokens: Int,
+        weekTokens: Int,
+        todayMessages: Int,
+        weekMessages: Int,
+        todayTokensByModel: [String: Int] = [:],
+        weekTokensByModel: [String: Int] = [:],
+        todayCost: Double? = nil,
+        weekCost: Double? = nil,
+        unknownModels: [String] = [],
+        dayEndsAt: Date? = nil,
+        yesterdayTokens: Int? = nil,
+        yesterdayMessages: Int? = nil
+    ) {
+        self.todayTokens = todayTokens
+        self.weekTokens = weekTokens
+        self.todayMessages = todayMessages
+        self.weekMessages = weekMessages
+        self.todayTokensByModel = todayTokensByModel
+        self.weekTokensByModel = weekTokensByModel
+        self.todayCost = todayCost
+        self.weekCost = weekCost
+        self.unknownModels = unknownModels
+        self.dayEndsAt = dayEndsAt
+        self.yesterdayTokens = yesterdayTokens
+        self.yesterdayMessages = yesterdayMessages
+    }
+
+    init(from decoder: Decoder) throws {
+        let container = try decoder.container(keyedBy: CodingKeys.self)
+        todayTokens = try container.decode(Int.self, forKey: .todayTokens)
+        weekTokens = try container.decode(Int.self, forKey: .weekTokens)
+        todayMessages = try container.decode(Int.self, forKey: .todayMessages)
+        weekMessages = try container.decode(Int.self, forKey: .weekMessages)
+        // Snapshots written before the per-model breakdown existed lack these keys.
+        todayTokensByModel = try container.decodeIfPresent([String: Int].self, forKey: .todayTokensByModel) ?? [:]
+        weekTokensByModel = try container.decodeIfPresent([String: Int].self, forKey: .weekTokensByModel) ?? [:]
+        todayCost = try container.decodeIfPresent(Double.self, forKey: .todayCost)
+        weekCost = try container.decodeIfPresent(Double.self, forKey: .weekCost)
+        unknownModels = try container.decodeIfPresent([String].self, forKey: .unknownModels) ?? []
+        dayEndsAt = try container.decodeIfPresent(Date.self, forKey: .dayEndsAt)
+        yesterdayTokens = try container.decodeIfPresent(Int.self, forKey: .yesterdayTokens)
+        yesterdayMessages = try container.decodeIfPresent(Int.self, forKey: .yesterdayMessages)
+    }
+
+    static let modelFamilies = ["opus", "sonnet", "haiku"]
+
+    /// Collapses a model ID to its family (`claude-sonnet-4-5-20250929` →
+    /// `sonnet`), giving charts stable keys across model releases. IDs outside
+    /// the known families are kept as they are.
+    static func modelFamily(_ modelID: String) -> String {
+        let id = modelID.lowercased()
+        return modelFamilies.first { id.contains($0) } ?? modelID
+    }
+
+    static func tokensByFamily(_ tokensByModel: [String: Int]) -> [String: Int] {
+        tokensByModel.reduce(into: [:]) { result, entry in
+            result[modelFamily(entry.key), default: 0] += entry.value
+        }
+    }
+
+    /// Raw model IDs stay in `todayTokensByModel` because pricing matches on them.
+    var todayTokensByFamily: [String: Int] {
+        Self.tokensByFamily(todayTokensByModel)
+    }
+
+    var weekTokensByFamily: [String: Int] {
+        Self.tokensByFamily(weekTokensByModel)
+    }
+
+    /// Copy with cost estimates from `pricing`; without a table the costs are cleared.
+    func withCosts(from pricing: PricingTable?) -> TokenStats {
+        let today = pricing?.estimateCost(todayTokensByModel)
+        let week = pricing?.estimateCost(weekTokensByModel)
+        return TokenStats(
+            todayTokens: todayTokens,
+            weekTokens: weekTokens,
+            todayMessages: todayMessages,
+            weekMessages: weekMessages,
+            todayTokensByModel: todayTokensByModel,
+            weekTokensByModel: weekTokensByModel,
+            todayCost: today?.cost,
+            weekCost: week?.cost,
+            unknownModels: week?.unknownModels ?? [],
+            dayEndsAt: dayEndsAt,
+            yesterdayTokens: yesterdayTokens,
+            yesterdayMessages: yesterdayMessages
+        )
+    }
+
+    /// Today's tokens relative to yesterday's, in percent (+25 means a
+    /// quarter more). Nil without a nonzero total for yesterday.
+    var tokenDeltaPercent: Double? {
+        guard let yesterdayTokens, yesterdayTokens > 0 else { return nil }
+        return Double(todayTokens - yesterdayTokens) / Double(yesterdayTokens) * 100
+    }
+
+    static func formatCost(_ value: Double) -> String {
+        value >= 100 ? String(format: "$%.0f", value) : String(format: "$%.2f", value)
+    }
+
     var formattedTodayTokens: String {
         Self.formatNumber(todayTokens)
     }
@@ -68,6 +321,7 @@ struct ProviderUsageSnapshot: Codable, Equatable {
     let lastUpdated: Date
     let lastSuccessfulUpdate: Date?
     let error: String?
+    let errorKind: UsageErrorKind?
 
     init(
         fiveHour: UsageMetric?,
@@ -78,7 +332,8 @@ struct ProviderUsageSnapshot: Codable, Equatable {
         tokenStats: TokenStats,
         lastUpdated: Date,
         lastSuccessfulUpdate: Date?,
-        error: String?
+        error: String?,
+        errorKind: UsageErrorKind? = nil
     ) {
         self.fiveHour = fiveHour
         self.sevenDay = sevenDay
@@ -89,13 +344,14 @@ struct ProviderUsageSnapshot: Codable, Equatable {
         self.lastUpdated = lastUpdated
         self.lastSuccessfulUpdate = lastSuccessfulUpdate
         self.error = error
+        self.errorKind = errorKind
     }
 
     var hasUsageData: Bool {
         fiveHour != nil || sevenDay != nil || extraMetric != nil
     }
 
-    func withError(_ message: String, tokenStats: TokenStats? = nil) -> ProviderUsageSnapshot {
+    func withError(_ message: String, kind: UsageErrorKind? = nil, tokenStats: TokenStats? = nil) -> ProviderUsageSnapshot {
         ProviderUsageSnapshot(
             fiveHour: fiveHour,
             sevenDay: sevenDay,
@@ -105,7 +361,8 @@ struct ProviderUsageSnapshot: Codable, Equatable {
             tokenStats: tokenStats ?? self.tokenStats,
             lastUpdated: Date(),
             lastSuccessfulUpdate: lastSuccessfulUpdate,
-            error: message
+            error: message,
+            errorKind: kind
         )
     }
 }
@@ -124,10 +381,105 @@ struct PaceSettings: Codable, Equatable {
     let enabledMetrics: Set<MetricKey>
 
     static let allEnabled = PaceSettings(enabledMetrics: Set(MetricKey.allCases))
+
+    /// Version written to pace-settings.json. Files without one are version 0.
+    static let schemaVersion = 1
+    static let schemaVersionKey = "schemaVersion"
+}
+
+extension PaceSettings {
+    /// Decodes a pace-settings file of any version, migrating it first.
+    static func decode(from data: Data) throws -> PaceSettings {
+        guard let json = try JSONSerialization.jsonObject(with: data) as? [String: Any] else {
+            throw DecodingError.dataCorrupted(.init(codingPath: [], debugDescription: "Pace settings aren't a JSON object"))
+        }
+        let migrated = try JSONSerialization.data(withJSONObject: migrate(json))
+        return try JSONDecoder().decode(PaceSettings.self, from: migrated)
+    }
+
+    /// The current schema, tagged with its version.
+    func encoded() throws -> Data {
+        let data = try JSONEncoder().encode(self)
+        guard var json = try JSONSerialization.jsonObject(with: data) as? [String: Any] else { return data }
+        json[Self.schemaVersionKey] = Self.schemaVersion
+        return try JSONSerialization.data(withJSONObject: json, options: .sortedKeys)
+    }
+
+    /// Runs each step from the file's version up to `schemaVersion`, so a
+    /// change to the format never drops choices saved by an older build.
+    static func migrate(_ json: [String: Any]) -> [String: Any] {
+        var json = json
+        var version = json[schemaVersionKey] as? Int ?? 0
+        while version < schemaVersion {
+            switch version {
+            case 0:
+                // Windows the app no longer knows are dropped instead of
+                // failing the whole decode.
+                let names = json["enabledMetrics"] as? [String] ?? MetricKey.allCases.map(\.rawValue)
+                json["enabledMetrics"] = names.filter { MetricKey(rawValue: $0) != nil }
+            default:
+                break
+            }
+            version += 1
+        }
+        json[schemaVersionKey] = version
+        return json
+    }
+}
+
+struct UsageSample: Equatable {
+    let date: Date
+    let percent: Double
+}
+
+/// Where utilization is heading, from recent refreshes of one window.
+struct BurnProjection: Equatable {
+    /// Percentage points per hour.
+    let ratePerHour: Double
+    /// When utilization reaches 100%; nil when it isn't rising or is already there.
+    let exhaustionAt: Date?
+    /// Expected utilization when the window resets, never below the current value.
+    let projectedAtReset: Double?
+
+    static let minimumSamples = 3
+    /// A fall of more than this many points between samples means the window reset.
+    static let resetDrop = 5.0
+}
+
+/// Least-squares trend of `samples` (oldest first) since the most recent
+/// reset. Nil with fewer than `BurnProjection.minimumSamples` samples.
+func computeBurnProjection(samples: [UsageSample], metric: UsageMetric, now: Date = .init()) -> BurnProjection? {
+    var recent: [UsageSample] = []
+    for sample in samples {
+        if let last = recent.last, last.percent - sample.percent > BurnProjection.resetDrop {
+            recent.removeAll()
+        }
+        recent.append(sample)
+    }
+    guard recent.count >= BurnProjection.minimumSamples, let first = recent.first else { return nil }
+
+    let hours = recent.map { $0.date.timeIntervalSince(first.date) / 3600 }
+    let meanHours = hours.reduce(0, +) / Double(hours.count)
+    let meanPercent = recent.map(\.percent).reduce(0, +) / Double(recent.count)
+    let variance = hours.map { ($0 - meanHours) * ($0 - meanHours) }.reduce(0, +)
+    guard variance > 0 else { return nil }
+    let covariance = zip(hours, recent).map { ($0 - meanHours) * ($1.percent - meanPercent) }.reduce(0, +)
+    let rate = covariance / variance
+
+    var exhaustionAt: Date?
+    if rate > 0, metric.percent < 100 {
+        exhaustionAt = now.addingTimeInterval((100 - metric.percent) / rate * 3600)
+    }
+    var projectedAtReset: Double?
+    if metric.hasResetTime {
+        let hoursLeft = max(0, metric.resetsAt.timeIntervalSince(now) / 3600)
+        projectedAtReset = max(metric.percent, metric.percent + rate * hoursLeft)
+    }
+    return BurnProjection(ratePerHour: rate, exhaustionAt: exhaustionAt, projectedAtReset: projectedAtReset)
 }
 
 func computePace(metric: UsageMetric, windowDuration: TimeInterval, now: Date = .init()) -> PaceInfo? {
-    guard windowDuration > 0 else { return nil }
+    guard windowDuration > 0, metric.hasResetTime else { return nil }
 
     let windowStart = metric.resetsAt.addingTimeInterval(-windowDuration)
     let elapsed = now.timeIntervalSince(windowStart)
@@ -162,6 +514,17 @@ struct UsageSnapshot: Codable, Equatable {
     let lastUpdated: Date
     let lastSuccessfulUpdate: Date?
     let error: String?
+    let errorKind: UsageErrorKind?
+    /// When the app will next call the API after backing off from repeated
+    /// failures; nil while refreshes run on the normal schedule.
+    let nextRetryAt: Date?
+    /// Nil until the profile has been fetched, or when the API doesn't expose it.
+    let plan: PlanInfo?
+    /// The thresholds the app colors bars with, so the widget agrees; nil in
+    /// snapshots written before they were configurable.
+    let statusThresholds: UsageStatusThresholds?
+    /// True for made-up numbers from demo mode; nil in real snapshots.
+    let isDemo: Bool?
 
     init(
         fiveHour: UsageMetric?,
@@ -172,7 +535,12 @@ struct UsageSnapshot: Codable, Equatable {
         tokenStats: TokenStats,
         lastUpdated: Date,
         lastSuccessfulUpdate: Date?,
-        error: String?
+        error: String?,
+        errorKind: UsageErrorKind? = nil,
+        nextRetryAt: Date? = nil,
+        plan: PlanInfo? = nil,
+        statusThresholds: UsageStatusThresholds? = nil,
+        isDemo: Bool? = nil
     ) {
         self.fiveHour = fiveHour
         self.sevenDay = sevenDay
@@ -183,6 +551,35 @@ struct UsageSnapshot: Codable, Equatable {
         self.lastUpdated = lastUpdated
         self.lastSuccessfulUpdate = lastSuccessfulUpdate
         self.error = error
+        self.errorKind = errorKind
+        self.nextRetryAt = nextRetryAt
+        self.plan = plan
+        self.statusThresholds = statusThresholds
+        self.isDemo = isDemo
+    }
+
+    /// Status of one of this snapshot's metrics against its thresholds.
+    func status(of metric: UsageMetric) -> UsageStatus {
+        metric.status(thresholds: statusThresholds ?? .standard)
+    }
+
+    func withStatusThresholds(_ thresholds: UsageStatusThresholds) -> UsageSnapshot {
+        UsageSnapshot(
+            fiveHour: fiveHour,
+            sevenDay: sevenDay,
+            sevenDaySonnet: sevenDaySonnet,
+            sevenDayOpus: sevenDayOpus,
+            codex: codex,
+            tokenStats: tokenStats,
+            lastUpdated: lastUpdated,
+            lastSuccessfulUpdate: lastSuccessfulUpdate,
+            error: error,
+            errorKind: errorKind,
+            nextRetryAt: nextRetryAt,
+            plan: plan,
+            statusThresholds: thresholds,
+            isDemo: isDemo
+        )
     }
 
     var maxUsagePercent: Double? {
@@ -205,6 +602,25 @@ struct UsageSnapshot: Codable, Equatable {
         fiveHour != nil || sevenDay != nil || sevenDaySonnet != nil || sevenDayOpus != nil || (codex?.hasUsageData ?? false)
     }
 
+    /// When the usage on screen was fetched, if the latest refresh failed and
+    /// it's being kept as last-known data; nil while the data is current.
+    var staleSince: Date? {
+        guard error != nil, hasUsageData else { return nil }
+        return lastSuccessfulUpdate
+    }
+
+    /// Seconds since the kept data was fetched; nil while the data is current.
+    func staleAge(now: Date = Date()) -> TimeInterval? {
+        staleSince.map { max(0, now.timeIntervalSince($0)) }
+    }
+
+    /// Whether the API sent a per-model weekly window. Plans without them get
+    /// neither, and their bars are hidden instead of shown empty; a nil
+    /// window on a plan that has them means no data, not zero usage.
+    var reportsModelWindows: Bool {
+        sevenDaySonnet != nil || sevenDayOpus != nil
+    }
+
     var hasCodexData: Bool {
         codex?.hasUsageData ?? false
     }
@@ -265,7 +681,7 @@ struct UsageSnapshot: Codable, Equatable {
         return messages
     }
 
-    func withError(_ message: String, tokenStats: TokenStats? = nil) -> UsageSnapshot {
+    func withError(_ message: String, kind: UsageErrorKind? = nil, tokenStats: TokenStats? = nil, nextRetryAt: Date? = nil) -> UsageSnapshot {
         UsageSnapshot(
             fiveHour: fiveHour,
             sevenDay: sevenDay,
@@ -275,10 +691,115 @@ struct UsageSnapshot: Codable, Equatable {
             tokenStats: tokenStats ?? self.tokenStats,
             lastUpdated: Date(),
             lastSuccessfulUpdate: lastSuccessfulUpdate,
-            error: message
+            error: message,
+            errorKind: kind,
+            nextRetryAt: nextRetryAt,
+            plan: plan,
+            statusThresholds: statusThresholds,
+            isDemo: isDemo
         )
     }
 
+    /// Same snapshot with the Claude usage, plan and last-success time
+    /// removed. Used on account switch so the previous account's numbers are
+    /// never shown as stale data for the new one; Codex data is kept.
+    func withoutClaudeUsage() -> UsageSnapshot {
+        UsageSnapshot(
+            fiveHour: nil,
+            sevenDay: nil,
+            sevenDaySonnet: nil,
+            sevenDayOpus: nil,
+            codex: codex,
+            tokenStats: tokenStats,
+            lastUpdated: lastUpdated,
+            lastSuccessfulUpdate: nil,
+            error: nil,
+            errorKind: nil,
+            nextRetryAt: nil,
+            plan: nil,
+            statusThresholds: statusThresholds,
+            isDemo: isDemo
+        )
+    }
+
+    /// The Claude windows present in this snapshot.
+    var claudeMetrics: [MetricKey: UsageMetric] {
+        let metrics: [MetricKey: UsageMetric?] = [
+            .fiveHour: fiveHour,
+            .sevenDay: sevenDay,
+            .sevenDaySonnet: sevenDaySonnet,
+            .sevenDayOpus: sevenDayOpus
+        ]
+        return metrics.compactMapValues { $0 }
+    }
+
+    /// Same snapshot with each Claude metric's burn projection set from
+    /// `projections`; metrics without one have it cleared.
+    func withBurnProjections(_ projections: [MetricKey: BurnProjection]) -> UsageSnapshot {
+        UsageSnapshot(
+            fiveHour: fiveHour?.withBurnProjection(projections[.fiveHour]),
+            sevenDay: sevenDay?.withBurnProjection(projections[.sevenDay]),
+            sevenDaySonnet: sevenDaySonnet?.withBurnProjection(projections[.sevenDaySonnet]),
+            sevenDayOpus: sevenDayOpus?.withBurnProjection(projections[.sevenDayOpus]),
+            codex: codex,
+            tokenStats: tokenStats,
+            lastUpdated: lastUpdated,
+            lastSuccessfulUpdate: lastSuccessfulUpdate,
+            error: error,
+            errorKind: errorKind,
+            nextRetryAt: nextRetryAt,
+            plan: plan,
+            statusThresholds: statusThresholds,
+            isDemo: isDemo
+        )
+    }
+
+    /// Same snapshot with fresh local token stats; usage and error state are untouched.
+    func withTokenStats(_ tokenStats: TokenStats) -> UsageSnapshot {
+        UsageSnapshot(
+            fiveHour: fiveHour,
+            sevenDay: sevenDay,
+            sevenDaySonnet: sevenDaySonnet,
+            sevenDayOpus: sevenDayOpus,
+            codex: codex,
+            tokenStats: tokenStats,
+            lastUpdated: lastUpdated,
+            lastSuccessfulUpdate: lastSuccessfulUpdate,
+            error: error,
+            errorKind: errorKind,
+            nextRetryAt: nextRetryAt,
+            plan: plan,
+            statusThresholds: statusThresholds,
+            isDemo: isDemo
+        )
+    }
+
+    /// "Retrying in 45s" while backing off, nil otherwise.
+    func retryStatus(now: Date = Date()) -> String? {
+        guard let nextRetryAt, nextRetryAt > now else { return nil }
+        let seconds = nextRetryAt.timeIntervalSince(now)
+        if seconds < 60 {
+            return "Retrying in \(Int(seconds.rounded(.up)))s"
+        }
+        return "Retrying in \(Int((seconds / 60).rounded(.up))) min"
+    }
+
+    /// `date` as a medium date and short time in `timeZone`, with the zone's
+    /// abbreviation appended when it isn't the Mac's own.
+    static func displayTime(_ date: Date, in timeZone: TimeZone, locale: Locale = .current) -> String {
+        let formatter = DateFormatter()
+        formatter.locale = locale
+        formatter.timeZone = timeZone
+        formatter.dateStyle = .medium
+        formatter.timeStyle = .short
+        let text = formatter.string(from: date)
+        guard timeZone.identifier != TimeZone.current.identifier,
+              let abbreviation = timeZone.abbreviation(for: date) else {
+            return text
+        }
+        return "\(text) \(abbreviation)"
+    }
+
     /// Canonical encoder — always uses iso8601 dates for interoperability
     /// between the main app and the widget extension.
     static func makeEncoder() -> JSONEncoder {
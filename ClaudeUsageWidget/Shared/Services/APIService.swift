@@ -1,19 +1,407 @@
 import Foundation
 
+struct RetryPolicy: Equatable {
+    let maxRetries: Int
+    let baseDelay: TimeInterval
+
+    static let `default` = RetryPolicy(maxRetries: 3, baseDelay: 0.5)
+    static let none = RetryPolicy(maxRetries: 0, baseDelay: 0)
+
+    /// Exponential backoff: `baseDelay * 2^retry`, stretched by `jitterFraction`
+    /// so clients that failed together don't retry in lockstep.
+    func delay(forRetry retry: Int, jitterFraction: Double) -> TimeInterval {
+        let exponential = baseDelay * pow(2, Double(retry))
+        return exponential * (1 + jitterFraction)
+    }
+}
+
+/// Backoff that spans refresh cycles, so an outage that outlasts the
+/// in-request retries doesn't get hit again on every timer tick. The first
+/// transient failure is retried on the next cycle as usual; each further
+/// consecutive failure doubles the pause, up to `maxDelay`. A `Retry-After`
+/// from the server always wins.
+struct RefreshBackoff: Equatable {
+    static let baseDelay: TimeInterval = 60
+    static let maxDelay: TimeInterval = 3600
+
+    private(set) var consecutiveFailures = 0
+    private(set) var retryAt: Date?
+    /// True when `retryAt` came from a `Retry-After` header rather than our own schedule.
+    private(set) var isServerMandated = false
+
+    func isBackingOff(now: Date = Date()) -> Bool {
+        guard let retryAt else { return false }
+        return retryAt > now
+    }
+
+    mutating func recordFailure(_ error: Error, now: Date = Date(), jitterFraction: Double = .random(in: 0...0.2)) {
+        if case APIError.rateLimited(let retryAfter?) = error {
+            consecutiveFailures += 1
+            retryAt = now.addingTimeInterval(retryAfter)
+            isServerMandated = true
+            return
+        }
+        guard Self.isTransient(error) else { return }
+
+        consecutiveFailures += 1
+        isServerMandated = false
+        guard consecutiveFailures > 1 else {
+            retryAt = nil
+            return
+        }
+        let exponential = Self.baseDelay * pow(2, Double(consecutiveFailures - 2))
+        retryAt = now.addingTimeInterval(min(exponential * (1 + jitterFraction), Self.maxDelay))
+    }
+
+    mutating func reset() {
+        self = RefreshBackoff()
+    }
+
+    static func isTransient(_ error: Error) -> Bool {
+        if case APIError.rateLimited = error { return true }
+        return APIService.isRetryable(error)
+    }
+}
+
+/// The URLSession every service shares, so connections and TLS sessions are
+/// reused across refreshes and a hung endpoint can't stall a refresh forever.
+/// Rebuilt when the proxy or timeout setting changes. While the configured proxy is
+/// malformed, `current()` throws so nothing goes out over a direct connection.
+final class SharedSession: @unchecked Sendable {
+    static let shared = SharedSession()
+
+    private let lock = NSLock()
+    private var session: URLSession
+    private(set) var proxy: ProxyConfiguration?
+    private(set) var requestTimeout: TimeInterval
+    private var proxyError: ProxyError?
+
+    init(proxy: ProxyConfiguration? = nil, requestTimeout: TimeInterval = URLSessionConfiguration.defaultRequestTimeout) {
+        self.proxy = proxy
+        self.requestTimeout = requestTimeout
+        self.session = Self.makeSession(proxy: proxy, requestTimeout: requestTimeout)
+    }
+
+    private static func makeSession(proxy: ProxyConfiguration?, requestTimeout: TimeInterval) -> URLSession {
+        URLSession(
+            configuration: .widget(proxy: proxy, requestTimeout: requestTimeout),
+            delegate: ProxyAuthenticationDelegate(proxy: proxy),
+            delegateQueue: nil
+        )
+    }
+
+    func current() throws -> URLSession {
+        lock.lock()
+        defer { lock.unlock() }
+        if let proxyError {
+            throw APIError.invalidProxy(proxyError.localizedDescription)
+        }
+        return session
+    }
+
+    /// Blocks requests until a valid configuration is applied.
+    func reject(_ error: ProxyError) {
+        lock.lock()
+        proxyError = error
+        lock.unlock()
+        DebugLogger.shared.log("Network requests blocked: \(error.localizedDescription)", level: .warning, source: "API")
+    }
+
+    /// Swaps in a session using `proxy` and `requestTimeout`; requests already
+    /// in flight finish on the old one.
+    func reconfigure(proxy: ProxyConfiguration?, requestTimeout: TimeInterval = URLSessionConfiguration.defaultRequestTimeout) {
+        lock.lock()
+        defer { lock.unlock() }
+        proxyError = nil
+        guard proxy != self.proxy || requestTimeout != self.requestTimeout else { return }
+        session.finishTasksAndInvalidate()
+        session = Self.makeSession(proxy: proxy, requestTimeout: requestTimeout)
+        self.proxy = proxy
+        self.requestTimeout = requestTimeout
+        DebugLogger.shared.log("Network session rebuilt (proxy: \(proxy.map(String.init(describing:)) ?? "system"), timeout: \(Int(requestTimeout))s)", source: "API")
+    }
+}
+
+/// Answers the proxy's 407 challenge with the configured credentials, once;
+/// if they're rejected the request fails with the proxy's error. Everything
+/// else gets the default handling.
+final class ProxyAuthenticationDelegate: NSObject, URLSessionTaskDelegate {
+    private let proxy: ProxyConfiguration?
+
+    init(proxy: ProxyConfiguration?) {
+        self.proxy = proxy
+    }
+
+    func urlSession(
+        _ session: URLSession,
+        task: URLSessionTask,
+        didReceive challenge: URLAuthenticationChallenge
+    ) async -> (URLSession.AuthChallengeDisposition, URLCredential?) {
+        guard challenge.previousFailureCount == 0,
+              let credential = proxy?.credential(for: challenge.protectionSpace) else {
+            return (.performDefaultHandling, nil)
+        }
+        return (.useCredential, credential)
+    }
+}
+
+extension URLSessionConfiguration {
+    static let defaultRequestTimeout: TimeInterval = 15
+    static let connectTimeout: TimeInterval = 10
+
+    /// `requestTimeout` caps the whole request. Connecting, and each wait for
+    /// more data, gets at most `connectTimeout` so a dead host fails early.
+    static func widget(proxy: ProxyConfiguration? = nil, requestTimeout: TimeInterval = defaultRequestTimeout) -> URLSessionConfiguration {
+        let config = URLSessionConfiguration.default
+        config.timeoutIntervalForRequest = min(connectTimeout, requestTimeout)
+        config.timeoutIntervalForResource = requestTimeout
+        config.httpAdditionalHeaders = ["User-Agent": userAgent]
+        if let proxy {
+            config.connectionProxyDictionary = proxy.connectionProxyDictionary
+        }
+        return config
+    }
+
+    static var userAgent: String {
+        let version = Bundle.main.infoDictionary?["CFBundleShortVersionString"] as? String ?? "dev"
+        return "claude-usage-widget/\(version)"
+    }
+}
+
+extension URLSession {
+    /// `data(for:)` with transport failures mapped to `APIError`. A timeout
+    /// names the limit that was hit: the total one once it has elapsed,
+    /// otherwise the connect/idle one.
+    func widgetData(for request: URLRequest) async throws -> (Data, URLResponse) {
+        let started = Date()
+        do {
+            return try await data(for: request)
+        } catch let error as URLError where error.code != .cancelled {
+            guard error.code == .timedOut else { throw APIError(error) }
+            let elapsed = Date().timeIntervalSince(started)
+            let limit = elapsed >= configuration.timeoutIntervalForResource
+                ? configuration.timeoutIntervalForResource
+                : configuration.timeoutIntervalForRequest
+            throw APIError.timedOut(after: limit)
+        }
+    }
+}
+
+extension APIError {
+    static let timedOutPrefix = "Request timed out"
+
+    /// Keeps timeouts distinguishable from other connection failures in the UI.
+    static func timedOut(after seconds: TimeInterval) -> APIError {
+        .networkError("\(timedOutPrefix) after \(Self.formatSeconds(seconds))")
+    }
+
+    init(_ error: URLError) {
+        self = .networkError(error.code == .timedOut ? Self.timedOutPrefix : error.localizedDescription)
+    }
+
+    private static func formatSeconds(_ seconds: TimeInterval) -> String {
+        seconds >= 1 ? "\(Int(seconds.rounded()))s" : String(format: "%.1fs", seconds)
+    }
+}
+
 final class APIService: APIServiceProtocol {
-    private let session: URLSession
-    private let baseURL = URL(string: "https://api.anthropic.com/api/oauth/usage")!
+    static let defaultBaseURL = URL(string: "https://api.anthropic.com")!
+    static let usagePath = "api/oauth/usage"
+    static let profilePath = "api/oauth/profile"
+    static let defaultCacheTTL: TimeInterval = 30
 
-    init(session: URLSession = .shared) {
+    private let session: URLSession?
+    private let baseURL: () throws -> URL
+    private let retryPolicy: () -> RetryPolicy
+    private let cacheTTL: () -> TimeInterval
+    private let now: () -> Date
+    private let sleep: (TimeInterval) async throws -> Void
+    private let cacheLock = NSLock()
+    private var cachedUsage: CachedUsage?
+
+    /// Without an explicit `session`, each request uses `SharedSession.shared`
+    /// so a proxy change takes effect on the next refresh. `baseURL`,
+    /// `retryPolicy` and `cacheTTL` are also read per request, so changes
+    /// from Settings apply without rebuilding the service.
+    ///
+    /// The last good response is kept and revalidated with `If-None-Match` /
+    /// `If-Modified-Since`. When the API sent neither validator, it is reused
+    /// without a request for `cacheTTL` seconds instead, unless the caller
+    /// asks for a fresh one.
+    init(
+        session: URLSession? = nil,
+        baseURL: @escaping () throws -> URL = { APIService.defaultBaseURL },
+        retryPolicy: @escaping () -> RetryPolicy = { .default },
+        cacheTTL: @escaping () -> TimeInterval = { APIService.defaultCacheTTL },
+        now: @escaping () -> Date = Date.init,
+        sleep: @escaping (TimeInterval) async throws -> Void = { try await Task.sleep(nanoseconds: UInt64($0 * 1_000_000_000)) }
+    ) {
         self.session = session
+        self.baseURL = baseURL
+        self.retryPolicy = retryPolicy
+        self.cacheTTL = cacheTTL
+        self.now = now
+        self.sleep = sleep
     }
 
-    func fetchUsage(token: String) async throws -> UsageApiResponse {
-        var request = URLRequest(url: baseURL)
+    func fetchUsage(token: String, allowingCached: Bool) async throws -> UsageApiResponse {
+        let url = Self.usageURL(baseURL: try baseURL())
+        let retryPolicy = self.retryPolicy()
+        let cached = cachedEntry(url: url, token: token)
+        if allowingCached, let cached, !cached.hasValidators, now().timeIntervalSince(cached.fetchedAt) < cacheTTL() {
+            DebugLogger.shared.log("Serving cached usage (\(Int(now().timeIntervalSince(cached.fetchedAt)))s old)", level: .debug, source: "API")
+            return cached.response
+        }
+
+        var retry = 0
+        while true {
+            do {
+                return try await fetchUsageOnce(url: url, token: token, cached: cached)
+            } catch {
+                guard retry < retryPolicy.maxRetries, Self.isRetryable(error) else { throw error }
+                var delay = retryPolicy.delay(forRetry: retry, jitterFraction: Double.random(in: 0...0.2))
+                if case APIError.rateLimited(let retryAfter?) = error {
+                    delay = retryAfter
+                }
+                retry += 1
+                DebugLogger.shared.log("Usage request failed (\(error)); retry \(retry)/\(retryPolicy.maxRetries) in \(String(format: "%.1f", delay))s", level: .warning, source: "API")
+                try await sleep(delay)
+            }
+        }
+    }
+
+    /// Appends the usage endpoint path, so a gateway mounted under a prefix
+    /// (`https://gateway.corp/anthropic`) keeps that prefix.
+    static func usageURL(baseURL: URL) -> URL {
+        baseURL.appendingPathComponent(usagePath)
+    }
+
+    /// Single attempt without caching; callers fetch the plan once per token.
+    func fetchProfile(token: String) async throws -> PlanInfo? {
+        var request = URLRequest(url: try baseURL().appendingPathComponent(Self.profilePath))
+        request.setValue("Bearer \(token)", forHTTPHeaderField: "Authorization")
+        request.setValue("oauth-2025-04-20", forHTTPHeaderField: "anthropic-beta")
+
+        let (data, response) = try await (session ?? SharedSession.shared.current()).widgetData(for: request)
+
+        guard let httpResponse = response as? HTTPURLResponse else {
+            throw APIError.networkError("Invalid response")
+        }
+
+        switch httpResponse.statusCode {
+        case 200:
+            break
+        case 404:
+            DebugLogger.shared.log("Profile endpoint not available (404)", source: "API")
+            return nil
+        case 401:
+            throw APIError.unauthorized
+        case 403:
+            throw APIError.forbidden
+        case 429:
+            throw APIError.rateLimited(retryAfter: Self.parseRetryAfter(httpResponse.value(forHTTPHeaderField: "Retry-After"), now: now()))
+        default:
+            throw APIError.serverError(httpResponse.statusCode)
+        }
+
+        let decoder = JSONDecoder()
+        decoder.keyDecodingStrategy = .convertFromSnakeCase
+        do {
+            return try decoder.decode(ProfileApiResponse.self, from: data).toPlanInfo()
+        } catch {
+            throw APIError.decodingError(error.localizedDescription)
+        }
+    }
+
+    /// Single unauthenticated `HEAD` of the base URL, without retries.
+    func probeReachability() async throws -> Int {
+        var request = URLRequest(url: try baseURL())
+        request.httpMethod = "HEAD"
+
+        let (_, response) = try await (session ?? SharedSession.shared.current()).widgetData(for: request)
+        guard let httpResponse = response as? HTTPURLResponse else {
+            throw APIError.networkError("Invalid response")
+        }
+        return httpResponse.statusCode
+    }
+
+    /// A 429 whose `Retry-After` is at most this long is waited out in place;
+    /// longer pauses are left to the refresh-cycle backoff.
+    static let maxInlineRetryAfter: TimeInterval = 5
+
+    /// Connection failures, 5xx responses and briefly rate-limited requests are
+    /// transient; other 4xx responses are not.
+    static func isRetryable(_ error: Error) -> Bool {
+        switch error {
+        case APIError.networkError:
+            return true
+        case APIError.rateLimited(let retryAfter?):
+            return retryAfter <= maxInlineRetryAfter
+        case APIError.serverError(let code):
+            return (500...599).contains(code)
+        default:
+            return false
+        }
+    }
+
+    /// `Retry-After` is either delta-seconds (`120`) or an HTTP-date
+    /// (`Wed, 21 Oct 2026 07:28:00 GMT`). Dates in the past yield zero.
+    static func parseRetryAfter(_ value: String?, now: Date = Date()) -> TimeInterval? {
+        guard let value = value?.trimmingCharacters(in: .whitespaces), !value.isEmpty else { return nil }
+        if let seconds = Int(value) {
+            return seconds >= 0 ? TimeInterval(seconds) : nil
+        }
+        guard let date = httpDateFormatter.date(from: value) else { return nil }
+        return max(0, date.timeIntervalSince(now))
+    }
+
+    /// Fallback when `Retry-After` is missing: the latest of the
+    /// `anthropic-ratelimit-*-reset` headers, which carry an RFC 3339
+    /// timestamp (or epoch seconds) for when each limit refills.
+    static func parseRateLimitReset(_ headers: [AnyHashable: Any], now: Date = Date()) -> TimeInterval? {
+        let resets = headers.compactMap { key, value -> Date? in
+            guard let name = (key as? String)?.lowercased(),
+                  name.hasPrefix("anthropic-ratelimit-"), name.hasSuffix("-reset"),
+                  let value = (value as? String)?.trimmingCharacters(in: .whitespaces) else { return nil }
+            if let date = rfc3339Formatter.date(from: value) ?? rfc3339FractionalFormatter.date(from: value) {
+                return date
+            }
+            return TimeInterval(value).map(Date.init(timeIntervalSince1970:))
+        }
+        guard let latest = resets.max() else { return nil }
+        return max(0, latest.timeIntervalSince(now))
+    }
+
+    private static let rfc3339Formatter = ISO8601DateFormatter()
+
+    private static let rfc3339FractionalFormatter: ISO8601DateFormatter = {
+        let formatter = ISO8601DateFormatter()
+        formatter.formatOptions = [.withInternetDateTime, .withFractionalSeconds]
+        return formatter
+    }()
+
+    private static let httpDateFormatter: DateFormatter = {
+        let formatter = DateFormatter()
+        formatter.locale = Locale(identifier: "en_US_POSIX")
+        formatter.timeZone = TimeZone(identifier: "GMT")
+        formatter.dateFormat = "EEE, dd MMM yyyy HH:mm:ss zzz"
+        return formatter
+    }()
+
+    private func fetchUsageOnce(url: URL, token: String, cached: CachedUsage?) async throws -> UsageApiResponse {
+        var request = URLRequest(url: url)
         request.setValue("Bearer \(token)", forHTTPHeaderField: "Authorization")
         request.setValue("oauth-2025-04-20", forHTTPHeaderField: "anthropic-beta")
+        // Validators are managed here, so keep URLCache from answering on its own.
+        request.cachePolicy = .reloadIgnoringLocalCacheData
+        if let etag = cached?.etag {
+            request.setValue(etag, forHTTPHeaderField: "If-None-Match")
+        }
+        if let lastModified = cached?.lastModified {
+            request.setValue(lastModified, forHTTPHeaderField: "If-Modified-Since")
+        }
 
-        let (data, response) = try await session.data(for: request)
+        let (data, response) = try await (session ?? SharedSession.shared.current()).widgetData(for: request)
 
         guard let httpResponse = response as? HTTPURLResponse else {
             throw APIError.networkError("Invalid response")
@@ -22,10 +410,110 @@ final class APIService: APIServiceProtocol {
         switch httpResponse.statusCode {
         case 200:
             break
+        case 304:
+            guard let cached else { throw APIError.serverError(304) }
+            storeCachedUsage(cached.refreshed(at: now()))
+            DebugLogger.shared.log("Usage not modified; reusing cached response", level: .debug, source: "API")
+            return cached.response
         case 401:
             throw APIError.unauthorized
         case 403:
             throw APIError.forbidden
+        case 429:
+            let retryAfter = Self.parseRetryAfter(httpResponse.value(forHTTPHeaderField: "Retry-After"), now: now())
+                ?? Self.parseRateLimitReset(httpResponse.allHeaderFields, now: now())
+            throw APIError.rateLimited(retryAfter: retryAfter)
+        default:
+            throw APIError.serverError(httpResponse.statusCode)
+        }
+
+        let decoder = JSONDecoder()
+        decoder.keyDecodingStrategy = .convertFromSnakeCase
+        let usage: UsageApiResponse
+        do {
+            usage = try decoder.decode(UsageApiResponse.self, from: data)
+        } catch {
+            throw APIError.decodingError(error.localizedDescription)
+        }
+        storeCachedUsage(CachedUsage(
+            url: url,
+            token: token,
+            response: usage,
+            etag: httpResponse.value(forHTTPHeaderField: "ETag"),
+            lastModified: httpResponse.value(forHTTPHeaderField: "Last-Modified"),
+            fetchedAt: now()
+        ))
+        return usage
+    }
+
+    private func cachedEntry(url: URL, token: String) -> CachedUsage? {
+        cacheLock.lock()
+        defer { cacheLock.unlock() }
+        guard let cachedUsage, cachedUsage.url == url, cachedUsage.token == token else { return nil }
+        return cachedUsage
+    }
+
+    private func storeCachedUsage(_ entry: CachedUsage) {
+        cacheLock.lock()
+        cachedUsage = entry
+        cacheLock.unlock()
+    }
+}
+
+/// Last good usage response for one endpoint and token, with its validators.
+private struct CachedUsage {
+    let url: URL
+    let token: String
+    let response: UsageApiResponse
+    let etag: String?
+    let lastModified: String?
+    let fetchedAt: Date
+
+    var hasValidators: Bool {
+        etag != nil || lastModified != nil
+    }
+
+    func refreshed(at date: Date) -> CachedUsage {
+        CachedUsage(url: url, token: token, response: response, etag: etag, lastModified: lastModified, fetchedAt: date)
+    }
+}
+
+/// Exchanges the refresh token Claude Code stored alongside the access token
+/// for a new access token. Renewed credentials are only kept in memory.
+final class TokenRefreshService: TokenRefreshServiceProtocol {
+    static let clientID = "9d1c250a-e61b-44d9-88ed-5944d1962f5e"
+
+    private let session: URLSession?
+    private let tokenURL = URL(string: "https://console.anthropic.com/v1/oauth/token")!
+
+    init(session: URLSession? = nil) {
+        self.session = session
+    }
+
+    func refresh(refreshToken: String) async throws -> OAuthCredentials {
+        var request = URLRequest(url: tokenURL)
+        request.httpMethod = "POST"
+        request.setValue("application/json", forHTTPHeaderField: "Content-Type")
+        request.httpBody = try JSONSerialization.data(withJSONObject: [
+            "grant_type": "refresh_token",
+            "refresh_token": refreshToken,
+            "client_id": Self.clientID
+        ])
+
+        let (data, response) = try await (session ?? SharedSession.shared.current()).widgetData(for: request)
+
+        guard let httpResponse = response as? HTTPURLResponse else {
+            throw APIError.networkError("Invalid response")
+        }
+
+        switch httpResponse.statusCode {
+        case 200:
+            break
+        case 400, 401:
+            // invalid_grant: the refresh token was revoked or already rotated.
+            throw APIError.unauthorized
+        case 403:
+            throw APIError.forbidden
         default:
             throw APIError.serverError(httpResponse.statusCode)
         }
@@ -33,7 +521,8 @@ final class APIService: APIServiceProtocol {
         let decoder = JSONDecoder()
         decoder.keyDecodingStrategy = .convertFromSnakeCase
         do {
-            return try decoder.decode(UsageApiResponse.self, from: data)
+            return try decoder.decode(OAuthTokenResponse.self, from: data)
+                .toCredentials(previousRefreshToken: refreshToken)
         } catch {
             throw APIError.decodingError(error.localizedDescription)
         }
@@ -84,10 +573,10 @@ final class CodexAuthService: CodexAuthServiceProtocol {
 }
 
 final class CodexAPIService: CodexAPIServiceProtocol {
-    private let session: URLSession
+    private let session: URLSession?
     private let baseURL = URL(string: "https://chatgpt.com/backend-api/wham/usage")!
 
-    init(session: URLSession = .shared) {
+    init(session: URLSession? = nil) {
         self.session = session
     }
 
@@ -96,7 +585,7 @@ final class CodexAPIService: CodexAPIServiceProtocol {
         request.setValue("Bearer \(credentials.accessToken)", forHTTPHeaderField: "Authorization")
         request.setValue(credentials.accountID, forHTTPHeaderField: "ChatGPT-Account-Id")
 
-        let (data, response) = try await session.data(for: request)
+        let (data, response) = try await (session ?? SharedSession.shared.current()).widgetData(for: request)
 
         guard let httpResponse = response as? HTTPURLResponse else {
             throw APIError.networkError("Invalid response")
@@ -1,30 +1,68 @@
 import Foundation
 
+/// Severity of a log line; anything less severe than the configured level is dropped.
+enum LogLevel: String, CaseIterable, Comparable {
+    case error
+    case warning
+    case info
+    case debug
+    case trace
+
+    static func < (lhs: LogLevel, rhs: LogLevel) -> Bool {
+        lhs.verbosity < rhs.verbosity
+    }
+
+    private var verbosity: Int {
+        Self.allCases.firstIndex(of: self) ?? 0
+    }
+}
+
 /// File-based debug logger that both the app and widget extension can write to
 /// via the shared app group container. View logs in Console.app or in the app's debug panel.
 final class DebugLogger {
     static let shared = DebugLogger()
+    /// Stored in the app group defaults so the widget extension honors it too.
+    static let levelKey = "logLevel"
+    static let defaultLevel = LogLevel.info
 
     private let fileURL: URL?
+    private let defaults: UserDefaults?
     private let queue = DispatchQueue(label: "com.andywendt.claude-usage-widget.debug-log")
     private let maxLines = 200
 
-    private init() {
-        if let containerURL = FileManager.default.containerURL(
+    private convenience init() {
+        let containerURL = FileManager.default.containerURL(
             forSecurityApplicationGroupIdentifier: SharedContainerService.appGroupID
-        ) {
-            self.fileURL = containerURL.appendingPathComponent("debug.log")
-        } else {
-            self.fileURL = nil
+        )
+        if containerURL == nil {
             NSLog("[DebugLogger] WARNING: App group container not available — logs will only go to NSLog")
         }
+        self.init(
+            fileURL: containerURL?.appendingPathComponent("debug.log"),
+            defaults: UserDefaults(suiteName: SharedContainerService.appGroupID)
+        )
+    }
+
+    init(fileURL: URL?, defaults: UserDefaults?) {
+        self.fileURL = fileURL
+        self.defaults = defaults
     }
 
-    func log(_ message: String, source: String = "Unknown") {
+    var minimumLevel: LogLevel {
+        get { defaults?.string(forKey: Self.levelKey).flatMap(LogLevel.init(rawValue:)) ?? Self.defaultLevel }
+        set { defaults?.set(newValue.rawValue, forKey: Self.levelKey) }
+    }
+
+    func isEnabled(_ level: LogLevel) -> Bool {
+        level <= minimumLevel
+    }
+
+    func log(_ message: String, level: LogLevel = .info, source: String = "Unknown") {
+        guard isEnabled(level) else { return }
         let timestamp = ISO8601DateFormatter().string(from: Date())
         let pid = ProcessInfo.processInfo.processIdentifier
         let processName = ProcessInfo.processInfo.processName
-        let line = "[\(timestamp)] [\(processName)/\(pid)] [\(source)] \(message)"
+        let line = "[\(timestamp)] [\(processName)/\(pid)] [\(level.rawValue.uppercased())] [\(source)] \(Self.redact(message))"
 
         // Always log to system log too
         NSLog("%@", line)
@@ -49,43 +87,68 @@ final class DebugLogger {
         }
     }
 
+    /// Masks anything shaped like a credential, whatever the level, so a
+    /// stray interpolation can't put a token in the log.
+    static func redact(_ message: String) -> String {
+        redactionPatterns.reduce(message) { text, regex in
+            regex.stringByReplacingMatches(
+                in: text,
+                range: NSRange(text.startIndex..., in: text),
+                withTemplate: "$1<redacted>"
+            )
+        }
+    }
+
+    private static let redactionPatterns: [NSRegularExpression] = [
+        // Anthropic keys and OAuth tokens (sk-ant-oat01-…); the type prefix is kept.
+        #"(sk-ant-[a-z]+[0-9]*-)[A-Za-z0-9_\-]+"#,
+        #"(Bearer\s+)[^\s"']+"#,
+        #"("(?:access|refresh)_?[Tt]oken"\s*:\s*")[^"]+"#
+    ].map { try! NSRegularExpression(pattern: $0, options: [.caseInsensitive]) }
+
+    /// Waits for pending writes, so a line logged just before is included.
     func readLogs() -> String {
         guard let fileURL else { return "(app group container not available)" }
-        return (try? String(contentsOf: fileURL, encoding: .utf8)) ?? "(no logs yet)"
+        return queue.sync {
+            (try? String(contentsOf: fileURL, encoding: .utf8)) ?? "(no logs yet)"
+        }
     }
 
     func clearLogs() {
         guard let fileURL else { return }
-        try? "".write(to: fileURL, atomically: true, encoding: .utf8)
+        queue.sync {
+            try? "".write(to: fileURL, atomically: true, encoding: .utf8)
+        }
     }
 
-    /// Diagnostic dump of app group container state
+    /// Diagnostic dump of app group container state, at debug level
     func dumpContainerDiagnostics(source: String) {
+        guard isEnabled(.debug) else { return }
         let containerURL = FileManager.default.containerURL(
             forSecurityApplicationGroupIdentifier: SharedContainerService.appGroupID
         )
-        log("--- Container Diagnostics ---", source: source)
-        log("App Group ID: \(SharedContainerService.appGroupID)", source: source)
-        log("Container URL: \(containerURL?.path ?? "NIL (app group not configured)")", source: source)
+        log("--- Container Diagnostics ---", level: .debug, source: source)
+        log("App Group ID: \(SharedContainerService.appGroupID)", level: .debug, source: source)
+        log("Container URL: \(containerURL?.path ?? "NIL (app group not configured)")", level: .debug, source: source)
 
         if let containerURL {
             let fm = FileManager.default
-            log("Container exists: \(fm.fileExists(atPath: containerURL.path))", source: source)
+            log("Container exists: \(fm.fileExists(atPath: containerURL.path))", level: .debug, source: source)
             if let contents = try? fm.contentsOfDirectory(atPath: containerURL.path) {
-                log("Container contents: \(contents)", source: source)
+                log("Container contents: \(contents)", level: .debug, source: source)
             }
 
             let snapshotURL = containerURL.appendingPathComponent("usage-snapshot.json")
             if fm.fileExists(atPath: snapshotURL.path) {
                 if let data = try? Data(contentsOf: snapshotURL),
                    let str = String(data: data.prefix(500), encoding: .utf8) {
-                    log("Snapshot file: \(data.count) bytes", source: source)
-                    log("Snapshot preview: \(str)", source: source)
+                    log("Snapshot file: \(data.count) bytes", level: .debug, source: source)
+                    log("Snapshot preview: \(str)", level: .debug, source: source)
                 }
             } else {
-                log("Snapshot file: NOT FOUND", source: source)
+                log("Snapshot file: NOT FOUND", level: .debug, source: source)
             }
         }
-        log("--- End Diagnostics ---", source: source)
+        log("--- End Diagnostics ---", level: .debug, source: source)
     }
 }
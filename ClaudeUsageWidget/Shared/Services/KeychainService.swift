@@ -1,69 +1,307 @@
 import Foundation
 import Security
 
+/// Which token source the user prefers. Anything other than `.keychain` is
+/// tried first and falls back to the Keychain and credentials file.
+enum TokenSource: String, CaseIterable {
+    case keychain
+    case environment
+    case file
+
+    var displayName: String {
+        switch self {
+        case .keychain: return "Keychain"
+        case .environment: return "Environment"
+        case .file: return "File"
+        }
+    }
+}
+
 final class KeychainService: KeychainServiceProtocol {
+    static let keychainServiceName = "Claude Code-credentials"
+    static let tokenEnvironmentVariable = "CLAUDE_WIDGET_TOKEN"
+    /// Last-resort token for headless setups with neither a Keychain item nor a credentials file.
+    static let fallbackEnvironmentVariable = "CLAUDE_OAUTH_TOKEN"
+
+    private let credentialsFileURL: URL
+    private let searchesKeychain: Bool
+    private let currentTokenSource: () -> TokenSource
+    private let environment: [String: String]
+    private let currentTokenFileURL: () -> URL?
+    private let selectedAccount: () -> String?
+    private let serviceName: () -> String
+
+    init(
+        environment: [String: String] = ProcessInfo.processInfo.environment,
+        homeDirectory: URL = FileManager.default.homeDirectoryForCurrentUser,
+        tokenSource: @escaping () -> TokenSource = { .keychain },
+        tokenFilePath: @escaping () -> String? = { nil },
+        selectedAccount: @escaping () -> String? = { nil },
+        serviceName: @escaping () -> String = { KeychainService.keychainServiceName }
+    ) {
+        self.credentialsFileURL = Self.credentialsFileURL(environment: environment, homeDirectory: homeDirectory)
+        self.searchesKeychain = true
+        self.currentTokenSource = tokenSource
+        self.environment = environment
+        self.currentTokenFileURL = {
+            tokenFilePath()
+                .flatMap { $0.isEmpty ? nil : $0 }
+                .map { URL(fileURLWithPath: ($0 as NSString).expandingTildeInPath) }
+        }
+        self.selectedAccount = selectedAccount
+        self.serviceName = serviceName
+    }
+
+    /// Test-only initializer that skips the Keychain and reads a specific credentials file
+    init(
+        credentialsFileURL: URL,
+        searchesKeychain: Bool = false,
+        tokenSource: TokenSource = .keychain,
+        environment: [String: String] = [:],
+        tokenFileURL: URL? = nil,
+        selectedAccount: String? = nil
+    ) {
+        self.credentialsFileURL = credentialsFileURL
+        self.searchesKeychain = searchesKeychain
+        self.currentTokenSource = { tokenSource }
+        self.environment = environment
+        self.currentTokenFileURL = { tokenFileURL }
+        self.selectedAccount = { selectedAccount }
+        self.serviceName = { KeychainService.keychainServiceName }
+    }
+
+    /// Claude Code honors `$CLAUDE_CONFIG_DIR` for its config directory and falls
+    /// back to `~/.claude` otherwise.
+    static func credentialsFileURL(environment: [String: String], homeDirectory: URL) -> URL {
+        if let configDir = environment["CLAUDE_CONFIG_DIR"], !configDir.isEmpty {
+            let expanded = (configDir as NSString).expandingTildeInPath
+            return URL(fileURLWithPath: expanded, isDirectory: true)
+                .appendingPathComponent(".credentials.json")
+        }
+        return homeDirectory.appendingPathComponent(".claude/.credentials.json")
+    }
+
+    /// Read on every use, so a change in Settings applies to the next read.
+    private var tokenSource: TokenSource { currentTokenSource() }
+    private var tokenFileURL: URL? { currentTokenFileURL() }
+
+    var searchedLocations: [String] {
+        var locations: [String] = []
+        switch tokenSource {
+        case .keychain:
+            break
+        case .environment:
+            locations.append("$\(Self.tokenEnvironmentVariable)")
+        case .file:
+            locations.append(tokenFileURL?.path ?? "token file (no path set)")
+        }
+        if searchesKeychain {
+            locations.append("Keychain item \"\(serviceName())\" (account \(keychainAccount))")
+            if selectedAccount() != nil { return locations }
+        }
+        locations.append(credentialsFileURL.path)
+        locations.append("$\(Self.fallbackEnvironmentVariable)")
+        return locations
+    }
+
+    func readCredentials() throws -> OAuthCredentials {
+        if let credentials = readFromPreferredSource() {
+            return credentials
+        }
 
-    func readToken() throws -> String {
         // Try macOS Keychain first
-        if let token = try? readFromKeychain() {
-            return token
+        var keychainError: KeychainError?
+        if searchesKeychain {
+            do {
+                return try readFromKeychain().withSource(.keychain)
+            } catch let error as KeychainError {
+                keychainError = error
+            } catch {}
+
+            // The credentials file and fallback token may belong to another
+            // account, so an explicitly chosen one doesn't fall back to them.
+            if selectedAccount() != nil, let keychainError {
+                DebugLogger.shared.log("No credentials for selected account \(keychainAccount): \(keychainError)", level: .warning, source: "Keychain")
+                throw keychainError == .notFound ? KeychainError.noCredentials(searched: searchedLocations) : keychainError
+            }
         }
 
         // Fall back to credentials file
-        if let token = try? readFromCredentialsFile() {
-            return token
+        if let credentials = try? readFromCredentialsFile() {
+            return credentials.withSource(.credentialsFile)
         }
 
-        throw KeychainError.notFound
+        if let token = environmentToken(Self.fallbackEnvironmentVariable) {
+            return OAuthCredentials(accessToken: token, source: .environment)
+        }
+
+        let searched = searchedLocations
+        DebugLogger.shared.log("No credentials found. Checked: \(searched.joined(separator: ", "))", level: .warning, source: "Keychain")
+        // A denied prompt is more useful to surface than "not found".
+        if let keychainError, keychainError == .accessDenied || keychainError == .needsPermission {
+            throw keychainError
+        }
+        throw KeychainError.noCredentials(searched: searched)
     }
 
-    private func readFromKeychain() throws -> String {
+    private func readFromPreferredSource() -> OAuthCredentials? {
+        switch tokenSource {
+        case .keychain:
+            return nil
+        case .environment:
+            guard let token = environmentToken(Self.tokenEnvironmentVariable) else {
+                DebugLogger.shared.log("$\(Self.tokenEnvironmentVariable) is not set; falling back to Keychain", level: .warning, source: "Keychain")
+                return nil
+            }
+            return OAuthCredentials(accessToken: token, source: .environment)
+        case .file:
+            guard let tokenFileURL, let data = try? Data(contentsOf: tokenFileURL) else {
+                DebugLogger.shared.log("Token file \(tokenFileURL?.path ?? "(not set)") unreadable; falling back to Keychain", level: .warning, source: "Keychain")
+                return nil
+            }
+            return Self.credentialsFromTokenFile(data)
+        }
+    }
+
+    private func environmentToken(_ name: String) -> String? {
+        let token = environment[name]?.trimmingCharacters(in: .whitespacesAndNewlines) ?? ""
+        return token.isEmpty ? nil : token
+    }
+
+    /// A token file may hold either a copy of Claude Code's credentials JSON
+    /// or just the bare access token.
+    static func credentialsFromTokenFile(_ data: Data) -> OAuthCredentials? {
+        if let credentials = try? extractCredentials(from: data) {
+            return credentials.withSource(.tokenFile)
+        }
+        let token = String(decoding: data, as: UTF8.self).trimmingCharacters(in: .whitespacesAndNewlines)
+        guard !token.isEmpty, !token.contains(where: \.isWhitespace) else { return nil }
+        return OAuthCredentials(accessToken: token, source: .tokenFile)
+    }
+
+    /// The account chosen in settings, or the login user name Claude Code uses by default.
+    private var keychainAccount: String {
+        selectedAccount() ?? NSUserName()
+    }
+
+    /// Account names of every credentials item with the configured service
+    /// name. Only attributes are requested, so this never triggers an access prompt.
+    func listAccounts() -> [String] {
+        guard searchesKeychain else { return [] }
+
+        let query: [String: Any] = [
+            kSecClass as String: kSecClassGenericPassword,
+            kSecAttrService as String: serviceName(),
+            kSecMatchLimit as String: kSecMatchLimitAll,
+            kSecReturnAttributes as String: true
+        ]
+
+        var result: AnyObject?
+        guard SecItemCopyMatching(query as CFDictionary, &result) == errSecSuccess,
+              let items = result as? [[String: Any]] else {
+            return []
+        }
+
+        let accounts = items.compactMap { $0[kSecAttrAccount as String] as? String }
+        return Array(Set(accounts)).sorted()
+    }
+
+    /// Writes renewed tokens back into Claude Code's Keychain item or
+    /// credentials file, so both keep working with the rotated refresh token.
+    /// Tokens from the environment or a token file are left alone.
+    func writeCredentials(_ credentials: OAuthCredentials) throws {
+        switch credentials.source {
+        case .keychain:
+            let query: [String: Any] = [
+                kSecClass as String: kSecClassGenericPassword,
+                kSecAttrService as String: serviceName(),
+                kSecAttrAccount as String: keychainAccount
+            ]
+            var result: AnyObject?
+            var readQuery = query
+            readQuery[kSecReturnData as String] = true
+            if let error = Self.classify(status: SecItemCopyMatching(readQuery as CFDictionary, &result)) {
+                throw error
+            }
+            guard let data = result as? Data else {
+                throw KeychainError.invalidData("Unexpected Keychain data format")
+            }
+            let updated = try credentials.replacingTokens(in: data)
+            let attributes = [kSecValueData as String: updated]
+            if let error = Self.classify(status: SecItemUpdate(query as CFDictionary, attributes as CFDictionary)) {
+                throw error
+            }
+        case .credentialsFile:
+            let updated = try credentials.replacingTokens(in: Data(contentsOf: credentialsFileURL))
+            // Stage the new contents in an owner-only file next to the
+            // original, so the tokens are never readable by others, then swap.
+            let fileManager = FileManager.default
+            let stagedURL = credentialsFileURL.deletingLastPathComponent()
+                .appendingPathComponent(".\(credentialsFileURL.lastPathComponent).\(UUID().uuidString)")
+            guard fileManager.createFile(
+                atPath: stagedURL.path,
+                contents: updated,
+                attributes: [.posixPermissions: 0o600]
+            ) else {
+                throw CocoaError(.fileWriteUnknown, userInfo: [NSFilePathErrorKey: stagedURL.path])
+            }
+            do {
+                _ = try fileManager.replaceItemAt(credentialsFileURL, withItemAt: stagedURL)
+            } catch {
+                try? fileManager.removeItem(at: stagedURL)
+                throw error
+            }
+        case .environment, .tokenFile, nil:
+            break
+        }
+    }
+
+    private func readFromKeychain() throws -> OAuthCredentials {
         let query: [String: Any] = [
             kSecClass as String: kSecClassGenericPassword,
-            kSecAttrService as String: "Claude Code-credentials",
-            kSecAttrAccount as String: NSUserName(),
+            kSecAttrService as String: serviceName(),
+            kSecAttrAccount as String: keychainAccount,
             kSecReturnData as String: true
         ]
 
         var result: AnyObject?
         let status = SecItemCopyMatching(query as CFDictionary, &result)
 
+        if let error = Self.classify(status: status) {
+            throw error
+        }
+        guard let data = result as? Data else {
+            throw KeychainError.invalidData("Unexpected Keychain data format")
+        }
+        return try Self.extractCredentials(from: data)
+    }
+
+    /// Maps a `SecItemCopyMatching` status to the error callers branch on; nil means success.
+    static func classify(status: OSStatus) -> KeychainError? {
         switch status {
         case errSecSuccess:
-            guard let data = result as? Data else {
-                throw KeychainError.invalidData("Unexpected Keychain data format")
-            }
-            return try Self.extractToken(from: data)
+            return nil
         case errSecItemNotFound:
-            throw KeychainError.notFound
-        case errSecAuthFailed, errSecInteractionNotAllowed:
-            throw KeychainError.accessDenied
+            return .notFound
+        case errSecUserCanceled, errSecAuthFailed:
+            return .accessDenied
+        case errSecInteractionNotAllowed:
+            return .needsPermission
         default:
-            throw KeychainError.invalidData("Keychain error: \(status)")
+            return .invalidData("Keychain error: \(status)")
         }
     }
 
-    private func readFromCredentialsFile() throws -> String {
-        let home = FileManager.default.homeDirectoryForCurrentUser
-        let credPath = home.appendingPathComponent(".claude/.credentials.json")
-        let data = try Data(contentsOf: credPath)
-        return try Self.extractToken(from: data)
+    private func readFromCredentialsFile() throws -> OAuthCredentials {
+        let data = try Data(contentsOf: credentialsFileURL)
+        return try Self.extractCredentials(from: data)
     }
 
     static func extractToken(from data: Data) throws -> String {
-        let json: Any
-        do {
-            json = try JSONSerialization.jsonObject(with: data)
-        } catch {
-            throw KeychainError.invalidData("Failed to parse credentials JSON: \(error.localizedDescription)")
-        }
-
-        guard let dict = json as? [String: Any],
-              let oauth = dict["claudeAiOauth"] as? [String: Any],
-              let token = oauth["accessToken"] as? String else {
-            throw KeychainError.invalidData("No OAuth token found in credentials")
-        }
+        try extractCredentials(from: data).accessToken
+    }
 
-        return token
+    static func extractCredentials(from data: Data) throws -> OAuthCredentials {
+        try OAuthCredentials(credentialsData: data)
     }
 }
@@ -0,0 +1,189 @@
+import Foundation
+import Security
+
+enum ProxyError: Error, Equatable {
+    case invalidURL(String)
+}
+
+extension ProxyError: LocalizedError {
+    var errorDescription: String? {
+        switch self {
+        case .invalidURL(let value):
+            return "Invalid proxy URL \"\(value)\". Use http://host:port, socks5://host:port, or add user:password@ before the host."
+        }
+    }
+}
+
+/// An HTTP or SOCKS5 proxy for the shared URLSession, taken from the proxy
+/// URL in Settings or, when that's empty, from `HTTPS_PROXY` / `ALL_PROXY` /
+/// `HTTP_PROXY` and `NO_PROXY`. Without either, URLSession follows the system
+/// proxy settings.
+struct ProxyConfiguration: Equatable {
+    enum Kind: Equatable {
+        case http
+        case socks5
+    }
+
+    static let environmentVariables = ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy", "HTTP_PROXY", "http_proxy"]
+
+    let kind: Kind
+    let host: String
+    let port: Int
+    let username: String?
+    /// From the URL for environment proxies; for the Settings proxy, filled
+    /// in from `ProxyPasswordStore`.
+    var password: String?
+    /// Hosts and domain suffixes that bypass the proxy.
+    let exceptions: [String]
+
+    init(urlString: String, exceptions: [String] = []) throws {
+        let trimmed = urlString.trimmingCharacters(in: .whitespaces)
+        guard let components = URLComponents(string: trimmed),
+              let scheme = components.scheme?.lowercased(),
+              let kind = Self.kind(forScheme: scheme),
+              let host = components.host, !host.isEmpty,
+              components.path.isEmpty || components.path == "/" else {
+            throw ProxyError.invalidURL(urlString)
+        }
+        if let port = components.port, !(1...65_535).contains(port) {
+            throw ProxyError.invalidURL(urlString)
+        }
+
+        self.kind = kind
+        self.host = host
+        self.port = components.port ?? Self.defaultPort(forScheme: scheme)
+        self.username = components.user.flatMap { $0.isEmpty ? nil : $0 }
+        self.password = components.password
+        self.exceptions = exceptions
+    }
+
+    private static func kind(forScheme scheme: String) -> Kind? {
+        switch scheme {
+        case "http", "https": return .http
+        case "socks5", "socks5h", "socks": return .socks5
+        default: return nil
+        }
+    }
+
+    private static func defaultPort(forScheme scheme: String) -> Int {
+        switch scheme {
+        case "https": return 443
+        case "http": return 80
+        default: return 1080
+        }
+    }
+
+    /// Throws for a malformed variable rather than returning nil, which would
+    /// quietly connect directly.
+    static func fromEnvironment(_ environment: [String: String] = ProcessInfo.processInfo.environment) throws -> ProxyConfiguration? {
+        guard let value = environmentVariables.lazy.compactMap({ environment[$0] }).first(where: { !$0.isEmpty }),
+              !noProxyHosts(environment).contains("*") else {
+            return nil
+        }
+        return try ProxyConfiguration(urlString: value, exceptions: noProxyHosts(environment))
+    }
+
+    /// `NO_PROXY` is a comma-separated list of hosts and domain suffixes.
+    static func noProxyHosts(_ environment: [String: String]) -> [String] {
+        (environment["NO_PROXY"] ?? environment["no_proxy"] ?? "")
+            .split(separator: ",")
+            .map { $0.trimmingCharacters(in: .whitespaces) }
+            .filter { !$0.isEmpty }
+    }
+
+    /// `user@host:port`, the Keychain account for this proxy's password.
+    var account: String {
+        "\(username ?? "")@\(host):\(port)"
+    }
+
+    /// The URL with any password removed, for storing in UserDefaults.
+    static func removingPassword(from urlString: String) -> String {
+        guard var components = URLComponents(string: urlString), components.password != nil else { return urlString }
+        components.password = nil
+        return components.string ?? urlString
+    }
+
+    /// The credential for a challenge from this proxy; nil for other hosts,
+    /// server challenges, or when there's no user name.
+    func credential(for protectionSpace: URLProtectionSpace) -> URLCredential? {
+        guard let username, protectionSpace.isProxy(),
+              protectionSpace.host.caseInsensitiveCompare(host) == .orderedSame,
+              protectionSpace.port == port else {
+            return nil
+        }
+        return URLCredential(user: username, password: password ?? "", persistence: .forSession)
+    }
+
+    /// Keys understood by `URLSessionConfiguration.connectionProxyDictionary`.
+    /// Credentials aren't included: URLSession ignores them for HTTPS
+    /// tunnels, so `ProxyAuthenticationDelegate` answers the challenge instead.
+    var connectionProxyDictionary: [AnyHashable: Any] {
+        var dictionary: [AnyHashable: Any]
+        switch kind {
+        case .http:
+            dictionary = [
+                kCFNetworkProxiesHTTPEnable as String: 1,
+                kCFNetworkProxiesHTTPProxy as String: host,
+                kCFNetworkProxiesHTTPPort as String: port,
+                kCFNetworkProxiesHTTPSEnable as String: 1,
+                kCFNetworkProxiesHTTPSProxy as String: host,
+                kCFNetworkProxiesHTTPSPort as String: port
+            ]
+        case .socks5:
+            dictionary = [
+                kCFNetworkProxiesSOCKSEnable as String: 1,
+                kCFNetworkProxiesSOCKSProxy as String: host,
+                kCFNetworkProxiesSOCKSPort as String: port
+            ]
+        }
+        if !exceptions.isEmpty {
+            dictionary[kCFNetworkProxiesExceptionsList as String] = exceptions
+        }
+        return dictionary
+    }
+}
+
+extension ProxyConfiguration: CustomStringConvertible {
+    var description: String {
+        let credentials = username.map { "\($0):<redacted>@" } ?? ""
+        return "ProxyConfiguration(\(kind) \(credentials)\(host):\(port), exceptions: \(exceptions))"
+    }
+}
+
+/// Keeps the Settings proxy's password in the Keychain.
+final class ProxyPasswordStore: ProxyPasswordStoreProtocol {
+    static let serviceName = "Claude Usage Widget proxy"
+
+    func password(for proxy: ProxyConfiguration) -> String? {
+        var query = baseQuery(for: proxy)
+        query[kSecReturnData as String] = true
+        var result: AnyObject?
+        guard SecItemCopyMatching(query as CFDictionary, &result) == errSecSuccess,
+              let data = result as? Data else {
+            return nil
+        }
+        return String(data: data, encoding: .utf8)
+    }
+
+    func setPassword(_ password: String?, for proxy: ProxyConfiguration) throws {
+        let query = baseQuery(for: proxy)
+        let deleteStatus = SecItemDelete(query as CFDictionary)
+        if deleteStatus != errSecItemNotFound, let error = KeychainService.classify(status: deleteStatus) {
+            throw error
+        }
+        guard let password else { return }
+        var item = query
+        item[kSecValueData as String] = Data(password.utf8)
+        if let error = KeychainService.classify(status: SecItemAdd(item as CFDictionary, nil)) {
+            throw error
+        }
+    }
+
+    private func baseQuery(for proxy: ProxyConfiguration) -> [String: Any] {
+        [
+            kSecClass as String: kSecClassGenericPassword,
+            kSecAttrService as String: Self.serviceName,
+            kSecAttrAccount as String: proxy.account
+        ]
+    }
+}
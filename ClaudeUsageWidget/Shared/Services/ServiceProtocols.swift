@@ -1,11 +1,39 @@
 import Foundation
 
 protocol KeychainServiceProtocol {
-    func readToken() throws -> String
+    func readCredentials() throws -> OAuthCredentials
+    func listAccounts() -> [String]
+    /// Saves renewed tokens to where `credentials.source` says they came from.
+    func writeCredentials(_ credentials: OAuthCredentials) throws
 }
 
 protocol APIServiceProtocol {
-    func fetchUsage(token: String) async throws -> UsageApiResponse
+    /// `allowingCached: false` skips any response the service would otherwise
+    /// reuse without a request.
+    func fetchUsage(token: String, allowingCached: Bool) async throws -> UsageApiResponse
+    /// Nil when the profile endpoint doesn't exist (404) or names no plan.
+    func fetchProfile(token: String) async throws -> PlanInfo?
+    /// HTTP status of an unauthenticated request to the API base URL; any
+    /// status means the host is reachable.
+    func probeReachability() async throws -> Int
+}
+
+extension APIServiceProtocol {
+    func fetchUsage(token: String) async throws -> UsageApiResponse {
+        try await fetchUsage(token: token, allowingCached: true)
+    }
+}
+
+/// Proxy passwords, kept out of UserDefaults. Keyed by the proxy's user,
+/// host and port.
+protocol ProxyPasswordStoreProtocol {
+    func password(for proxy: ProxyConfiguration) -> String?
+    /// Nil removes the stored password.
+    func setPassword(_ password: String?, for proxy: ProxyConfiguration) throws
+}
+
+protocol TokenRefreshServiceProtocol {
+    func refresh(refreshToken: String) async throws -> OAuthCredentials
 }
 
 protocol CodexAuthServiceProtocol {
@@ -18,6 +46,12 @@ protocol CodexAPIServiceProtocol {
 
 protocol StatsServiceProtocol {
     func readStats() -> TokenStats
+    /// One entry per calendar day for the last `days` days, oldest first.
+    func tokenHistory(days: Int) -> [DailyTokens]
+    /// Per-project totals for today and the week, busiest project first.
+    func projectStats() -> [ProjectUsage]
+    /// The raw daily stats, or nil when the source has none.
+    func statsCache() -> StatsCache?
 }
 
 protocol SharedContainerServiceProtocol {
@@ -27,18 +61,45 @@ protocol SharedContainerServiceProtocol {
     func readPaceSettings() -> PaceSettings
 }
 
+protocol UsageHistoryServiceProtocol {
+    func record(_ snapshot: UsageSnapshot) throws
+    func snapshots(from start: Date, to end: Date) throws -> [UsageSnapshot]
+    func series(from start: Date, to end: Date, resolution: TimeInterval) throws -> [UsageHistoryPoint]
+    func forEachPoint(from start: Date, to end: Date, _ body: (UsageHistoryPoint) throws -> Void) throws
+    @discardableResult
+    func rollUpDailyPeaks(now: Date, calendar: Calendar) throws -> Int
+    func dailyPeaks(days: Int) throws -> [DailyPeak]
+    func prune(olderThan cutoff: Date) throws
+}
+
+protocol UsageNotifierProtocol {
+    func post(title: String, body: String, identifier: String)
+}
+
 enum KeychainError: Error, Equatable {
+    /// The Keychain item doesn't exist.
     case notFound
+    /// No source had credentials; `searched` lists where the read looked.
+    case noCredentials(searched: [String])
+    /// The user clicked Deny (or cancelled) on the Keychain prompt.
     case accessDenied
+    /// The Keychain can't show its prompt right now, e.g. while the screen is locked.
+    case needsPermission
     case invalidData(String)
 }
 
 enum APIError: Error, Equatable {
     case unauthorized
     case forbidden
+    /// HTTP 429; `retryAfter` is the server's requested wait in seconds, if it sent one.
+    case rateLimited(retryAfter: TimeInterval?)
     case serverError(Int)
     case networkError(String)
     case decodingError(String)
+    /// The configured API base URL isn't an absolute https URL; no request was sent.
+    case invalidBaseURL(String)
+    /// The configured proxy is malformed; requests are held back instead of going direct.
+    case invalidProxy(String)
 }
 
 enum CodexAuthError: Error, Equatable {
@@ -0,0 +1,241 @@
+import Foundation
+
+/// Per-project token totals from Claude Code's session transcripts
+/// (`~/.claude/projects/<project>/*.jsonl`). Those directories can hold
+/// hundreds of megabytes, so each file is read from where the previous scan
+/// stopped, and offsets and per-day totals are kept in `stateFileURL` between
+/// launches. A file that shrank or was replaced is read again from the start.
+final class SessionScanner {
+    static var defaultStateURL: URL {
+        FileManager.default.homeDirectoryForCurrentUser.appendingPathComponent(".claude-widget/scan-state.json")
+    }
+
+    private let projectsDirectoryURL: URL
+    private let stateFileURL: URL
+    private let readChunkSize: Int
+    private let lock = NSLock()
+    private var state: ScanState?
+
+    init(projectsDirectoryURL: URL, stateFileURL: URL = SessionScanner.defaultStateURL, readChunkSize: Int = 1 << 20) {
+        self.projectsDirectoryURL = projectsDirectoryURL.standardizedFileURL
+        self.stateFileURL = stateFileURL
+        self.readChunkSize = readChunkSize
+    }
+
+    /// Projects with usage this week, as `week` defines it, busiest first.
+    func scan(now: Date = Date(), calendar: Calendar = .current, week: WeekDefinition = .rolling) -> [ProjectUsage] {
+        lock.lock()
+        defer { lock.unlock() }
+
+        let formatter = StatsService.dayFormatter(calendar: calendar)
+        let firstDay = formatter.string(from: StatsService.startOfWeek(now: now, calendar: calendar, week: week))
+        let today = formatter.string(from: now)
+        // A calendar week never starts before the rolling one, so keeping the
+        // rolling week's days lets the setting change without a rescan.
+        let startOfRollingWeek = StatsService.startOfWeek(now: now, calendar: calendar, week: .rolling)
+        let firstKeptDay = formatter.string(from: startOfRollingWeek)
+
+        var state = self.state ?? loadState()
+        var changed = false
+        // Day keys are in the day-boundary zone; after it changes, recount.
+        if state.timeZone != calendar.timeZone.identifier {
+            if !state.files.isEmpty {
+                DebugLogger.shared.log("Day-boundary zone changed; rescanning transcripts", source: "Stats")
+            }
+            state = ScanState(timeZone: calendar.timeZone.identifier)
+            changed = true
+        }
+        let files = transcriptFiles(modifiedSince: startOfRollingWeek)
+
+        // The state file is shared by every profile's projects directory.
+        let prefix = projectsDirectoryURL.path + "/"
+        let removed = state.files.keys.filter { $0.hasPrefix(prefix) && files[$0] == nil }
+        for path in removed {
+            state.files[path] = nil
+            changed = true
+        }
+
+        var seenMessages = Set(state.files.values.flatMap { $0.messageIDs.values.joined() })
+        for (path, file) in files.sorted(by: { $0.key < $1.key }) {
+            var fileState = state.files[path] ?? FileScanState(project: file.project)
+            if file.size < fileState.offset || file.inode != fileState.inode {
+                seenMessages.subtract(fileState.messageIDs.values.joined())
+                fileState = FileScanState(project: file.project, inode: file.inode)
+            }
+            guard file.size > fileState.offset else { continue }
+
+            read(URL(fileURLWithPath: path), into: &fileState, seenMessages: &seenMessages, formatter: formatter)
+            state.files[path] = fileState
+            changed = true
+        }
+
+        for path in state.files.keys where path.hasPrefix(prefix) {
+            guard let fileState = state.files[path] else { continue }
+            let staleDays = Set(fileState.days.keys).union(fileState.messageIDs.keys).filter { $0 < firstKeptDay }
+            guard !staleDays.isEmpty else { continue }
+            staleDays.forEach {
+                state.files[path]?.days[$0] = nil
+                state.files[path]?.messageIDs[$0] = nil
+            }
+            changed = true
+        }
+
+        self.state = state
+        if changed {
+            saveState(state)
+        }
+        return Self.projectUsage(from: state.files.filter { $0.key.hasPrefix(prefix) }.map(\.value), firstDay: firstDay, today: today)
+    }
+
+    /// Reads `url` from `fileState.offset` in `readChunkSize` pieces, so a
+    /// first scan of a large transcript doesn't load it whole.
+    private func read(_ url: URL, into fileState: inout FileScanState, seenMessages: inout Set<String>, formatter: DateFormatter) {
+        guard let handle = try? FileHandle(forReadingFrom: url) else { return }
+        defer { try? handle.close() }
+
+        var pending = Data()
+        do {
+            try handle.seek(toOffset: fileState.offset)
+            while let chunk = try handle.read(upToCount: readChunkSize), !chunk.isEmpty {
+                pending.append(chunk)
+                // A line still being written has no newline yet; leave it for the next scan.
+                guard let lastNewline = pending.lastIndex(of: UInt8(ascii: "\n")) else { continue }
+                let complete = pending[pending.startIndex...lastNewline]
+                count(complete, into: &fileState, seenMessages: &seenMessages, formatter: formatter)
+                fileState.offset += UInt64(complete.count)
+                pending = Data(pending[(lastNewline + 1)...])
+            }
+        } catch {
+            DebugLogger.shared.log("Transcript read failed for \(url.lastPathComponent): \(error)", level: .warning, source: "Stats")
+        }
+    }
+
+    private func count(_ complete: Data, into fileState: inout FileScanState, seenMessages: inout Set<String>, formatter: DateFormatter) {
+        let decoder = JSONDecoder()
+        decoder.dateDecodingStrategy = .custom { decoder in
+            try StatsService.decodeISO8601Date(from: decoder)
+        }
+
+        for line in complete.split(separator: UInt8(ascii: "\n")) {
+            guard let entry = try? decoder.decode(TranscriptUsageEntry.self, from: Data(line)) else { continue }
+            let day = formatter.string(from: entry.timestamp)
+            if let uniqueHash = StatsService.uniqueHash(for: entry) {
+                guard seenMessages.insert(uniqueHash).inserted else { continue }
+                fileState.messageIDs[day, default: []].append(uniqueHash)
+            }
+
+            let usage = entry.message.usage
+            let tokens = TokenBreakdown(
+                input: max(0, usage.inputTokens),
+                output: max(0, usage.outputTokens),
+                cacheCreation: max(0, usage.cacheCreationInputTokens ?? 0),
+                cacheRead: max(0, usage.cacheReadInputTokens ?? 0)
+            )
+            let model = entry.message.model ?? StatsService.unknownModel
+            fileState.days[day, default: [:]][model, default: .zero] += tokens
+        }
+    }
+
+    private static func projectUsage(from files: [FileScanState], firstDay: String, today: String) -> [ProjectUsage] {
+        var todayByProject: [String: [String: TokenBreakdown]] = [:]
+        var weekByProject: [String: [String: TokenBreakdown]] = [:]
+
+        for file in files {
+            for (day, models) in file.days where day >= firstDay && day <= today {
+                weekByProject[file.project, default: [:]].merge(models, uniquingKeysWith: +)
+                if day == today {
+                    todayByProject[file.project, default: [:]].merge(models, uniquingKeysWith: +)
+                }
+            }
+        }
+
+        return weekByProject
+            .map { project, weekByModel in
+                let todayByModel = todayByProject[project] ?? [:]
+                return ProjectUsage(
+                    project: project,
+                    today: todayByModel.values.reduce(.zero, +),
+                    week: weekByModel.values.reduce(.zero, +),
+                    todayByModel: todayByModel,
+                    weekByModel: weekByModel
+                )
+            }
+            .filter { $0.week.total > 0 }
+            .sorted { ($0.week.total, $1.project) > ($1.week.total, $0.project) }
+    }
+
+    private func transcriptFiles(modifiedSince cutoff: Date) -> [String: TranscriptFileInfo] {
+        let keys: [URLResourceKey] = [.isRegularFileKey, .contentModificationDateKey, .fileSizeKey]
+        guard let enumerator = FileManager.default.enumerator(
+            at: projectsDirectoryURL,
+            includingPropertiesForKeys: keys,
+            options: [.skipsHiddenFiles]
+        ) else {
+            return [:]
+        }
+
+        let root = projectsDirectoryURL.pathComponents.count
+        var files: [String: TranscriptFileInfo] = [:]
+        for case let fileURL as URL in enumerator where fileURL.pathExtension == "jsonl" {
+            let values = try? fileURL.resourceValues(forKeys: Set(keys))
+            guard values?.isRegularFile == true,
+                  (values?.contentModificationDate ?? .distantPast) >= cutoff else { continue }
+
+            let components = fileURL.standardizedFileURL.pathComponents
+            let project = components.count > root + 1 ? components[root] : projectsDirectoryURL.lastPathComponent
+            let inode = (try? FileManager.default.attributesOfItem(atPath: fileURL.path))?[.systemFileNumber] as? NSNumber
+            files[fileURL.standardizedFileURL.path] = TranscriptFileInfo(
+                project: project,
+                size: UInt64(values?.fileSize ?? 0),
+                inode: inode?.uint64Value
+            )
+        }
+        return files
+    }
+
+    private func loadState() -> ScanState {
+        guard let data = FileManager.default.contents(atPath: stateFileURL.path) else { return ScanState() }
+        guard let state = try? JSONDecoder().decode(ScanState.self, from: data), state.version == ScanState.currentVersion else {
+            DebugLogger.shared.log("Discarding unreadable scan state; rescanning transcripts", level: .warning, source: "Stats")
+            return ScanState()
+        }
+        return state
+    }
+
+    private func saveState(_ state: ScanState) {
+        do {
+            try FileManager.default.createDirectory(at: stateFileURL.deletingLastPathComponent(), withIntermediateDirectories: true)
+            try JSONEncoder().encode(state).write(to: stateFileURL, options: .atomic)
+        } catch {
+            DebugLogger.shared.log("Scan state write failed: \(error)", level: .warning, source: "Stats")
+        }
+    }
+}
+
+private struct ScanState: Codable {
+    static let currentVersion = 2
+
+    var version = currentVersion
+    /// Zone the day keys below are in.
+    var timeZone: String?
+    var files: [String: FileScanState] = [:]
+}
+
+private struct FileScanState: Codable {
+    let project: String
+    var inode: UInt64?
+    /// Bytes consumed so far; always at a line boundary.
+    var offset: UInt64 = 0
+    /// `yyyy-MM-dd` → keys of messages counted from this file, so a message
+    /// copied into a resumed session's transcript isn't counted twice. A
+    /// copy keeps its original timestamp, so keys are dropped with their day.
+    var messageIDs: [String: [String]] = [:]
+    /// `yyyy-MM-dd` → model → tokens.
+    var days: [String: [String: TokenBreakdown]] = [:]
+}
+
+private struct TranscriptFileInfo {
+    let project: String
+    let size: UInt64
+    let inode: UInt64?
+}
@@ -37,10 +37,10 @@ final class SharedContainerService: SharedContainerServiceProtocol {
         let encoder = UsageSnapshot.makeEncoder()
         let data = try encoder.encode(snapshot)
 
-        debug.log("Writing snapshot: \(data.count) bytes, error=\(snapshot.error ?? "nil"), fiveHour=\(snapshot.fiveHour?.percent ?? -1)%", source: "SharedContainer")
+        debug.log("Writing snapshot: \(data.count) bytes, error=\(snapshot.error ?? "nil"), fiveHour=\(snapshot.fiveHour?.percent ?? -1)%", level: .debug, source: "SharedContainer")
 
         guard let fileURL = snapshotFileURL else {
-            debug.log("WRITE FAILED: container URL is nil — app group not configured", source: "SharedContainer")
+            debug.log("WRITE FAILED: container URL is nil — app group not configured", level: .error, source: "SharedContainer")
             throw SharedContainerError.noContainer
         }
 
@@ -49,9 +49,9 @@ final class SharedContainerService: SharedContainerServiceProtocol {
 
         // Read-back verification
         if let readBack = try? Data(contentsOf: fileURL) {
-            debug.log("Write verified: read-back got \(readBack.count) bytes (match: \(readBack == data))", source: "SharedContainer")
+            debug.log("Write verified: read-back got \(readBack.count) bytes (match: \(readBack == data))", level: .debug, source: "SharedContainer")
         } else {
-            debug.log("WRITE VERIFICATION FAILED: read-back returned nil!", source: "SharedContainer")
+            debug.log("WRITE VERIFICATION FAILED: read-back returned nil!", level: .error, source: "SharedContainer")
         }
     }
 
@@ -59,27 +59,27 @@ final class SharedContainerService: SharedContainerServiceProtocol {
         let debug = DebugLogger.shared
 
         guard let fileURL = snapshotFileURL else {
-            debug.log("READ FAILED: container URL is nil — app group not configured", source: "SharedContainer")
+            debug.log("READ FAILED: container URL is nil — app group not configured", level: .error, source: "SharedContainer")
             return nil
         }
 
         guard FileManager.default.fileExists(atPath: fileURL.path) else {
-            debug.log("READ: no snapshot file at \(fileURL.path)", source: "SharedContainer")
+            debug.log("READ: no snapshot file at \(fileURL.path)", level: .debug, source: "SharedContainer")
             containerLog.info("[SharedContainer] no snapshot file")
             return nil
         }
 
         do {
             let data = try Data(contentsOf: fileURL)
-            debug.log("READ: got \(data.count) bytes from \(fileURL.lastPathComponent)", source: "SharedContainer")
+            debug.log("READ: got \(data.count) bytes from \(fileURL.lastPathComponent)", level: .debug, source: "SharedContainer")
             containerLog.info("[SharedContainer] read \(data.count) bytes from file")
 
             let decoder = UsageSnapshot.makeDecoder()
             let snapshot = try decoder.decode(UsageSnapshot.self, from: data)
-            debug.log("READ decoded OK: fiveHour=\(snapshot.fiveHour?.percent ?? -1)%, error=\(snapshot.error ?? "nil"), lastUpdated=\(snapshot.lastUpdated)", source: "SharedContainer")
+            debug.log("READ decoded OK: fiveHour=\(snapshot.fiveHour?.percent ?? -1)%, error=\(snapshot.error ?? "nil"), lastUpdated=\(snapshot.lastUpdated)", level: .debug, source: "SharedContainer")
             return snapshot
         } catch {
-            debug.log("READ ERROR: \(String(reflecting: error))", source: "SharedContainer")
+            debug.log("READ ERROR: \(String(reflecting: error))", level: .error, source: "SharedContainer")
             containerLog.error("[SharedContainer] read error: \(String(reflecting: error), privacy: .public)")
             return nil
         }
@@ -93,8 +93,7 @@ final class SharedContainerService: SharedContainerServiceProtocol {
         guard let fileURL = paceSettingsFileURL else {
             throw SharedContainerError.noContainer
         }
-        let data = try JSONEncoder().encode(settings)
-        try data.write(to: fileURL, options: .atomic)
+        try settings.encoded().write(to: fileURL, options: .atomic)
     }
 
     func readPaceSettings() -> PaceSettings {
@@ -110,11 +109,26 @@ final class SharedContainerService: SharedContainerServiceProtocol {
 
         do {
             let data = try Data(contentsOf: fileURL)
-            let settings = try JSONDecoder().decode(PaceSettings.self, from: data)
-            return settings
+            return try PaceSettings.decode(from: data)
         } catch {
             containerLog.error("[SharedContainer] readPaceSettings decode error: \(String(reflecting: error), privacy: .public)")
+            backUpUnreadableFile(fileURL)
             return .allEnabled
         }
     }
+
+    /// Keeps a file that failed to decode as `<name>.bak` instead of letting
+    /// the next write replace it, so the user's choices can be recovered.
+    private func backUpUnreadableFile(_ fileURL: URL) {
+        let backupURL = fileURL.appendingPathExtension("bak")
+        do {
+            if FileManager.default.fileExists(atPath: backupURL.path) {
+                try FileManager.default.removeItem(at: backupURL)
+            }
+            try FileManager.default.copyItem(at: fileURL, to: backupURL)
+            DebugLogger.shared.log("\(fileURL.lastPathComponent) unreadable; kept a copy as \(backupURL.lastPathComponent) and using defaults", level: .error, source: "SharedContainer")
+        } catch {
+            DebugLogger.shared.log("Backing up \(fileURL.lastPathComponent) failed: \(error)", level: .error, source: "SharedContainer")
+        }
+    }
 }
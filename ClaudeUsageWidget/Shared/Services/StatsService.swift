@@ -1,88 +1,295 @@
 import Foundation
 import SQLite3
 
+/// Where "this week" starts for the weekly token totals.
+enum WeekDefinition: String, CaseIterable {
+    /// The last seven days, today included.
+    case rolling
+    case startsMonday
+    case startsSunday
+
+    var displayName: String {
+        switch self {
+        case .rolling: return "Last 7 days"
+        case .startsMonday: return "Since Monday"
+        case .startsSunday: return "Since Sunday"
+        }
+    }
+}
+
 final class StatsService: StatsServiceProtocol {
-    private let statsFilePath: String
-    private let sessionMetaDirectoryPath: String
-    private let projectsDirectoryPath: String
+    /// Bucket for transcript entries that don't name a model.
+    static let unknownModel = "unknown"
+
+    static var defaultStatsFileURL: URL {
+        FileManager.default.homeDirectoryForCurrentUser.appendingPathComponent(".claude/stats-cache.json")
+    }
+
+    private let defaultStatsFilePath: String
+    private let defaultSessionMetaDirectoryPath: String
+    private let defaultProjectsDirectoryPath: String
+    private let statsFileOverride: () -> URL?
+    private let statsCacheOverride: () -> URL?
+    private let weekDefinition: () -> WeekDefinition
+    private let dayBoundaryTimeZone: () -> TimeZone
+    private let pricingFileURL: URL
+    private let partialReadRetryDelay: TimeInterval
+    private let scanStateURL: URL
+    private let scannersLock = NSLock()
+    private var sessionScanners: [String: SessionScanner] = [:]
     private let transcriptCacheLock = NSLock()
     private var cachedTranscriptState: CachedTranscriptState?
-
-    init(statsFilePath: String? = nil, sessionMetaDirectoryPath: String? = nil, projectsDirectoryPath: String? = nil) {
+    private let statsCacheLock = NSLock()
+    private var lastGoodStatsCache: (path: String, cache: StatsCache)?
+
+    init(
+        statsFilePath: String? = nil,
+        sessionMetaDirectoryPath: String? = nil,
+        projectsDirectoryPath: String? = nil,
+        pricingFileURL: URL = PricingTable.defaultURL,
+        partialReadRetryDelay: TimeInterval = 0.2,
+        scanStateURL: URL = SessionScanner.defaultStateURL,
+        statsFileOverride: @escaping () -> URL? = { nil },
+        statsCacheOverride: @escaping () -> URL? = { nil },
+        weekDefinition: @escaping () -> WeekDefinition = { .rolling },
+        dayBoundaryTimeZone: @escaping () -> TimeZone = { .current }
+    ) {
         let home = FileManager.default.homeDirectoryForCurrentUser
+        self.pricingFileURL = pricingFileURL
+        self.partialReadRetryDelay = partialReadRetryDelay
+        self.scanStateURL = scanStateURL
+        self.statsFileOverride = statsFileOverride
+        self.statsCacheOverride = statsCacheOverride
+        self.weekDefinition = weekDefinition
+        self.dayBoundaryTimeZone = dayBoundaryTimeZone
 
         if let path = statsFilePath {
-            self.statsFilePath = path
+            self.defaultStatsFilePath = path
         } else {
-            self.statsFilePath = home.appendingPathComponent(".claude/stats-cache.json").path
+            self.defaultStatsFilePath = Self.defaultStatsFileURL.path
         }
 
         if let path = sessionMetaDirectoryPath {
-            self.sessionMetaDirectoryPath = path
+            self.defaultSessionMetaDirectoryPath = path
         } else {
-            self.sessionMetaDirectoryPath = home
+            self.defaultSessionMetaDirectoryPath = home
                 .appendingPathComponent(".claude/usage-data/session-meta")
                 .path
         }
 
         if let path = projectsDirectoryPath {
-            self.projectsDirectoryPath = path
+            self.defaultProjectsDirectoryPath = path
         } else {
-            self.projectsDirectoryPath = home
+            self.defaultProjectsDirectoryPath = home
                 .appendingPathComponent(".claude/projects")
                 .path
         }
     }
 
+    /// `statsFileOverride` (set by the active profile) moves all three
+    /// sources into the directory holding that stats cache;
+    /// `statsCacheOverride` (the Settings path) moves only the cache.
+    private var statsFilePath: String {
+        (statsFileOverride() ?? statsCacheOverride())?.path ?? defaultStatsFilePath
+    }
+
+    private var sessionMetaDirectoryPath: String {
+        guard let override = statsFileOverride() else { return defaultSessionMetaDirectoryPath }
+        return override.deletingLastPathComponent().appendingPathComponent("usage-data/session-meta").path
+    }
+
+    private var projectsDirectoryPath: String {
+        guard let override = statsFileOverride() else { return defaultProjectsDirectoryPath }
+        return override.deletingLastPathComponent().appendingPathComponent("projects").path
+    }
+
+    /// Today and the week start at midnight in `dayBoundaryTimeZone`.
+    private var calendar: Calendar {
+        Self.calendar(in: dayBoundaryTimeZone())
+    }
+
+    static func calendar(in timeZone: TimeZone) -> Calendar {
+        var calendar = Calendar.current
+        calendar.timeZone = timeZone
+        return calendar
+    }
+
+    /// The next midnight in `calendar`'s time zone, when today's totals start over.
+    static func endOfDay(now: Date, calendar: Calendar) -> Date {
+        let startOfToday = calendar.startOfDay(for: now)
+        return calendar.date(byAdding: .day, value: 1, to: startOfToday) ?? startOfToday.addingTimeInterval(86400)
+    }
+
     func readStats() -> TokenStats {
+        var stats = readTokenStats().withCosts(from: PricingTable.load(from: pricingFileURL))
+        stats.dayEndsAt = Self.endOfDay(now: Date(), calendar: calendar)
+        return stats
+    }
+
+    private func readTokenStats() -> TokenStats {
         if let transcriptStats = readTranscriptStats() {
             return transcriptStats
         }
 
-        guard let data = FileManager.default.contents(atPath: statsFilePath),
-              let cache = try? JSONDecoder().decode(StatsCache.self, from: data) else {
+        guard let cache = readStatsCache() else {
             return readSessionMetaStats() ?? TokenStats(todayTokens: 0, weekTokens: 0, todayMessages: 0, weekMessages: 0)
         }
 
-        let cacheStats = Self.calculateTokenStats(from: cache)
-        if Self.cacheIncludesCurrentWeek(cache) {
+        let week = weekDefinition()
+        let calendar = self.calendar
+        let cacheStats = Self.calculateTokenStats(from: cache, calendar: calendar, week: week)
+        if Self.cacheIncludesCurrentWeek(cache, calendar: calendar, week: week) {
             return cacheStats
         }
 
         return readSessionMetaStats() ?? cacheStats
     }
 
-    static func calculateTokenStats(from cache: StatsCache) -> TokenStats {
-        let formatter = DateFormatter()
-        formatter.dateFormat = "yyyy-MM-dd"
-        let today = formatter.string(from: Date())
-        let weekAgo = formatter.string(from: Calendar.current.date(byAdding: .day, value: -7, to: Date())!)
+    func projectStats() -> [ProjectUsage] {
+        let path = projectsDirectoryPath
+        scannersLock.lock()
+        let scanner = sessionScanners[path] ?? SessionScanner(
+            projectsDirectoryURL: URL(fileURLWithPath: path, isDirectory: true),
+            stateFileURL: scanStateURL
+        )
+        sessionScanners[path] = scanner
+        scannersLock.unlock()
+        return scanner.scan(calendar: calendar, week: weekDefinition())
+    }
+
+    func statsCache() -> StatsCache? {
+        readStatsCache()
+    }
+
+    func tokenHistory(days: Int) -> [DailyTokens] {
+        let cache = readStatsCache()
+        return Self.tokenHistory(from: cache ?? StatsCache(dailyActivity: nil, dailyModelTokens: nil, lastComputedDate: nil), days: days, calendar: calendar)
+    }
+
+    /// Claude Code can be caught mid-write, leaving truncated JSON. A parse
+    /// failure is retried once after `partialReadRetryDelay`; if it still
+    /// fails, the last cache that parsed is used so stats don't drop to zero.
+    /// Nil when the file doesn't exist.
+    private func readStatsCache() -> StatsCache? {
+        let path = statsFilePath
+        guard FileManager.default.fileExists(atPath: path) else { return nil }
+        if let cache = decodeStatsCache(at: path) {
+            return cache
+        }
+
+        Thread.sleep(forTimeInterval: partialReadRetryDelay)
+        if let cache = decodeStatsCache(at: path) {
+            return cache
+        }
+
+        statsCacheLock.lock()
+        defer { statsCacheLock.unlock() }
+        let fallback = lastGoodStatsCache?.path == path ? lastGoodStatsCache?.cache : nil
+        DebugLogger.shared.log(
+            "stats-cache.json unreadable; \(fallback == nil ? "no earlier copy to fall back to" : "using last good copy")",
+            level: .warning,
+            source: "Stats"
+        )
+        return fallback
+    }
+
+    private func decodeStatsCache(at path: String) -> StatsCache? {
+        guard let data = FileManager.default.contents(atPath: path),
+              let cache = try? JSONDecoder().decode(StatsCache.self, from: data) else { return nil }
+        statsCacheLock.lock()
+        lastGoodStatsCache = (path, cache)
+        statsCacheLock.unlock()
+        return cache
+    }
+
+    /// Daily totals for the last `days` days including today, ascending.
+    /// Days missing from the cache are filled with empty entries so a chart
+    /// gets a continuous series.
+    static func tokenHistory(from cache: StatsCache, days: Int, now: Date = Date(), calendar: Calendar = .current) -> [DailyTokens] {
+        guard days > 0 else { return [] }
+        let formatter = dayFormatter(calendar: calendar)
+
+        var tokensByAge: [Int: [String: Int]] = [:]
+        for day in cache.dailyModelTokens ?? [] {
+            guard let age = daysAgo(day.date, now: now, formatter: formatter, calendar: calendar), (0..<days).contains(age) else { continue }
+            tokensByAge[age, default: [:]].merge(day.tokensByModel, uniquingKeysWith: +)
+        }
+
+        let startOfToday = calendar.startOfDay(for: now)
+        return (0..<days).reversed().compactMap { age in
+            guard let date = calendar.date(byAdding: .day, value: -age, to: startOfToday) else { return nil }
+            return DailyTokens(date: formatter.string(from: date), tokensByModel: tokensByAge[age] ?? [:])
+        }
+    }
+
+    /// A rolling week is the last `weekLength` calendar days, today included.
+    static let weekLength = 7
+
+    /// Entries whose date doesn't parse as `yyyy-MM-dd` are skipped and
+    /// logged rather than guessed at.
+    static func calculateTokenStats(
+        from cache: StatsCache,
+        now: Date = Date(),
+        calendar: Calendar = .current,
+        week: WeekDefinition = .rolling
+    ) -> TokenStats {
+        let formatter = dayFormatter(calendar: calendar)
+        let weekDays = daysInWeekSoFar(now: now, calendar: calendar, week: week)
+        var skippedDates: Set<String> = []
 
         var todayTokens = 0
         var weekTokens = 0
         var todayMessages = 0
         var weekMessages = 0
+        var todayTokensByModel: [String: Int] = [:]
+        var weekTokensByModel: [String: Int] = [:]
+        var yesterdayTokens: Int?
+        var yesterdayMessages: Int?
 
         if let dailyTokens = cache.dailyModelTokens {
             for day in dailyTokens {
+                guard let age = daysAgo(day.date, now: now, formatter: formatter, calendar: calendar) else {
+                    skippedDates.insert(day.date)
+                    continue
+                }
                 let dayTotal = day.tokensByModel.values.reduce(0, +)
-                if day.date == today { todayTokens = dayTotal }
-                if day.date >= weekAgo { weekTokens += dayTotal }
+                if age == 0 {
+                    todayTokens = dayTotal
+                    todayTokensByModel = day.tokensByModel
+                }
+                if age == 1 { yesterdayTokens = dayTotal }
+                if (0..<weekDays).contains(age) {
+                    weekTokens += dayTotal
+                    weekTokensByModel.merge(day.tokensByModel, uniquingKeysWith: +)
+                }
             }
         }
 
         if let dailyActivity = cache.dailyActivity {
             for day in dailyActivity {
-                if day.date == today { todayMessages = day.messageCount }
-                if day.date >= weekAgo { weekMessages += day.messageCount }
+                guard let age = daysAgo(day.date, now: now, formatter: formatter, calendar: calendar) else {
+                    skippedDates.insert(day.date)
+                    continue
+                }
+                if age == 0 { todayMessages = day.messageCount }
+                if age == 1 { yesterdayMessages = day.messageCount }
+                if (0..<weekDays).contains(age) { weekMessages += day.messageCount }
             }
         }
 
+        if !skippedDates.isEmpty {
+            DebugLogger.shared.log("Skipped stats entries with unparsable dates: \(skippedDates.sorted().joined(separator: ", "))", level: .warning, source: "Stats")
+        }
+
         return TokenStats(
             todayTokens: todayTokens,
             weekTokens: weekTokens,
             todayMessages: todayMessages,
-            weekMessages: weekMessages
+            weekMessages: weekMessages,
+            todayTokensByModel: todayTokensByModel,
+            weekTokensByModel: weekTokensByModel,
+            yesterdayTokens: yesterdayTokens,
+            yesterdayMessages: yesterdayMessages
         )
     }
 
@@ -93,9 +300,9 @@ final class StatsService: StatsServiceProtocol {
             return nil
         }
 
-        let calendar = Calendar.current
+        let calendar = self.calendar
         let startOfToday = calendar.startOfDay(for: Date())
-        let startOfWeek = calendar.date(byAdding: .day, value: -7, to: startOfToday) ?? startOfToday
+        let startOfWeek = Self.startOfWeek(now: Date(), calendar: calendar, week: weekDefinition())
 
         guard let enumerator = fileManager.enumerator(
             at: projectsURL,
@@ -107,6 +314,9 @@ final class StatsService: StatsServiceProtocol {
 
         var transcriptFiles: [TranscriptFile] = []
         var fingerprint = Hasher()
+        // Same files, different window (a new day or week setting) means different totals.
+        fingerprint.combine(startOfToday)
+        fingerprint.combine(startOfWeek)
         let decoder = JSONDecoder()
         decoder.dateDecodingStrategy = .custom { decoder in
             try Self.decodeISO8601Date(from: decoder)
@@ -141,6 +351,8 @@ final class StatsService: StatsServiceProtocol {
         var weekTokens = 0
         var todayMessages = 0
         var weekMessages = 0
+        var todayTokensByModel: [String: Int] = [:]
+        var weekTokensByModel: [String: Int] = [:]
         var processedHashes = Set<String>()
         var foundTranscriptEntry = false
 
@@ -167,14 +379,18 @@ final class StatsService: StatsServiceProtocol {
                     max(0, usage.cacheCreationInputTokens ?? 0) +
                     max(0, usage.cacheReadInputTokens ?? 0)
 
+                let model = entry.message.model ?? Self.unknownModel
+
                 if entry.timestamp >= startOfToday {
                     todayTokens += tokenTotal
                     todayMessages += 1
+                    todayTokensByModel[model, default: 0] += tokenTotal
                 }
 
                 if entry.timestamp >= startOfWeek {
                     weekTokens += tokenTotal
                     weekMessages += 1
+                    weekTokensByModel[model, default: 0] += tokenTotal
                 }
             }
         }
@@ -183,7 +399,9 @@ final class StatsService: StatsServiceProtocol {
             todayTokens: todayTokens,
             weekTokens: weekTokens,
             todayMessages: todayMessages,
-            weekMessages: weekMessages
+            weekMessages: weekMessages,
+            todayTokensByModel: todayTokensByModel,
+            weekTokensByModel: weekTokensByModel
         ) : nil
 
         cacheTranscriptStats(stats, fingerprint: fingerprintValue)
@@ -222,9 +440,9 @@ extension StatsService {
             return nil
         }
 
-        let formatter = Self.dayFormatter()
-        let today = formatter.string(from: Date())
-        let weekAgo = formatter.string(from: Calendar.current.date(byAdding: .day, value: -7, to: Date())!)
+        let calendar = self.calendar
+        let startOfToday = calendar.startOfDay(for: Date())
+        let startOfWeek = Self.startOfWeek(now: Date(), calendar: calendar, week: weekDefinition())
 
         let decoder = JSONDecoder()
         decoder.dateDecodingStrategy = .iso8601
@@ -245,15 +463,14 @@ extension StatsService {
 
             foundSession = true
 
-            let sessionDay = formatter.string(from: session.startTime)
             let tokenTotal = max(0, session.inputTokens) + max(0, session.outputTokens)
             let messageTotal = max(0, session.userMessageCount) + max(0, session.assistantMessageCount)
 
-            if sessionDay == today {
+            if session.startTime >= startOfToday {
                 todayTokens += tokenTotal
                 todayMessages += messageTotal
             }
-            if sessionDay >= weekAgo {
+            if session.startTime >= startOfWeek {
                 weekTokens += tokenTotal
                 weekMessages += messageTotal
             }
@@ -269,27 +486,59 @@ extension StatsService {
         )
     }
 
-    private static func cacheIncludesCurrentWeek(_ cache: StatsCache) -> Bool {
-        let formatter = dayFormatter()
-        let weekAgo = formatter.string(from: Calendar.current.date(byAdding: .day, value: -7, to: Date())!)
-
-        let mostRecentDate = (
-            (cache.dailyActivity ?? []).map(\.date) +
+    private static func cacheIncludesCurrentWeek(_ cache: StatsCache, calendar: Calendar, week: WeekDefinition) -> Bool {
+        let weekDays = daysInWeekSoFar(now: Date(), calendar: calendar, week: week)
+        let formatter = dayFormatter(calendar: calendar)
+        let dates = (cache.dailyActivity ?? []).map(\.date) +
             (cache.dailyModelTokens ?? []).map(\.date) +
             [cache.lastComputedDate].compactMap { $0 }
-        ).max()
 
-        guard let mostRecentDate else { return false }
-        return mostRecentDate >= weekAgo
+        return dates.contains { date in
+            guard let age = daysAgo(date, now: Date(), formatter: formatter, calendar: calendar) else { return false }
+            return age < weekDays
+        }
+    }
+
+    /// Midnight at the start of the oldest day in the week window. A calendar
+    /// week starts on its first weekday, so on that day it covers only today.
+    static func startOfWeek(now: Date, calendar: Calendar, week: WeekDefinition = .rolling) -> Date {
+        let startOfToday = calendar.startOfDay(for: now)
+        let daysBack: Int
+        switch week {
+        case .rolling:
+            daysBack = weekLength - 1
+        case .startsMonday, .startsSunday:
+            let firstWeekday = week == .startsMonday ? 2 : 1
+            daysBack = (calendar.component(.weekday, from: startOfToday) - firstWeekday + 7) % 7
+        }
+        return calendar.date(byAdding: .day, value: -daysBack, to: startOfToday) ?? startOfToday
+    }
+
+    /// Calendar days from the start of the week through today, inclusive.
+    static func daysInWeekSoFar(now: Date, calendar: Calendar, week: WeekDefinition) -> Int {
+        let start = startOfWeek(now: now, calendar: calendar, week: week)
+        return (calendar.dateComponents([.day], from: start, to: calendar.startOfDay(for: now)).day ?? 0) + 1
     }
 
-    private static func dayFormatter() -> DateFormatter {
+    /// Whole calendar days between a `yyyy-MM-dd` string and `now`: 0 for
+    /// today, 1 for yesterday, negative for future dates. Nil when the string
+    /// doesn't parse, so malformed entries are skipped instead of miscounted.
+    static func daysAgo(_ dateString: String, now: Date, formatter: DateFormatter, calendar: Calendar) -> Int? {
+        guard let date = formatter.date(from: dateString) else { return nil }
+        return calendar.dateComponents([.day], from: calendar.startOfDay(for: date), to: calendar.startOfDay(for: now)).day
+    }
+
+    static func dayFormatter(calendar: Calendar) -> DateFormatter {
         let formatter = DateFormatter()
+        formatter.locale = Locale(identifier: "en_US_POSIX")
+        formatter.calendar = Calendar(identifier: .gregorian)
+        formatter.timeZone = calendar.timeZone
         formatter.dateFormat = "yyyy-MM-dd"
+        formatter.isLenient = false
         return formatter
     }
 
-    private static func uniqueHash(for entry: TranscriptUsageEntry) -> String? {
+    static func uniqueHash(for entry: TranscriptUsageEntry) -> String? {
         guard let messageID = entry.message.id, let requestID = entry.requestId else {
             return nil
         }
@@ -297,7 +546,7 @@ extension StatsService {
         return "\(messageID):\(requestID)"
     }
 
-    private static func decodeISO8601Date(from decoder: Decoder) throws -> Date {
+    static func decodeISO8601Date(from decoder: Decoder) throws -> Date {
         let container = try decoder.singleValueContainer()
         let value = try container.decode(String.self)
 
@@ -317,8 +566,16 @@ extension StatsService {
 
 final class CodexStatsService: StatsServiceProtocol {
     private let databasePath: String
-
-    init(databasePath: String? = nil) {
+    private let weekDefinition: () -> WeekDefinition
+    private let dayBoundaryTimeZone: () -> TimeZone
+
+    init(
+        databasePath: String? = nil,
+        weekDefinition: @escaping () -> WeekDefinition = { .rolling },
+        dayBoundaryTimeZone: @escaping () -> TimeZone = { .current }
+    ) {
+        self.weekDefinition = weekDefinition
+        self.dayBoundaryTimeZone = dayBoundaryTimeZone
         if let databasePath {
             self.databasePath = databasePath
         } else {
@@ -328,6 +585,19 @@ final class CodexStatsService: StatsServiceProtocol {
         }
     }
 
+    /// The Codex state database only records per-thread totals, not a daily series.
+    func tokenHistory(days: Int) -> [DailyTokens] {
+        []
+    }
+
+    func projectStats() -> [ProjectUsage] {
+        []
+    }
+
+    func statsCache() -> StatsCache? {
+        nil
+    }
+
     func readStats() -> TokenStats {
         var db: OpaquePointer?
         guard sqlite3_open_v2(databasePath, &db, SQLITE_OPEN_READONLY, nil) == SQLITE_OK, let db else {
@@ -338,19 +608,18 @@ final class CodexStatsService: StatsServiceProtocol {
         }
         defer { sqlite3_close(db) }
 
-        let calendar = Calendar.current
+        let calendar = StatsService.calendar(in: dayBoundaryTimeZone())
         let startOfToday = Int(calendar.startOfDay(for: Date()).timeIntervalSince1970)
-        let startOfWeek = Int(
-            calendar.date(byAdding: .day, value: -7, to: calendar.startOfDay(for: Date()))?
-                .timeIntervalSince1970 ?? 0
-        )
+        let startOfWeek = Int(StatsService.startOfWeek(now: Date(), calendar: calendar, week: weekDefinition()).timeIntervalSince1970)
 
-        return TokenStats(
+        var stats = TokenStats(
             todayTokens: queryInt(db, sql: "SELECT COALESCE(SUM(tokens_used), 0) FROM threads WHERE model_provider = 'openai' AND created_at >= ?", threshold: startOfToday),
             weekTokens: queryInt(db, sql: "SELECT COALESCE(SUM(tokens_used), 0) FROM threads WHERE model_provider = 'openai' AND created_at >= ?", threshold: startOfWeek),
             todayMessages: queryInt(db, sql: "SELECT COUNT(*) FROM threads WHERE model_provider = 'openai' AND created_at >= ?", threshold: startOfToday),
             weekMessages: queryInt(db, sql: "SELECT COUNT(*) FROM threads WHERE model_provider = 'openai' AND created_at >= ?", threshold: startOfWeek)
         )
+        stats.dayEndsAt = StatsService.endOfDay(now: Date(), calendar: calendar)
+        return stats
     }
 
     private func queryInt(_ db: OpaquePointer, sql: String, threshold: Int) -> Int {
@@ -387,18 +656,19 @@ private struct SessionMeta: Decodable {
     }
 }
 
-private struct TranscriptUsageEntry: Decodable {
+struct TranscriptUsageEntry: Decodable {
     let timestamp: Date
     let requestId: String?
     let message: TranscriptMessage
 }
 
-private struct TranscriptMessage: Decodable {
+struct TranscriptMessage: Decodable {
     let usage: TranscriptTokenUsage
     let id: String?
+    let model: String?
 }
 
-private struct TranscriptTokenUsage: Decodable {
+struct TranscriptTokenUsage: Decodable {
     let inputTokens: Int
     let outputTokens: Int
     let cacheCreationInputTokens: Int?
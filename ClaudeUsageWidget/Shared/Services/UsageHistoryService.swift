@@ -0,0 +1,389 @@
+import Foundation
+import SQLite3
+
+enum UsageHistoryError: Error, Equatable {
+    case database(String)
+}
+
+/// One bucket of a downsampled history series. Percentages are the highest
+/// seen in the bucket, so short spikes toward a limit survive downsampling.
+struct UsageHistoryPoint: Equatable {
+    let recordedAt: Date
+    let fiveHourPercent: Double?
+    let sevenDayPercent: Double?
+    let sevenDaySonnetPercent: Double?
+    let sevenDayOpusPercent: Double?
+    let todayTokens: Int
+    /// Only set on raw rows; downsampled series leave it nil.
+    var weekTokens: Int? = nil
+}
+
+/// Highest utilization of each window on one calendar day. Days without a
+/// successful refresh have no entry.
+struct DailyPeak: Equatable {
+    /// `yyyy-MM-dd` in the day-boundary time zone.
+    let day: String
+    let fiveHourPercent: Double?
+    let sevenDayPercent: Double?
+    let sevenDaySonnetPercent: Double?
+    let sevenDayOpusPercent: Double?
+}
+
+/// Appends each successful snapshot to a local SQLite database so usage can be
+/// charted over days and weeks. Rows older than the retention window are
+/// pruned on launch.
+final class UsageHistoryService: UsageHistoryServiceProtocol {
+    static let defaultRetention: TimeInterval = 90 * 24 * 3600
+
+    /// Applied in order; `PRAGMA user_version` records how many have run. Add
+    /// new steps at the end, never edit shipped ones.
+    static let migrations = [
+        """
+        CREATE TABLE IF NOT EXISTS usage_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            recorded_at REAL NOT NULL,
+            five_hour_percent REAL,
+            seven_day_percent REAL,
+            seven_day_sonnet_percent REAL,
+            seven_day_opus_percent REAL,
+            today_tokens INTEGER NOT NULL,
+            week_tokens INTEGER NOT NULL,
+            snapshot TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS usage_history_recorded_at ON usage_history (recorded_at);
+        """,
+        """
+        CREATE TABLE IF NOT EXISTS daily_peaks (
+            day TEXT PRIMARY KEY,
+            five_hour_percent REAL,
+            seven_day_percent REAL,
+            seven_day_sonnet_percent REAL,
+            seven_day_opus_percent REAL
+        );
+        """
+    ]
+
+    private let databaseURL: URL
+
+    init(databaseURL: URL? = nil) {
+        if let databaseURL {
+            self.databaseURL = databaseURL
+        } else {
+            self.databaseURL = FileManager.default.homeDirectoryForCurrentUser
+                .appendingPathComponent(".claude-widget/history.db")
+        }
+    }
+
+    func record(_ snapshot: UsageSnapshot) throws {
+        let json = try UsageSnapshot.makeEncoder().encode(snapshot)
+
+        try withDatabase { db in
+            let sql = """
+            INSERT INTO usage_history (
+                recorded_at, five_hour_percent, seven_day_percent, seven_day_sonnet_percent,
+                seven_day_opus_percent, today_tokens, week_tokens, snapshot
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            """
+            try execute(db, sql: sql) { statement in
+                sqlite3_bind_double(statement, 1, snapshot.lastUpdated.timeIntervalSince1970)
+                bindPercent(statement, 2, snapshot.fiveHour)
+                bindPercent(statement, 3, snapshot.sevenDay)
+                bindPercent(statement, 4, snapshot.sevenDaySonnet)
+                bindPercent(statement, 5, snapshot.sevenDayOpus)
+                sqlite3_bind_int64(statement, 6, sqlite3_int64(snapshot.tokenStats.todayTokens))
+                sqlite3_bind_int64(statement, 7, sqlite3_int64(snapshot.tokenStats.weekTokens))
+                sqlite3_bind_text(statement, 8, String(decoding: json, as: UTF8.self), -1, sqliteTransient)
+            }
+        }
+    }
+
+    func snapshots(from start: Date, to end: Date) throws -> [UsageSnapshot] {
+        try withDatabase { db in
+            let sql = "SELECT snapshot FROM usage_history WHERE recorded_at >= ? AND recorded_at <= ? ORDER BY recorded_at"
+            var statement: OpaquePointer?
+            guard sqlite3_prepare_v2(db, sql, -1, &statement, nil) == SQLITE_OK, let statement else {
+                throw UsageHistoryError.database(Self.message(db))
+            }
+            defer { sqlite3_finalize(statement) }
+
+            sqlite3_bind_double(statement, 1, start.timeIntervalSince1970)
+            sqlite3_bind_double(statement, 2, end.timeIntervalSince1970)
+
+            let decoder = UsageSnapshot.makeDecoder()
+            var results: [UsageSnapshot] = []
+            while sqlite3_step(statement) == SQLITE_ROW {
+                guard let text = sqlite3_column_text(statement, 0) else { continue }
+                let data = Data(String(cString: text).utf8)
+                if let snapshot = try? decoder.decode(UsageSnapshot.self, from: data) {
+                    results.append(snapshot)
+                }
+            }
+            return results
+        }
+    }
+
+    /// Calls `body` with each row between `start` and `end`, oldest first,
+    /// without collecting them.
+    func forEachPoint(from start: Date, to end: Date, _ body: (UsageHistoryPoint) throws -> Void) throws {
+        try withDatabase { db in
+            let sql = """
+            SELECT recorded_at, five_hour_percent, seven_day_percent, seven_day_sonnet_percent,
+                   seven_day_opus_percent, today_tokens, week_tokens
+            FROM usage_history
+            WHERE recorded_at >= ? AND recorded_at <= ?
+            ORDER BY recorded_at
+            """
+            var statement: OpaquePointer?
+            guard sqlite3_prepare_v2(db, sql, -1, &statement, nil) == SQLITE_OK, let statement else {
+                throw UsageHistoryError.database(Self.message(db))
+            }
+            defer { sqlite3_finalize(statement) }
+
+            sqlite3_bind_double(statement, 1, start.timeIntervalSince1970)
+            sqlite3_bind_double(statement, 2, end.timeIntervalSince1970)
+
+            while sqlite3_step(statement) == SQLITE_ROW {
+                try body(UsageHistoryPoint(
+                    recordedAt: Date(timeIntervalSince1970: sqlite3_column_double(statement, 0)),
+                    fiveHourPercent: Self.optionalDouble(statement, 1),
+                    sevenDayPercent: Self.optionalDouble(statement, 2),
+                    sevenDaySonnetPercent: Self.optionalDouble(statement, 3),
+                    sevenDayOpusPercent: Self.optionalDouble(statement, 4),
+                    todayTokens: Int(sqlite3_column_int64(statement, 5)),
+                    weekTokens: Int(sqlite3_column_int64(statement, 6))
+                ))
+            }
+        }
+    }
+
+    /// Rows between `start` and `end` grouped into `resolution`-second buckets,
+    /// oldest first. Each point is stamped with the start of its bucket.
+    func series(from start: Date, to end: Date, resolution: TimeInterval) throws -> [UsageHistoryPoint] {
+        let bucket = max(resolution, 1)
+        return try withDatabase { db in
+            let sql = """
+            SELECT CAST(recorded_at / ?1 AS INTEGER) * ?1 AS bucket,
+                   MAX(five_hour_percent), MAX(seven_day_percent),
+                   MAX(seven_day_sonnet_percent), MAX(seven_day_opus_percent),
+                   MAX(today_tokens)
+            FROM usage_history
+            WHERE recorded_at >= ?2 AND recorded_at <= ?3
+            GROUP BY bucket
+            ORDER BY bucket
+            """
+            var statement: OpaquePointer?
+            guard sqlite3_prepare_v2(db, sql, -1, &statement, nil) == SQLITE_OK, let statement else {
+                throw UsageHistoryError.database(Self.message(db))
+            }
+            defer { sqlite3_finalize(statement) }
+
+            sqlite3_bind_double(statement, 1, bucket)
+            sqlite3_bind_double(statement, 2, start.timeIntervalSince1970)
+            sqlite3_bind_double(statement, 3, end.timeIntervalSince1970)
+
+            var points: [UsageHistoryPoint] = []
+            while sqlite3_step(statement) == SQLITE_ROW {
+                points.append(UsageHistoryPoint(
+                    recordedAt: Date(timeIntervalSince1970: sqlite3_column_double(statement, 0)),
+                    fiveHourPercent: Self.optionalDouble(statement, 1),
+                    sevenDayPercent: Self.optionalDouble(statement, 2),
+                    sevenDaySonnetPercent: Self.optionalDouble(statement, 3),
+                    sevenDayOpusPercent: Self.optionalDouble(statement, 4),
+                    todayTokens: Int(sqlite3_column_int64(statement, 5))
+                ))
+            }
+            return points
+        }
+    }
+
+    /// Stores a `DailyPeak` for every day before the one containing `now`
+    /// that has samples and comes after the last day already rolled up.
+    /// Returns the number of days written. Peaks outlive history pruning.
+    @discardableResult
+    func rollUpDailyPeaks(now: Date, calendar: Calendar) throws -> Int {
+        let formatter = StatsService.dayFormatter(calendar: calendar)
+        return try withDatabase { db in
+            var start = Date(timeIntervalSince1970: 0)
+            if let lastDay = try Self.queryText(db, sql: "SELECT MAX(day) FROM daily_peaks"),
+               let lastDate = formatter.date(from: lastDay),
+               let next = calendar.date(byAdding: .day, value: 1, to: lastDate) {
+                start = next
+            }
+            let end = calendar.startOfDay(for: now)
+            guard start < end else { return 0 }
+
+            var peaks: [String: [Double?]] = [:]
+            let sql = """
+            SELECT recorded_at, five_hour_percent, seven_day_percent, seven_day_sonnet_percent, seven_day_opus_percent
+            FROM usage_history WHERE recorded_at >= ? AND recorded_at < ?
+            """
+            var statement: OpaquePointer?
+            guard sqlite3_prepare_v2(db, sql, -1, &statement, nil) == SQLITE_OK, let statement else {
+                throw UsageHistoryError.database(Self.message(db))
+            }
+            sqlite3_bind_double(statement, 1, start.timeIntervalSince1970)
+            sqlite3_bind_double(statement, 2, end.timeIntervalSince1970)
+            while sqlite3_step(statement) == SQLITE_ROW {
+                let day = formatter.string(from: Date(timeIntervalSince1970: sqlite3_column_double(statement, 0)))
+                let sample = (1...4).map { Self.optionalDouble(statement, Int32($0)) }
+                let current = peaks[day] ?? [nil, nil, nil, nil]
+                peaks[day] = zip(current, sample).map { old, new in [old, new].compactMap { $0 }.max() }
+            }
+            sqlite3_finalize(statement)
+
+            guard !peaks.isEmpty else { return 0 }
+            guard sqlite3_exec(db, "BEGIN", nil, nil, nil) == SQLITE_OK else {
+                throw UsageHistoryError.database(Self.message(db))
+            }
+            do {
+                for (day, values) in peaks {
+                    let insert = """
+                    INSERT OR REPLACE INTO daily_peaks (
+                        day, five_hour_percent, seven_day_percent, seven_day_sonnet_percent, seven_day_opus_percent
+                    ) VALUES (?, ?, ?, ?, ?)
+                    """
+                    try execute(db, sql: insert) { statement in
+                        sqlite3_bind_text(statement, 1, day, -1, sqliteTransient)
+                        for (offset, value) in values.enumerated() {
+                            bindDouble(statement, Int32(offset + 2), value)
+                        }
+                    }
+                }
+            } catch {
+                sqlite3_exec(db, "ROLLBACK", nil, nil, nil)
+                throw error
+            }
+            guard sqlite3_exec(db, "COMMIT", nil, nil, nil) == SQLITE_OK else {
+                let message = Self.message(db)
+                sqlite3_exec(db, "ROLLBACK", nil, nil, nil)
+                throw UsageHistoryError.database(message)
+            }
+            return peaks.count
+        }
+    }
+
+    /// The most recent `days` rolled-up days, oldest first.
+    func dailyPeaks(days: Int) throws -> [DailyPeak] {
+        try withDatabase { db in
+            let sql = """
+            SELECT day, five_hour_percent, seven_day_percent, seven_day_sonnet_percent, seven_day_opus_percent
+            FROM daily_peaks ORDER BY day DESC LIMIT ?
+            """
+            var statement: OpaquePointer?
+            guard sqlite3_prepare_v2(db, sql, -1, &statement, nil) == SQLITE_OK, let statement else {
+                throw UsageHistoryError.database(Self.message(db))
+            }
+            defer { sqlite3_finalize(statement) }
+
+            sqlite3_bind_int64(statement, 1, sqlite3_int64(max(0, days)))
+            var peaks: [DailyPeak] = []
+            while sqlite3_step(statement) == SQLITE_ROW {
+                guard let text = sqlite3_column_text(statement, 0) else { continue }
+                peaks.append(DailyPeak(
+                    day: String(cString: text),
+                    fiveHourPercent: Self.optionalDouble(statement, 1),
+                    sevenDayPercent: Self.optionalDouble(statement, 2),
+                    sevenDaySonnetPercent: Self.optionalDouble(statement, 3),
+                    sevenDayOpusPercent: Self.optionalDouble(statement, 4)
+                ))
+            }
+            return peaks.reversed()
+        }
+    }
+
+    func prune(olderThan cutoff: Date) throws {
+        try withDatabase { db in
+            try execute(db, sql: "DELETE FROM usage_history WHERE recorded_at < ?") { statement in
+                sqlite3_bind_double(statement, 1, cutoff.timeIntervalSince1970)
+            }
+        }
+    }
+
+    private func withDatabase<T>(_ work: (OpaquePointer) throws -> T) throws -> T {
+        try FileManager.default.createDirectory(
+            at: databaseURL.deletingLastPathComponent(),
+            withIntermediateDirectories: true
+        )
+
+        var db: OpaquePointer?
+        guard sqlite3_open_v2(databaseURL.path, &db, SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE, nil) == SQLITE_OK, let db else {
+            let message = db.map(Self.message) ?? "unable to open \(databaseURL.path)"
+            if db != nil {
+                sqlite3_close(db)
+            }
+            throw UsageHistoryError.database(message)
+        }
+        defer { sqlite3_close(db) }
+
+        try migrate(db)
+        return try work(db)
+    }
+
+    /// Databases created before versioning have the first migration's table
+    /// but a `user_version` of 0; its `IF NOT EXISTS` makes rerunning it safe.
+    private func migrate(_ db: OpaquePointer) throws {
+        let version = Self.userVersion(db)
+        guard version < Self.migrations.count else { return }
+
+        for (index, sql) in Self.migrations.enumerated().dropFirst(version) {
+            let step = "BEGIN; \(sql) PRAGMA user_version = \(index + 1); COMMIT;"
+            guard sqlite3_exec(db, step, nil, nil, nil) == SQLITE_OK else {
+                let message = Self.message(db)
+                sqlite3_exec(db, "ROLLBACK", nil, nil, nil)
+                throw UsageHistoryError.database(message)
+            }
+        }
+    }
+
+    static func userVersion(_ db: OpaquePointer) -> Int {
+        var statement: OpaquePointer?
+        guard sqlite3_prepare_v2(db, "PRAGMA user_version", -1, &statement, nil) == SQLITE_OK, let statement else { return 0 }
+        defer { sqlite3_finalize(statement) }
+        return sqlite3_step(statement) == SQLITE_ROW ? Int(sqlite3_column_int64(statement, 0)) : 0
+    }
+
+    private func execute(_ db: OpaquePointer, sql: String, bind: (OpaquePointer) -> Void) throws {
+        var statement: OpaquePointer?
+        guard sqlite3_prepare_v2(db, sql, -1, &statement, nil) == SQLITE_OK, let statement else {
+            throw UsageHistoryError.database(Self.message(db))
+        }
+        defer { sqlite3_finalize(statement) }
+
+        bind(statement)
+        guard sqlite3_step(statement) == SQLITE_DONE else {
+            throw UsageHistoryError.database(Self.message(db))
+        }
+    }
+
+    private func bindPercent(_ statement: OpaquePointer, _ index: Int32, _ metric: UsageMetric?) {
+        bindDouble(statement, index, metric?.percent)
+    }
+
+    private func bindDouble(_ statement: OpaquePointer, _ index: Int32, _ value: Double?) {
+        if let value {
+            sqlite3_bind_double(statement, index, value)
+        } else {
+            sqlite3_bind_null(statement, index)
+        }
+    }
+
+    private static func queryText(_ db: OpaquePointer, sql: String) throws -> String? {
+        var statement: OpaquePointer?
+        guard sqlite3_prepare_v2(db, sql, -1, &statement, nil) == SQLITE_OK, let statement else {
+            throw UsageHistoryError.database(message(db))
+        }
+        defer { sqlite3_finalize(statement) }
+        guard sqlite3_step(statement) == SQLITE_ROW, let text = sqlite3_column_text(statement, 0) else { return nil }
+        return String(cString: text)
+    }
+
+    private static func optionalDouble(_ statement: OpaquePointer, _ column: Int32) -> Double? {
+        sqlite3_column_type(statement, column) == SQLITE_NULL ? nil : sqlite3_column_double(statement, column)
+    }
+
+    private static func message(_ db: OpaquePointer) -> String {
+        String(cString: sqlite3_errmsg(db))
+    }
+}
+
+private let sqliteTransient = unsafeBitCast(-1, to: sqlite3_destructor_type.self)
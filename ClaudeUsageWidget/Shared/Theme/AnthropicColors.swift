@@ -36,6 +36,14 @@ enum AnthropicColors {
         colors: [codexBlue, codexTeal], startPoint: .leading, endPoint: .trailing
     )
 
+    static func statusGradient(for status: UsageStatus, isOpus: Bool = false) -> LinearGradient {
+        switch status {
+        case .critical: return dangerGradient
+        case .warning: return warningGradient
+        case .ok: return isOpus ? opusGradient : normalGradient
+        }
+    }
+
     static func paceColor(for status: PaceStatus) -> Color {
         switch status {
         case .under: return paceGreen
@@ -7,6 +7,10 @@ enum MenuBarIconTier: Equatable {
     case moderate
     case high
     case critical
+    /// The last refresh failed.
+    case error
+    /// Scheduled refresh is paused.
+    case paused
 
     var symbolName: String {
         switch self {
@@ -15,12 +19,14 @@ enum MenuBarIconTier: Equatable {
         case .moderate: return "gauge.open.with.lines.needle.50percent"
         case .high:     return "gauge.open.with.lines.needle.67percent"
         case .critical: return "gauge.open.with.lines.needle.84percent"
+        case .error:    return "exclamationmark.triangle"
+        case .paused:   return "pause.circle"
         }
     }
 
     var tintNSColor: NSColor {
         switch self {
-        case .idle:     return .labelColor
+        case .idle, .error, .paused: return .labelColor
         case .low:      return NSColor(AnthropicColors.iconGreen)
         case .moderate: return NSColor(AnthropicColors.tan)
         case .high:     return NSColor(AnthropicColors.coral)
@@ -35,21 +41,37 @@ enum MenuBarIconTier: Equatable {
         case .moderate: return "Claude Usage: Moderate"
         case .high:     return "Claude Usage: High"
         case .critical: return "Claude Usage: Critical"
+        case .error:    return "Claude Usage: Error"
+        case .paused:   return "Claude Usage: Paused"
         }
     }
 
-    static func from(percent: Double) -> MenuBarIconTier {
+    /// Paused is drawn greyed out.
+    var menuBarOpacity: Double {
+        self == .paused ? 0.5 : 1
+    }
+
+    static func from(percent: Double, thresholds: IconThresholds = .standard) -> MenuBarIconTier {
         switch percent {
-        case ..<40:  return .low
-        case ..<70:  return .moderate
-        case ..<90:  return .high
-        default:     return .critical
+        case ..<thresholds.moderate: return .low
+        case ..<thresholds.high:     return .moderate
+        case ..<thresholds.critical: return .high
+        default:                     return .critical
         }
     }
 
+    /// Paused wins over an error, which wins over the usage level.
+    static func current(snapshot: UsageSnapshot?, isPaused: Bool, thresholds: IconThresholds = .standard) -> MenuBarIconTier {
+        if isPaused { return .paused }
+        guard let snapshot else { return .idle }
+        if snapshot.error != nil { return .error }
+        guard let percent = snapshot.maxUsagePercent else { return .idle }
+        return from(percent: percent, thresholds: thresholds)
+    }
+
     /// Renders the SF Symbol as a tinted NSImage suitable for the menu bar.
-    /// For the `.idle` case, returns a template image so macOS handles
-    /// dark/light mode automatically. For all other tiers, returns a
+    /// For `.idle`, `.error` and `.paused`, returns a template image so macOS
+    /// handles dark/light mode automatically. For all other tiers, returns a
     /// non-template image with the tier color baked in.
     func menuBarImage() -> NSImage {
         guard let baseImage = NSImage(
@@ -63,7 +85,7 @@ enum MenuBarIconTier: Equatable {
             return NSImage(size: NSSize(width: 18, height: 18))
         }
 
-        if self == .idle {
+        if [.idle, .error, .paused].contains(self) {
             baseImage.isTemplate = true
             return baseImage
         }
@@ -83,3 +105,12 @@ enum MenuBarIconTier: Equatable {
         return tinted
     }
 }
+
+/// Utilization at which the icon moves up a tier.
+struct IconThresholds: Equatable {
+    let moderate: Double
+    let high: Double
+    let critical: Double
+
+    static let standard = IconThresholds(moderate: 40, high: 70, critical: 90)
+}
@@ -85,6 +85,22 @@ final class APIModelsTests: XCTestCase {
         XCTAssertNil(snapshot.error)
     }
 
+    func testToSnapshotKeepsRawResetAndFlagsUnparsableOnes() {
+        let response = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 45.0, resetsAt: "2026-03-21T18:00:00.123+00:00"),
+            sevenDay: UsageWindow(utilization: 30.0, resetsAt: "next tuesday"),
+            sevenDaySonnet: nil,
+            sevenDayOpus: nil
+        )
+        let snapshot = response.toSnapshot(tokenStats: .zero)
+
+        XCTAssertEqual(snapshot.fiveHour?.rawResetsAt, "2026-03-21T18:00:00.123+00:00")
+        XCTAssertEqual(snapshot.fiveHour?.hasResetTime, true)
+        XCTAssertEqual(snapshot.sevenDay?.rawResetsAt, "next tuesday")
+        XCTAssertEqual(snapshot.sevenDay?.hasResetTime, false)
+        XCTAssertNil(snapshot.sevenDay?.secondsUntilReset())
+    }
+
     func testToSnapshotSetsLastSuccessfulUpdate() {
         let response = UsageApiResponse(
             fiveHour: UsageWindow(utilization: 45.0, resetsAt: "2026-03-21T18:00:00Z"),
@@ -25,6 +25,14 @@ final class MockURLProtocol: URLProtocol {
     override func stopLoading() {}
 }
 
+/// Accepts the request and never answers, so only the session timeout can end it.
+final class HangingURLProtocol: URLProtocol {
+    override class func canInit(with request: URLRequest) -> Bool { true }
+    override class func canonicalRequest(for request: URLRequest) -> URLRequest { request }
+    override func startLoading() {}
+    override func stopLoading() {}
+}
+
 final class APIServiceTests: XCTestCase {
     var service: APIService!
     var codexService: CodexAPIService!
@@ -32,7 +40,7 @@ final class APIServiceTests: XCTestCase {
     override func setUp() {
         let config = URLSessionConfiguration.ephemeral
         config.protocolClasses = [MockURLProtocol.self]
-        service = APIService(session: URLSession(configuration: config))
+        service = APIService(session: URLSession(configuration: config), retryPolicy: { .none })
         codexService = CodexAPIService(session: URLSession(configuration: config))
     }
 
@@ -122,6 +130,609 @@ final class APIServiceTests: XCTestCase {
         }
     }
 
+    func testFetchUsage429ThrowsRateLimitedWithRetryAfter() async {
+        MockURLProtocol.requestHandler = { request in
+            let response = HTTPURLResponse(url: request.url!, statusCode: 429, httpVersion: nil, headerFields: ["Retry-After": "120"])!
+            return (response, Data())
+        }
+
+        do {
+            _ = try await service.fetchUsage(token: "token")
+            XCTFail("Expected rate limited error")
+        } catch APIError.rateLimited(let retryAfter) {
+            XCTAssertEqual(retryAfter, 120)
+        } catch {
+            XCTFail("Unexpected error: \(error)")
+        }
+    }
+
+    func testParseRetryAfterSeconds() {
+        XCTAssertEqual(APIService.parseRetryAfter("30"), 30)
+        XCTAssertEqual(APIService.parseRetryAfter(" 0 "), 0)
+        XCTAssertNil(APIService.parseRetryAfter("-5"))
+        XCTAssertNil(APIService.parseRetryAfter(nil))
+        XCTAssertNil(APIService.parseRetryAfter("soon"))
+    }
+
+    func testParseRetryAfterHTTPDate() {
+        let now = Date(timeIntervalSince1970: 1_792_567_600) // Wed, 21 Oct 2026 07:26:40 GMT
+        XCTAssertEqual(APIService.parseRetryAfter("Wed, 21 Oct 2026 07:28:00 GMT", now: now), 80)
+        XCTAssertEqual(APIService.parseRetryAfter("Wed, 21 Oct 2026 07:00:00 GMT", now: now), 0)
+    }
+
+    func testParseRateLimitResetUsesLatestAnthropicHeader() {
+        let now = Date(timeIntervalSince1970: 1_792_567_600) // 2026-10-21T07:26:40Z
+        let headers: [AnyHashable: Any] = [
+            "anthropic-ratelimit-requests-reset": "2026-10-21T07:27:40Z",
+            "Anthropic-Ratelimit-Tokens-Reset": "2026-10-21T07:30:00.500Z",
+            "anthropic-ratelimit-requests-remaining": "0",
+            "Content-Type": "application/json"
+        ]
+
+        XCTAssertEqual(APIService.parseRateLimitReset(headers, now: now) ?? 0, 200.5, accuracy: 0.001)
+        XCTAssertEqual(APIService.parseRateLimitReset(["anthropic-ratelimit-unified-reset": "1792567660"], now: now), 60)
+        XCTAssertNil(APIService.parseRateLimitReset(["anthropic-ratelimit-requests-reset": "soon"], now: now))
+        XCTAssertNil(APIService.parseRateLimitReset([:], now: now))
+    }
+
+    func testFetchUsage429FallsBackToRateLimitResetHeaders() async {
+        let clock = MutableClock()
+        clock.now = Date(timeIntervalSince1970: 1_792_567_600)
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [MockURLProtocol.self]
+        let limited = APIService(session: URLSession(configuration: config), retryPolicy: { .none }, now: { clock.now })
+        MockURLProtocol.requestHandler = { request in
+            let response = HTTPURLResponse(url: request.url!, statusCode: 429, httpVersion: nil, headerFields: [
+                "anthropic-ratelimit-requests-reset": "2026-10-21T07:28:00Z"
+            ])!
+            return (response, Data())
+        }
+
+        do {
+            _ = try await limited.fetchUsage(token: "token")
+            XCTFail("Expected rate limited error")
+        } catch APIError.rateLimited(let retryAfter) {
+            XCTAssertEqual(retryAfter, 80)
+        } catch {
+            XCTFail("Unexpected error: \(error)")
+        }
+    }
+
+    func testRateLimitedIsOnlyRetriedForShortRetryAfter() {
+        XCTAssertTrue(APIService.isRetryable(APIError.rateLimited(retryAfter: 2)))
+        XCTAssertFalse(APIService.isRetryable(APIError.rateLimited(retryAfter: 30)))
+        XCTAssertFalse(APIService.isRetryable(APIError.rateLimited(retryAfter: nil)))
+        XCTAssertFalse(APIService.isRetryable(APIError.unauthorized))
+        XCTAssertFalse(APIService.isRetryable(APIError.forbidden))
+    }
+
+    func testFetchUsageWaitsOutShortRetryAfter() async throws {
+        let delays = SleepRecorder()
+        let retrying = makeRetryingService(delays: delays)
+        var attempts = 0
+        MockURLProtocol.requestHandler = { request in
+            attempts += 1
+            let status = attempts == 1 ? 429 : 200
+            let response = HTTPURLResponse(url: request.url!, statusCode: status, httpVersion: nil, headerFields: ["Retry-After": "2"])!
+            return (response, #"{"five_hour": {"utilization": 12.0, "resets_at": "2026-03-21T18:00:00Z"}}"#.data(using: .utf8)!)
+        }
+
+        _ = try await retrying.fetchUsage(token: "token")
+
+        XCTAssertEqual(attempts, 2)
+        XCTAssertEqual(delays.values, [2])
+    }
+
+    // MARK: - Refresh backoff
+
+    func testRefreshBackoffStartsAfterSecondConsecutiveFailure() {
+        let now = Date(timeIntervalSince1970: 1_000_000)
+        var backoff = RefreshBackoff()
+
+        backoff.recordFailure(APIError.networkError("offline"), now: now, jitterFraction: 0)
+        XCTAssertFalse(backoff.isBackingOff(now: now))
+
+        backoff.recordFailure(APIError.serverError(503), now: now, jitterFraction: 0)
+        XCTAssertEqual(backoff.retryAt, now.addingTimeInterval(60))
+
+        backoff.recordFailure(APIError.serverError(503), now: now, jitterFraction: 0)
+        XCTAssertEqual(backoff.retryAt, now.addingTimeInterval(120))
+        XCTAssertTrue(backoff.isBackingOff(now: now.addingTimeInterval(119)))
+        XCTAssertFalse(backoff.isBackingOff(now: now.addingTimeInterval(120)))
+    }
+
+    func testRefreshBackoffIsCapped() {
+        let now = Date(timeIntervalSince1970: 1_000_000)
+        var backoff = RefreshBackoff()
+        for _ in 0..<20 {
+            backoff.recordFailure(APIError.networkError("offline"), now: now, jitterFraction: 0.2)
+        }
+
+        XCTAssertEqual(backoff.retryAt, now.addingTimeInterval(RefreshBackoff.maxDelay))
+    }
+
+    func testRefreshBackoffHonorsRetryAfterAndIgnoresAuthErrors() {
+        let now = Date(timeIntervalSince1970: 1_000_000)
+        var backoff = RefreshBackoff()
+
+        backoff.recordFailure(APIError.unauthorized, now: now)
+        XCTAssertEqual(backoff.consecutiveFailures, 0)
+        XCTAssertNil(backoff.retryAt)
+
+        backoff.recordFailure(APIError.rateLimited(retryAfter: 600), now: now)
+        XCTAssertEqual(backoff.retryAt, now.addingTimeInterval(600))
+        XCTAssertTrue(backoff.isServerMandated)
+
+        backoff.reset()
+        XCTAssertEqual(backoff, RefreshBackoff())
+    }
+
+    // MARK: - Retry
+
+    private func makeRetryingService(maxRetries: Int = 3, delays: SleepRecorder) -> APIService {
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [MockURLProtocol.self]
+        return APIService(
+            session: URLSession(configuration: config),
+            retryPolicy: { RetryPolicy(maxRetries: maxRetries, baseDelay: 0.5) },
+            sleep: { delays.record($0) }
+        )
+    }
+
+    func testFetchUsageRetriesServerErrorsThenSucceeds() async throws {
+        let delays = SleepRecorder()
+        let retrying = makeRetryingService(delays: delays)
+        var attempts = 0
+        MockURLProtocol.requestHandler = { request in
+            attempts += 1
+            let status = attempts < 3 ? 503 : 200
+            let response = HTTPURLResponse(url: request.url!, statusCode: status, httpVersion: nil, headerFields: nil)!
+            return (response, #"{"five_hour": {"utilization": 12.0, "resets_at": "2026-03-21T18:00:00Z"}}"#.data(using: .utf8)!)
+        }
+
+        let result = try await retrying.fetchUsage(token: "token")
+
+        XCTAssertEqual(attempts, 3)
+        XCTAssertEqual(result.fiveHour?.utilization, 12.0)
+        XCTAssertEqual(delays.values.count, 2)
+    }
+
+    func testFetchUsageDoesNotRetryClientErrors() async {
+        let delays = SleepRecorder()
+        let retrying = makeRetryingService(delays: delays)
+        var attempts = 0
+        MockURLProtocol.requestHandler = { request in
+            attempts += 1
+            let response = HTTPURLResponse(url: request.url!, statusCode: 401, httpVersion: nil, headerFields: nil)!
+            return (response, Data())
+        }
+
+        do {
+            _ = try await retrying.fetchUsage(token: "token")
+            XCTFail("Expected unauthorized error")
+        } catch APIError.unauthorized {
+            // expected
+        } catch {
+            XCTFail("Unexpected error: \(error)")
+        }
+        XCTAssertEqual(attempts, 1)
+        XCTAssertTrue(delays.values.isEmpty)
+    }
+
+    func testFetchUsageRetriesConnectionErrorsAndReturnsFinalError() async {
+        let delays = SleepRecorder()
+        let retrying = makeRetryingService(maxRetries: 2, delays: delays)
+        var attempts = 0
+        MockURLProtocol.requestHandler = { _ in
+            attempts += 1
+            throw URLError(.networkConnectionLost)
+        }
+
+        do {
+            _ = try await retrying.fetchUsage(token: "token")
+            XCTFail("Expected network error")
+        } catch APIError.networkError {
+            // expected
+        } catch {
+            XCTFail("Unexpected error: \(error)")
+        }
+        XCTAssertEqual(attempts, 3, "One initial attempt plus two retries")
+        XCTAssertEqual(delays.values.count, 2)
+    }
+
+    func testUsageURLKeepsGatewayPathPrefix() {
+        XCTAssertEqual(APIService.usageURL(baseURL: APIService.defaultBaseURL).absoluteString, "https://api.anthropic.com/api/oauth/usage")
+        XCTAssertEqual(
+            APIService.usageURL(baseURL: URL(string: "https://gateway.corp/anthropic")!).absoluteString,
+            "https://gateway.corp/anthropic/api/oauth/usage"
+        )
+    }
+
+    func testFetchUsageReadsBaseURLPerRequest() async throws {
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [MockURLProtocol.self]
+        var baseURL = URL(string: "https://gateway-a.corp")!
+        let gatewayService = APIService(session: URLSession(configuration: config), baseURL: { baseURL }, retryPolicy: { .none })
+        var requestedHosts: [String] = []
+        MockURLProtocol.requestHandler = { request in
+            requestedHosts.append(request.url?.host ?? "")
+            let response = HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!
+            return (response, "{}".data(using: .utf8)!)
+        }
+
+        _ = try await gatewayService.fetchUsage(token: "token")
+        baseURL = URL(string: "https://gateway-b.corp")!
+        _ = try await gatewayService.fetchUsage(token: "token")
+
+        XCTAssertEqual(requestedHosts, ["gateway-a.corp", "gateway-b.corp"])
+    }
+
+    // MARK: - Profile
+
+    func testFetchProfileReadsPlanAndOrganization() async throws {
+        let responseJSON = """
+        {
+            "account": {"has_claude_max": true, "has_claude_pro": false},
+            "organization": {"name": "Acme", "organization_type": "claude_max", "rate_limit_tier": "default_claude_max_20x"}
+        }
+        """.data(using: .utf8)!
+        MockURLProtocol.requestHandler = { request in
+            XCTAssertEqual(request.url?.path, "/api/oauth/profile")
+            XCTAssertEqual(request.value(forHTTPHeaderField: "Authorization"), "Bearer test-token")
+            let response = HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!
+            return (response, responseJSON)
+        }
+
+        let plan = try await service.fetchProfile(token: "test-token")
+        XCTAssertEqual(plan, PlanInfo(name: "Max 20x", organization: "Acme"))
+    }
+
+    func testFetchProfile404ReturnsNil() async throws {
+        MockURLProtocol.requestHandler = { request in
+            let response = HTTPURLResponse(url: request.url!, statusCode: 404, httpVersion: nil, headerFields: nil)!
+            return (response, Data())
+        }
+
+        let plan = try await service.fetchProfile(token: "test-token")
+        XCTAssertNil(plan)
+    }
+
+    func testProbeReachabilitySendsUnauthenticatedHead() async throws {
+        MockURLProtocol.requestHandler = { request in
+            XCTAssertEqual(request.httpMethod, "HEAD")
+            XCTAssertNil(request.value(forHTTPHeaderField: "Authorization"))
+            let response = HTTPURLResponse(url: request.url!, statusCode: 404, httpVersion: nil, headerFields: nil)!
+            return (response, Data())
+        }
+
+        let status = try await service.probeReachability()
+        XCTAssertEqual(status, 404)
+    }
+
+    func testProfilePlanNameFallsBackToAccountFlags() throws {
+        let decoder = JSONDecoder()
+        decoder.keyDecodingStrategy = .convertFromSnakeCase
+        let pro = try decoder.decode(ProfileApiResponse.self, from: Data(#"{"account": {"has_claude_pro": true}}"#.utf8))
+        let empty = try decoder.decode(ProfileApiResponse.self, from: Data("{}".utf8))
+
+        XCTAssertEqual(pro.toPlanInfo(), PlanInfo(name: "Pro", organization: nil))
+        XCTAssertNil(empty.toPlanInfo())
+    }
+
+    // MARK: - Response cache
+
+    private func makeCachingService(cacheTTL: TimeInterval = 30, clock: MutableClock) -> APIService {
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [MockURLProtocol.self]
+        return APIService(session: URLSession(configuration: config), retryPolicy: { .none }, cacheTTL: { cacheTTL }, now: { clock.now })
+    }
+
+    func testFetchUsageRevalidatesWithETagAndReusesBodyOn304() async throws {
+        let clock = MutableClock()
+        let caching = makeCachingService(clock: clock)
+        var conditionalHeaders: [String?] = []
+        MockURLProtocol.requestHandler = { request in
+            let etag = request.value(forHTTPHeaderField: "If-None-Match")
+            conditionalHeaders.append(etag)
+            if etag == "\"v1\"" {
+                return (HTTPURLResponse(url: request.url!, statusCode: 304, httpVersion: nil, headerFields: nil)!, Data())
+            }
+            let response = HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: ["ETag": "\"v1\""])!
+            return (response, #"{"five_hour": {"utilization": 21.0, "resets_at": "2026-03-21T18:00:00Z"}}"#.data(using: .utf8)!)
+        }
+
+        _ = try await caching.fetchUsage(token: "token")
+        let revalidated = try await caching.fetchUsage(token: "token")
+
+        XCTAssertEqual(conditionalHeaders, [nil, "\"v1\""], "Responses with validators are always revalidated")
+        XCTAssertEqual(revalidated.fiveHour?.utilization, 21.0)
+    }
+
+    func testFetchUsageServesResponseWithoutValidatorsForTTL() async throws {
+        let clock = MutableClock()
+        let caching = makeCachingService(cacheTTL: 30, clock: clock)
+        var requests = 0
+        MockURLProtocol.requestHandler = { request in
+            requests += 1
+            let response = HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!
+            return (response, #"{"five_hour": {"utilization": 21.0, "resets_at": "2026-03-21T18:00:00Z"}}"#.data(using: .utf8)!)
+        }
+
+        _ = try await caching.fetchUsage(token: "token")
+        clock.now = clock.now.addingTimeInterval(29)
+        let cached = try await caching.fetchUsage(token: "token")
+        XCTAssertEqual(requests, 1)
+        XCTAssertEqual(cached.fiveHour?.utilization, 21.0)
+
+        _ = try await caching.fetchUsage(token: "other-token")
+        XCTAssertEqual(requests, 2, "A different token never sees another token's cached response")
+
+        clock.now = clock.now.addingTimeInterval(31)
+        _ = try await caching.fetchUsage(token: "other-token")
+        XCTAssertEqual(requests, 3)
+    }
+
+    func testFetchUsageNotAllowingCachedRequestsWithinTTL() async throws {
+        let clock = MutableClock()
+        var ttl: TimeInterval = 30
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [MockURLProtocol.self]
+        let caching = APIService(session: URLSession(configuration: config), retryPolicy: { .none }, cacheTTL: { ttl }, now: { clock.now })
+        var requests = 0
+        MockURLProtocol.requestHandler = { request in
+            requests += 1
+            return (HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!, "{}".data(using: .utf8)!)
+        }
+
+        _ = try await caching.fetchUsage(token: "token")
+        _ = try await caching.fetchUsage(token: "token", allowingCached: false)
+        XCTAssertEqual(requests, 2)
+
+        _ = try await caching.fetchUsage(token: "token")
+        XCTAssertEqual(requests, 2, "The fresh response is cached in turn")
+
+        ttl = 0
+        _ = try await caching.fetchUsage(token: "token")
+        XCTAssertEqual(requests, 3, "The TTL is read on each request")
+    }
+
+    func testFetchUsageWithZeroTTLAlwaysRequests() async throws {
+        let caching = makeCachingService(cacheTTL: 0, clock: MutableClock())
+        var requests = 0
+        MockURLProtocol.requestHandler = { request in
+            requests += 1
+            return (HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!, "{}".data(using: .utf8)!)
+        }
+
+        _ = try await caching.fetchUsage(token: "token")
+        _ = try await caching.fetchUsage(token: "token")
+
+        XCTAssertEqual(requests, 2)
+    }
+
+    func testFetchUsageWithInvalidBaseURLFailsWithoutRequest() async {
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [MockURLProtocol.self]
+        let misconfigured = APIService(
+            session: URLSession(configuration: config),
+            baseURL: { throw APIError.invalidBaseURL("gateway.corp") },
+            retryPolicy: { RetryPolicy(maxRetries: 3, baseDelay: 0) }
+        )
+        var requests = 0
+        MockURLProtocol.requestHandler = { request in
+            requests += 1
+            return (HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!, "{}".data(using: .utf8)!)
+        }
+
+        do {
+            _ = try await misconfigured.fetchUsage(token: "token")
+            XCTFail("Expected invalid base URL error")
+        } catch {
+            XCTAssertEqual(error as? APIError, .invalidBaseURL("gateway.corp"))
+        }
+        XCTAssertEqual(requests, 0)
+    }
+
+    func testFetchUsageTimeoutSurfacesAsNetworkError() async {
+        MockURLProtocol.requestHandler = { _ in throw URLError(.timedOut) }
+
+        do {
+            _ = try await service.fetchUsage(token: "token")
+            XCTFail("Expected network error")
+        } catch APIError.networkError(let message) {
+            XCTAssertTrue(message.hasPrefix(APIError.timedOutPrefix), message)
+            XCTAssertEqual(UsageErrorKind(APIError.networkError(message)), .network)
+        } catch {
+            XCTFail("Unexpected error: \(error)")
+        }
+    }
+
+    func testCodexFetchUsageConnectionErrorSurfacesAsNetworkError() async {
+        MockURLProtocol.requestHandler = { _ in throw URLError(.timedOut) }
+
+        do {
+            _ = try await codexService.fetchUsage(credentials: CodexAuthCredentials(accessToken: "t", accountID: "a"))
+            XCTFail("Expected network error")
+        } catch APIError.networkError(let message) {
+            XCTAssertTrue(message.hasPrefix(APIError.timedOutPrefix), message)
+        } catch {
+            XCTFail("Unexpected error: \(error)")
+        }
+    }
+
+    func testWidgetSessionConfigurationSetsTimeoutsAndUserAgent() {
+        let config = URLSessionConfiguration.widget()
+
+        XCTAssertEqual(config.timeoutIntervalForRequest, URLSessionConfiguration.connectTimeout)
+        XCTAssertEqual(config.timeoutIntervalForResource, 15)
+        XCTAssertEqual(URLSessionConfiguration.widget(requestTimeout: 5).timeoutIntervalForRequest, 5)
+        let userAgent = config.httpAdditionalHeaders?["User-Agent"] as? String
+        XCTAssertTrue(userAgent?.hasPrefix("claude-usage-widget/") ?? false)
+        XCTAssertNil(config.connectionProxyDictionary)
+    }
+
+    func testWidgetSessionConfigurationAppliesProxy() throws {
+        let proxy = try ProxyConfiguration(urlString: "http://proxy.corp:3128")
+        let config = URLSessionConfiguration.widget(proxy: proxy)
+
+        XCTAssertEqual(config.connectionProxyDictionary?[kCFNetworkProxiesHTTPSProxy as String] as? String, "proxy.corp")
+        XCTAssertEqual(config.connectionProxyDictionary?[kCFNetworkProxiesHTTPSPort as String] as? Int, 3128)
+    }
+
+    func testSharedSessionRebuildsOnlyWhenProxyChanges() throws {
+        let shared = SharedSession(proxy: nil)
+        let original = try shared.current()
+
+        shared.reconfigure(proxy: nil)
+        XCTAssertTrue(try shared.current() === original)
+
+        let proxy = try ProxyConfiguration(urlString: "http://proxy.corp:3128")
+        shared.reconfigure(proxy: proxy)
+        XCTAssertFalse(try shared.current() === original)
+        XCTAssertEqual(shared.proxy, proxy)
+    }
+
+    func testSharedSessionRebuildsWhenRequestTimeoutChanges() throws {
+        let shared = SharedSession(proxy: nil)
+        let original = try shared.current()
+
+        shared.reconfigure(proxy: nil, requestTimeout: 45)
+
+        let rebuilt = try shared.current()
+        XCTAssertFalse(rebuilt === original)
+        XCTAssertEqual(rebuilt.configuration.timeoutIntervalForResource, 45)
+    }
+
+    func testSharedSessionRefusesRequestsWhileProxyIsInvalid() throws {
+        let shared = SharedSession(proxy: nil)
+        shared.reject(.invalidURL("proxy:1080"))
+
+        XCTAssertThrowsError(try shared.current()) { error in
+            guard case APIError.invalidProxy = error else {
+                return XCTFail("Expected invalidProxy, got \(error)")
+            }
+        }
+
+        shared.reconfigure(proxy: nil)
+        XCTAssertNoThrow(try shared.current())
+    }
+
+    func testWidgetSessionTimesOutHungRequest() async {
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [HangingURLProtocol.self]
+        config.timeoutIntervalForRequest = 0.2
+        config.timeoutIntervalForResource = 0.2
+        let hanging = APIService(session: URLSession(configuration: config), retryPolicy: { .none })
+
+        do {
+            _ = try await hanging.fetchUsage(token: "token")
+            XCTFail("Expected timeout")
+        } catch let error as APIError {
+            XCTAssertEqual(error, .timedOut(after: 0.2))
+            XCTAssertEqual(UsageErrorKind(error), .network)
+        } catch {
+            XCTFail("Unexpected error: \(error)")
+        }
+    }
+
+    func testTimeoutMessageNamesConnectLimitWhenItFiresFirst() async {
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [HangingURLProtocol.self]
+        config.timeoutIntervalForRequest = 0.2
+        config.timeoutIntervalForResource = 5
+        let hanging = APIService(session: URLSession(configuration: config), retryPolicy: { .none })
+
+        do {
+            _ = try await hanging.fetchUsage(token: "token")
+            XCTFail("Expected timeout")
+        } catch {
+            XCTAssertEqual(error as? APIError, .networkError("Request timed out after 0.2s"))
+        }
+    }
+
+    func testRetryDelayDoublesEachAttempt() {
+        let policy = RetryPolicy(maxRetries: 3, baseDelay: 0.5)
+        XCTAssertEqual(policy.delay(forRetry: 0, jitterFraction: 0), 0.5)
+        XCTAssertEqual(policy.delay(forRetry: 1, jitterFraction: 0), 1.0)
+        XCTAssertEqual(policy.delay(forRetry: 2, jitterFraction: 0), 2.0)
+        XCTAssertEqual(policy.delay(forRetry: 1, jitterFraction: 0.2), 1.2, accuracy: 0.0001)
+    }
+
+    // MARK: - Token refresh
+
+    private func makeTokenRefreshService() -> TokenRefreshService {
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [MockURLProtocol.self]
+        return TokenRefreshService(session: URLSession(configuration: config))
+    }
+
+    func testTokenRefreshPostsRefreshGrant() async throws {
+        let refresher = makeTokenRefreshService()
+        var body: [String: String]?
+        MockURLProtocol.requestHandler = { request in
+            XCTAssertEqual(request.httpMethod, "POST")
+            XCTAssertEqual(request.value(forHTTPHeaderField: "Content-Type"), "application/json")
+            body = Self.jsonBody(of: request)
+            let response = HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!
+            return (response, #"{"access_token": "new-access", "refresh_token": "new-refresh", "expires_in": 3600}"#.data(using: .utf8)!)
+        }
+
+        let before = Date()
+        let credentials = try await refresher.refresh(refreshToken: "old-refresh")
+
+        XCTAssertEqual(body?["grant_type"], "refresh_token")
+        XCTAssertEqual(body?["refresh_token"], "old-refresh")
+        XCTAssertEqual(body?["client_id"], TokenRefreshService.clientID)
+        XCTAssertEqual(credentials.accessToken, "new-access")
+        XCTAssertEqual(credentials.refreshToken, "new-refresh")
+        XCTAssertGreaterThanOrEqual(credentials.expiresAt ?? .distantPast, before.addingTimeInterval(3600))
+    }
+
+    func testTokenRefreshKeepsRefreshTokenWhenNotRotated() async throws {
+        let refresher = makeTokenRefreshService()
+        MockURLProtocol.requestHandler = { request in
+            let response = HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!
+            return (response, #"{"access_token": "new-access"}"#.data(using: .utf8)!)
+        }
+
+        let credentials = try await refresher.refresh(refreshToken: "old-refresh")
+
+        XCTAssertEqual(credentials.refreshToken, "old-refresh")
+        XCTAssertNil(credentials.expiresAt)
+    }
+
+    func testTokenRefreshInvalidGrantThrowsUnauthorized() async {
+        let refresher = makeTokenRefreshService()
+        MockURLProtocol.requestHandler = { request in
+            let response = HTTPURLResponse(url: request.url!, statusCode: 400, httpVersion: nil, headerFields: nil)!
+            return (response, #"{"error": "invalid_grant"}"#.data(using: .utf8)!)
+        }
+
+        do {
+            _ = try await refresher.refresh(refreshToken: "revoked")
+            XCTFail("Expected unauthorized error")
+        } catch APIError.unauthorized {
+            // expected
+        } catch {
+            XCTFail("Unexpected error: \(error)")
+        }
+    }
+
+    private static func jsonBody(of request: URLRequest) -> [String: String]? {
+        var data = request.httpBody ?? Data()
+        if data.isEmpty, let stream = request.httpBodyStream {
+            stream.open()
+            defer { stream.close() }
+            var buffer = [UInt8](repeating: 0, count: 1024)
+            while stream.hasBytesAvailable {
+                let read = stream.read(&buffer, maxLength: buffer.count)
+                guard read > 0 else { break }
+                data.append(buffer, count: read)
+            }
+        }
+        return (try? JSONSerialization.jsonObject(with: data)) as? [String: String]
+    }
+
     func testCodexFetchUsageSuccess() async throws {
         let responseJSON = """
         {
@@ -172,3 +783,15 @@ final class APIServiceTests: XCTestCase {
         XCTAssertEqual(credentials.accountID, "account-123")
     }
 }
+
+final class SleepRecorder: @unchecked Sendable {
+    private(set) var values: [TimeInterval] = []
+
+    func record(_ delay: TimeInterval) {
+        values.append(delay)
+    }
+}
+
+final class MutableClock: @unchecked Sendable {
+    var now = Date(timeIntervalSince1970: 1_800_000_000)
+}
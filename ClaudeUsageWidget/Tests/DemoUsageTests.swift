@@ -0,0 +1,28 @@
+import XCTest
+@testable import ClaudeUsageWidget
+
+final class DemoUsageTests: XCTestCase {
+    func testValuesFollowTheClockAndStayInRange() throws {
+        // A multiple of five hours, so a demo 5-hour window starts here.
+        let start = Date(timeIntervalSince1970: 1_800_000_000)
+        let early = DemoUsage.snapshot(now: start.addingTimeInterval(600))
+        let late = DemoUsage.snapshot(now: start.addingTimeInterval(4 * 3600))
+
+        let earlyFiveHour = try XCTUnwrap(early.fiveHour)
+        let lateFiveHour = try XCTUnwrap(late.fiveHour)
+        XCTAssertLessThan(earlyFiveHour.percent, lateFiveHour.percent)
+        XCTAssertEqual(earlyFiveHour.resetsAt, lateFiveHour.resetsAt, "Same window, same reset")
+        XCTAssertEqual(lateFiveHour.resetsAt, start.addingTimeInterval(5 * 3600))
+
+        for metric in late.claudeMetrics.values {
+            XCTAssertTrue((0...100).contains(metric.percent))
+        }
+    }
+
+    func testSnapshotIsMarkedAsDemo() {
+        let snapshot = DemoUsage.snapshot(now: Date(timeIntervalSince1970: 1_800_000_000))
+        XCTAssertEqual(snapshot.isDemo, true)
+        XCTAssertNil(snapshot.error)
+        XCTAssertEqual(snapshot.plan?.name, "Demo")
+    }
+}
@@ -0,0 +1,119 @@
+import XCTest
+@testable import ClaudeUsageWidget
+
+final class FileWatcherTests: XCTestCase {
+    private var tmpDir: URL!
+
+    override func setUpWithError() throws {
+        tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        try FileManager.default.createDirectory(at: tmpDir, withIntermediateDirectories: true)
+    }
+
+    override func tearDown() {
+        try? FileManager.default.removeItem(at: tmpDir)
+    }
+
+    func testRapidWritesAreCoalescedIntoOneCallback() throws {
+        let fileURL = tmpDir.appendingPathComponent("stats-cache.json")
+        try "{}".write(to: fileURL, atomically: true, encoding: .utf8)
+        let scheduler = ManualScheduler()
+        var changes = 0
+        let watcher = FileWatcher(fileURL: fileURL, debounce: 0.3, schedule: scheduler.schedule) { changes += 1 }
+        XCTAssertTrue(watcher.start())
+
+        for index in 1...5 {
+            try write("{\"n\": \(index)}", to: fileURL, modified: index)
+            watcher.directoryDidChange()
+        }
+
+        XCTAssertEqual(scheduler.delays.last, 0.3)
+        scheduler.fireAll()
+        XCTAssertEqual(changes, 1)
+        watcher.stop()
+    }
+
+    func testWritesToOtherFilesAreIgnored() throws {
+        let fileURL = tmpDir.appendingPathComponent("stats-cache.json")
+        try "{}".write(to: fileURL, atomically: true, encoding: .utf8)
+        let scheduler = ManualScheduler()
+        var changes = 0
+        let watcher = FileWatcher(fileURL: fileURL, debounce: 0.1, schedule: scheduler.schedule) { changes += 1 }
+        watcher.start()
+
+        try "noise".write(to: tmpDir.appendingPathComponent("history.jsonl"), atomically: true, encoding: .utf8)
+        watcher.directoryDidChange()
+
+        scheduler.fireAll()
+        XCTAssertEqual(changes, 0)
+        watcher.stop()
+    }
+
+    func testStartFailsForMissingDirectory() {
+        let watcher = FileWatcher(fileURL: tmpDir.appendingPathComponent("missing/stats-cache.json")) {}
+
+        XCTAssertFalse(watcher.start())
+        XCTAssertFalse(watcher.isWatching)
+    }
+
+    func testStartSucceedsOnceDirectoryIsCreated() throws {
+        let directory = tmpDir.appendingPathComponent("later")
+        let fileURL = directory.appendingPathComponent("stats-cache.json")
+        let watcher = FileWatcher(fileURL: fileURL, debounce: 0.1) {}
+        XCTAssertFalse(watcher.start())
+
+        try FileManager.default.createDirectory(at: directory, withIntermediateDirectories: true)
+
+        XCTAssertTrue(watcher.start())
+        XCTAssertTrue(watcher.isWatching)
+        watcher.stop()
+        XCTAssertFalse(watcher.isWatching)
+    }
+
+    func testStopPreventsPendingCallback() throws {
+        let fileURL = tmpDir.appendingPathComponent("stats-cache.json")
+        try "{}".write(to: fileURL, atomically: true, encoding: .utf8)
+        let scheduler = ManualScheduler()
+        var changes = 0
+        let watcher = FileWatcher(fileURL: fileURL, debounce: 0.3, schedule: scheduler.schedule) { changes += 1 }
+        watcher.start()
+
+        try write("{\"n\": 1}", to: fileURL, modified: 1)
+        watcher.directoryDidChange()
+        watcher.stop()
+
+        scheduler.fireAll()
+        XCTAssertEqual(changes, 0)
+    }
+
+    /// Writes `contents` with an explicit modification date, so each write
+    /// registers regardless of the file system's timestamp resolution.
+    private func write(_ contents: String, to fileURL: URL, modified seconds: Int) throws {
+        try contents.write(to: fileURL, atomically: true, encoding: .utf8)
+        try FileManager.default.setAttributes(
+            [.modificationDate: Date(timeIntervalSince1970: 1_700_000_000 + TimeInterval(seconds))],
+            ofItemAtPath: fileURL.path
+        )
+    }
+}
+
+/// Holds scheduled callbacks until the test fires them.
+private final class ManualScheduler {
+    private let lock = NSLock()
+    private var pending: [() -> Void] = []
+    private(set) var delays: [TimeInterval] = []
+
+    func schedule(_ delay: TimeInterval, _ work: @escaping () -> Void) {
+        lock.lock()
+        defer { lock.unlock() }
+        delays.append(delay)
+        pending.append(work)
+    }
+
+    func fireAll() {
+        lock.lock()
+        let work = pending
+        pending = []
+        lock.unlock()
+        work.forEach { $0() }
+    }
+}
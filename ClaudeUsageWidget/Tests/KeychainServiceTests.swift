@@ -1,3 +1,4 @@
+import Security
 import XCTest
 @testable import ClaudeUsageWidget
 
@@ -36,6 +37,88 @@ final class KeychainParsingTests: XCTestCase {
         }
     }
 
+    func testExtractCredentialsParsesExpiryInMilliseconds() throws {
+        let json = """
+        {"claudeAiOauth": {"accessToken": "token", "expiresAt": 1774400000000}}
+        """.data(using: .utf8)!
+
+        let credentials = try KeychainService.extractCredentials(from: json)
+        XCTAssertEqual(credentials.accessToken, "token")
+        XCTAssertEqual(credentials.expiresAt, Date(timeIntervalSince1970: 1_774_400_000))
+    }
+
+    func testExtractCredentialsWithoutExpiry() throws {
+        let json = """
+        {"claudeAiOauth": {"accessToken": "token"}}
+        """.data(using: .utf8)!
+
+        let credentials = try KeychainService.extractCredentials(from: json)
+        XCTAssertNil(credentials.expiresAt)
+    }
+
+    func testCredentialsExpiryHonorsGraceWindow() {
+        let now = Date(timeIntervalSince1970: 1_000_000)
+        let credentials = OAuthCredentials(accessToken: "token", expiresAt: now.addingTimeInterval(30))
+
+        XCTAssertFalse(credentials.isExpired(now: now))
+        XCTAssertTrue(credentials.isExpired(now: now, grace: 60))
+        XCTAssertFalse(OAuthCredentials(accessToken: "token").isExpired(now: now, grace: 60))
+    }
+
+    func testParseCredentialsFromJSONString() throws {
+        let credentials = try OAuthCredentials(credentialsJSON: #"{"claudeAiOauth": {"accessToken": "string-token"}}"#)
+        XCTAssertEqual(credentials.accessToken, "string-token")
+    }
+
+    func testParseCredentialsRejectsNonStringAccessToken() {
+        XCTAssertThrowsError(try OAuthCredentials(credentialsJSON: #"{"claudeAiOauth": {"accessToken": 12345}}"#)) { error in
+            XCTAssertEqual(error as? KeychainError, .invalidData("No OAuth token found in credentials"))
+        }
+    }
+
+    func testParseCredentialsRejectsEmptyAccessToken() {
+        XCTAssertThrowsError(try OAuthCredentials(credentialsJSON: #"{"claudeAiOauth": {"accessToken": ""}}"#)) { error in
+            XCTAssertEqual(error as? KeychainError, .invalidData("No OAuth token found in credentials"))
+        }
+    }
+
+    func testParseCredentialsRejectsNonObjectOAuthBlob() {
+        XCTAssertThrowsError(try OAuthCredentials(credentialsJSON: #"{"claudeAiOauth": ["accessToken"]}"#)) { error in
+            XCTAssertEqual(error as? KeychainError, .invalidData("No OAuth token found in credentials"))
+        }
+    }
+
+    func testParseCredentialsIgnoresNonNumericExpiry() throws {
+        let credentials = try OAuthCredentials(credentialsJSON: #"{"claudeAiOauth": {"accessToken": "t", "expiresAt": "soon"}}"#)
+        XCTAssertNil(credentials.expiresAt)
+    }
+
+    func testParseCredentialsReadsRefreshToken() throws {
+        let credentials = try OAuthCredentials(credentialsJSON: #"{"claudeAiOauth": {"accessToken": "t", "refreshToken": "rt-123"}}"#)
+        XCTAssertEqual(credentials.refreshToken, "rt-123")
+
+        let withoutRefresh = try OAuthCredentials(credentialsJSON: #"{"claudeAiOauth": {"accessToken": "t", "refreshToken": ""}}"#)
+        XCTAssertNil(withoutRefresh.refreshToken)
+    }
+
+    func testCredentialsDescriptionRedactsTokens() {
+        let credentials = OAuthCredentials(accessToken: "sk-ant-secret", refreshToken: "rt-secret")
+        let described = "\(credentials)"
+
+        XCTAssertFalse(described.contains("sk-ant-secret"))
+        XCTAssertFalse(described.contains("rt-secret"))
+        XCTAssertTrue(described.contains("<redacted>"))
+    }
+
+    func testClassifyKeychainStatus() {
+        XCTAssertNil(KeychainService.classify(status: errSecSuccess))
+        XCTAssertEqual(KeychainService.classify(status: errSecItemNotFound), .notFound)
+        XCTAssertEqual(KeychainService.classify(status: errSecUserCanceled), .accessDenied)
+        XCTAssertEqual(KeychainService.classify(status: errSecAuthFailed), .accessDenied)
+        XCTAssertEqual(KeychainService.classify(status: errSecInteractionNotAllowed), .needsPermission)
+        XCTAssertEqual(KeychainService.classify(status: errSecParam), .invalidData("Keychain error: \(errSecParam)"))
+    }
+
     func testExtractTokenInvalidJSON() {
         let json = "not json at all".data(using: .utf8)!
 
@@ -48,3 +131,246 @@ final class KeychainParsingTests: XCTestCase {
         }
     }
 }
+
+final class CredentialsFileLookupTests: XCTestCase {
+    private var tmpDir: URL!
+
+    override func setUpWithError() throws {
+        tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        try FileManager.default.createDirectory(at: tmpDir, withIntermediateDirectories: true)
+    }
+
+    override func tearDown() {
+        try? FileManager.default.removeItem(at: tmpDir)
+    }
+
+    func testCredentialsPathDefaultsToHomeClaudeDirectory() {
+        let url = KeychainService.credentialsFileURL(environment: [:], homeDirectory: tmpDir)
+        XCTAssertEqual(url, tmpDir.appendingPathComponent(".claude/.credentials.json"))
+    }
+
+    func testCredentialsPathRespectsClaudeConfigDir() {
+        let configDir = tmpDir.appendingPathComponent("custom-config")
+        let url = KeychainService.credentialsFileURL(
+            environment: ["CLAUDE_CONFIG_DIR": configDir.path],
+            homeDirectory: tmpDir
+        )
+        XCTAssertEqual(url.path, configDir.appendingPathComponent(".credentials.json").path)
+    }
+
+    func testCredentialsPathIgnoresEmptyClaudeConfigDir() {
+        let url = KeychainService.credentialsFileURL(environment: ["CLAUDE_CONFIG_DIR": ""], homeDirectory: tmpDir)
+        XCTAssertEqual(url, tmpDir.appendingPathComponent(".claude/.credentials.json"))
+    }
+
+    func testReadTokenFromCredentialsFile() throws {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        try #"{"claudeAiOauth": {"accessToken": "file-token"}}"#.write(to: fileURL, atomically: true, encoding: .utf8)
+
+        let service = KeychainService(credentialsFileURL: fileURL)
+
+        XCTAssertEqual(try service.readCredentials().accessToken, "file-token")
+    }
+
+    func testSelectedAccountDoesNotFallBackToCredentialsFile() throws {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        try #"{"claudeAiOauth": {"accessToken": "other-account-token"}}"#.write(to: fileURL, atomically: true, encoding: .utf8)
+        let service = KeychainService(
+            credentialsFileURL: fileURL,
+            searchesKeychain: true,
+            selectedAccount: "missing-\(UUID().uuidString)"
+        )
+
+        XCTAssertThrowsError(try service.readCredentials()) { error in
+            XCTAssertEqual(error as? KeychainError, .noCredentials(searched: service.searchedLocations))
+        }
+        XCTAssertFalse(service.searchedLocations.contains(fileURL.path))
+    }
+
+    func testWriteCredentialsUpdatesCredentialsFileAndKeepsOtherFields() throws {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        let original = #"{"claudeAiOauth": {"accessToken": "old", "refreshToken": "old-refresh", "expiresAt": 1000, "scopes": ["user:inference"]}, "other": 1}"#
+        try original.write(to: fileURL, atomically: true, encoding: .utf8)
+        let service = KeychainService(credentialsFileURL: fileURL)
+
+        let renewed = OAuthCredentials(
+            accessToken: "new",
+            expiresAt: Date(timeIntervalSince1970: 1_900_000_000),
+            refreshToken: "new-refresh",
+            source: .credentialsFile
+        )
+        try service.writeCredentials(renewed)
+
+        let read = try service.readCredentials()
+        XCTAssertEqual(read.accessToken, "new")
+        XCTAssertEqual(read.refreshToken, "new-refresh")
+        XCTAssertEqual(read.expiresAt, Date(timeIntervalSince1970: 1_900_000_000))
+        let json = try JSONSerialization.jsonObject(with: Data(contentsOf: fileURL)) as? [String: Any]
+        XCTAssertEqual(json?["other"] as? Int, 1)
+        XCTAssertEqual((json?["claudeAiOauth"] as? [String: Any])?["scopes"] as? [String], ["user:inference"])
+    }
+
+    func testWriteCredentialsKeepsCredentialsFileOwnerOnly() throws {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        try #"{"claudeAiOauth": {"accessToken": "old"}}"#.write(to: fileURL, atomically: true, encoding: .utf8)
+        try FileManager.default.setAttributes([.posixPermissions: 0o600], ofItemAtPath: fileURL.path)
+        let service = KeychainService(credentialsFileURL: fileURL)
+
+        try service.writeCredentials(OAuthCredentials(accessToken: "new", source: .credentialsFile))
+
+        let mode = try FileManager.default.attributesOfItem(atPath: fileURL.path)[.posixPermissions] as? NSNumber
+        XCTAssertEqual(mode?.intValue, 0o600)
+        XCTAssertEqual(try FileManager.default.contentsOfDirectory(atPath: tmpDir.path), [".credentials.json"], "No staged file is left behind")
+    }
+
+    func testWriteCredentialsLeavesEnvironmentTokensAlone() throws {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        try #"{"claudeAiOauth": {"accessToken": "file-token"}}"#.write(to: fileURL, atomically: true, encoding: .utf8)
+        let service = KeychainService(credentialsFileURL: fileURL)
+
+        try service.writeCredentials(OAuthCredentials(accessToken: "new", source: .environment))
+        XCTAssertEqual(try service.readCredentials().accessToken, "file-token")
+    }
+
+    func testTokenSourceIsReadOnEveryUse() throws {
+        var source = TokenSource.keychain
+        let service = KeychainService(
+            environment: [KeychainService.tokenEnvironmentVariable: "env-token"],
+            homeDirectory: tmpDir,
+            tokenSource: { source }
+        )
+        XCTAssertFalse(service.searchedLocations.contains("$\(KeychainService.tokenEnvironmentVariable)"))
+
+        source = .environment
+        XCTAssertEqual(service.searchedLocations.first, "$\(KeychainService.tokenEnvironmentVariable)")
+        XCTAssertEqual(try service.readCredentials().accessToken, "env-token")
+    }
+
+    func testReadTokenMissingFileThrowsNoCredentials() {
+        let service = KeychainService(credentialsFileURL: tmpDir.appendingPathComponent("missing.json"))
+
+        XCTAssertThrowsError(try service.readCredentials()) { error in
+            XCTAssertEqual(error as? KeychainError, .noCredentials(searched: service.searchedLocations))
+        }
+    }
+
+    func testSearchedLocationsListsCredentialsFile() {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        let service = KeychainService(credentialsFileURL: fileURL)
+
+        XCTAssertEqual(service.searchedLocations, [fileURL.path, "$CLAUDE_OAUTH_TOKEN"])
+    }
+
+    func testCredentialsFileSourceIsReported() throws {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        try #"{"claudeAiOauth": {"accessToken": "file-token"}}"#.write(to: fileURL, atomically: true, encoding: .utf8)
+
+        let service = KeychainService(credentialsFileURL: fileURL)
+
+        XCTAssertEqual(try service.readCredentials().source, .credentialsFile)
+    }
+
+    func testEnvironmentTokenSourceTakesPrecedence() throws {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        try #"{"claudeAiOauth": {"accessToken": "file-token"}}"#.write(to: fileURL, atomically: true, encoding: .utf8)
+
+        let service = KeychainService(
+            credentialsFileURL: fileURL,
+            tokenSource: .environment,
+            environment: [KeychainService.tokenEnvironmentVariable: " env-token\n"]
+        )
+
+        let credentials = try service.readCredentials()
+        XCTAssertEqual(credentials.accessToken, "env-token")
+        XCTAssertEqual(credentials.source, .environment)
+    }
+
+    func testMissingEnvironmentTokenFallsBackToCredentialsFile() throws {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        try #"{"claudeAiOauth": {"accessToken": "file-token"}}"#.write(to: fileURL, atomically: true, encoding: .utf8)
+
+        let service = KeychainService(credentialsFileURL: fileURL, tokenSource: .environment)
+
+        let credentials = try service.readCredentials()
+        XCTAssertEqual(credentials.accessToken, "file-token")
+        XCTAssertEqual(credentials.source, .credentialsFile)
+    }
+
+    func testTokenFileWithBareToken() throws {
+        let tokenURL = tmpDir.appendingPathComponent("token.txt")
+        try "bare-token\n".write(to: tokenURL, atomically: true, encoding: .utf8)
+
+        let service = KeychainService(
+            credentialsFileURL: tmpDir.appendingPathComponent("missing.json"),
+            tokenSource: .file,
+            tokenFileURL: tokenURL
+        )
+
+        let credentials = try service.readCredentials()
+        XCTAssertEqual(credentials.accessToken, "bare-token")
+        XCTAssertEqual(credentials.source, .tokenFile)
+    }
+
+    func testTokenFileWithCredentialsJSON() throws {
+        let tokenURL = tmpDir.appendingPathComponent("creds.json")
+        try #"{"claudeAiOauth": {"accessToken": "json-token", "refreshToken": "rt"}}"#.write(to: tokenURL, atomically: true, encoding: .utf8)
+
+        let service = KeychainService(
+            credentialsFileURL: tmpDir.appendingPathComponent("missing.json"),
+            tokenSource: .file,
+            tokenFileURL: tokenURL
+        )
+
+        let credentials = try service.readCredentials()
+        XCTAssertEqual(credentials.accessToken, "json-token")
+        XCTAssertEqual(credentials.refreshToken, "rt")
+        XCTAssertEqual(credentials.source, .tokenFile)
+    }
+
+    func testSearchedLocationsIncludesPreferredSource() {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        let service = KeychainService(credentialsFileURL: fileURL, tokenSource: .environment)
+
+        XCTAssertEqual(service.searchedLocations, ["$CLAUDE_WIDGET_TOKEN", fileURL.path, "$CLAUDE_OAUTH_TOKEN"])
+    }
+
+    func testListAccountsIsEmptyWhenKeychainIsSkipped() {
+        let service = KeychainService(credentialsFileURL: tmpDir.appendingPathComponent(".credentials.json"))
+
+        XCTAssertEqual(service.listAccounts(), [])
+    }
+
+    func testFallsBackToOAuthTokenEnvironmentVariable() throws {
+        let service = KeychainService(
+            credentialsFileURL: tmpDir.appendingPathComponent("missing.json"),
+            environment: [KeychainService.fallbackEnvironmentVariable: "ci-token"]
+        )
+
+        let credentials = try service.readCredentials()
+        XCTAssertEqual(credentials.accessToken, "ci-token")
+        XCTAssertEqual(credentials.source, .environment)
+    }
+
+    func testCredentialsFileWinsOverOAuthTokenEnvironmentVariable() throws {
+        let fileURL = tmpDir.appendingPathComponent(".credentials.json")
+        try #"{"claudeAiOauth": {"accessToken": "file-token"}}"#.write(to: fileURL, atomically: true, encoding: .utf8)
+
+        let service = KeychainService(
+            credentialsFileURL: fileURL,
+            environment: [KeychainService.fallbackEnvironmentVariable: "ci-token"]
+        )
+
+        XCTAssertEqual(try service.readCredentials().accessToken, "file-token")
+    }
+
+    func testBlankOAuthTokenEnvironmentVariableIsIgnored() {
+        let service = KeychainService(
+            credentialsFileURL: tmpDir.appendingPathComponent("missing.json"),
+            environment: [KeychainService.fallbackEnvironmentVariable: "  "]
+        )
+
+        XCTAssertThrowsError(try service.readCredentials()) { error in
+            XCTAssertEqual(error as? KeychainError, .noCredentials(searched: service.searchedLocations))
+        }
+    }
+}
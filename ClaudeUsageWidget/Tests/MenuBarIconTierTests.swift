@@ -38,6 +38,13 @@ final class MenuBarIconTierFromPercentTests: XCTestCase {
     func testFromNegativePercent() {
         XCTAssertEqual(MenuBarIconTier.from(percent: -5), .low)
     }
+
+    func testCustomThresholds() {
+        let thresholds = IconThresholds(moderate: 50, high: 80, critical: 95)
+        XCTAssertEqual(MenuBarIconTier.from(percent: 75, thresholds: thresholds), .moderate)
+        XCTAssertEqual(MenuBarIconTier.from(percent: 94, thresholds: thresholds), .high)
+        XCTAssertEqual(MenuBarIconTier.from(percent: 95, thresholds: thresholds), .critical)
+    }
 }
 
 final class MenuBarIconTierPropertiesTests: XCTestCase {
@@ -63,7 +70,7 @@ final class MenuBarIconTierPropertiesTests: XCTestCase {
     }
 
     func testAllTiersHaveNonEmptyAccessibilityLabel() {
-        let tiers: [MenuBarIconTier] = [.idle, .low, .moderate, .high, .critical]
+        let tiers: [MenuBarIconTier] = [.idle, .low, .moderate, .high, .critical, .error, .paused]
         for tier in tiers {
             XCTAssertFalse(tier.accessibilityLabel.isEmpty, "\(tier) should have a non-empty accessibility label")
         }
@@ -77,8 +84,14 @@ final class MenuBarIconTierPropertiesTests: XCTestCase {
         XCTAssertEqual(MenuBarIconTier.critical.accessibilityLabel, "Claude Usage: Critical")
     }
 
+    func testStatusTiersAreTemplateImages() {
+        XCTAssertTrue(MenuBarIconTier.error.menuBarImage().isTemplate)
+        XCTAssertTrue(MenuBarIconTier.paused.menuBarImage().isTemplate)
+        XCTAssertFalse(MenuBarIconTier.critical.menuBarImage().isTemplate)
+    }
+
     func testMenuBarImageReturnsImageForAllTiers() {
-        let tiers: [MenuBarIconTier] = [.idle, .low, .moderate, .high, .critical]
+        let tiers: [MenuBarIconTier] = [.idle, .low, .moderate, .high, .critical, .error, .paused]
         for tier in tiers {
             let image = tier.menuBarImage()
             XCTAssertGreaterThan(image.size.width, 0, "menuBarImage() for \(tier) should have non-zero width")
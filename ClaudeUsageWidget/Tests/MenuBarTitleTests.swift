@@ -0,0 +1,42 @@
+import XCTest
+@testable import ClaudeUsageWidget
+
+final class MenuBarTitleMetricTests: XCTestCase {
+    private let snapshot = UsageSnapshot(
+        fiveHour: UsageMetric(percent: 41.6, resetsAt: Date()),
+        sevenDay: UsageMetric(percent: 18, resetsAt: Date()),
+        sevenDaySonnet: nil,
+        sevenDayOpus: UsageMetric(percent: 77, resetsAt: Date()),
+        tokenStats: .zero,
+        lastUpdated: Date(),
+        lastSuccessfulUpdate: Date(),
+        error: nil
+    )
+
+    func testTitleForEachMetric() {
+        XCTAssertEqual(MenuBarTitleMetric.fiveHour.title(for: snapshot), "5h: 42%")
+        XCTAssertEqual(MenuBarTitleMetric.sevenDay.title(for: snapshot), "7d: 18%")
+        XCTAssertEqual(MenuBarTitleMetric.worst.title(for: snapshot), "Opus: 77%")
+        XCTAssertNil(MenuBarTitleMetric.off.title(for: snapshot))
+    }
+
+    func testNoTitleWithoutData() {
+        XCTAssertNil(MenuBarTitleMetric.fiveHour.title(for: nil))
+        let empty = UsageSnapshot(
+            fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil,
+            tokenStats: .zero, lastUpdated: Date(), lastSuccessfulUpdate: nil, error: "offline"
+        )
+        XCTAssertNil(MenuBarTitleMetric.worst.title(for: empty))
+    }
+
+    func testTooltipListsWindowsAndError() {
+        XCTAssertEqual(MenuBarTooltip.text(for: snapshot), "5-hour: 42%\nWeekly: 18%\nOpus: 77%")
+        XCTAssertEqual(MenuBarTooltip.text(for: nil), "Claude Usage: no data yet")
+
+        let failed = UsageSnapshot(
+            fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil,
+            tokenStats: .zero, lastUpdated: Date(), lastSuccessfulUpdate: nil, error: "offline"
+        )
+        XCTAssertEqual(MenuBarTooltip.text(for: failed), "Claude Usage: no data yet\nLast refresh failed: offline")
+    }
+}
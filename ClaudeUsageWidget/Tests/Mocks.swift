@@ -3,26 +3,85 @@ import Foundation
 
 final class MockKeychainService: KeychainServiceProtocol {
     var tokenToReturn: String?
+    var expiresAtToReturn: Date?
+    var refreshTokenToReturn: String?
+    var sourceToReturn: CredentialSource = .keychain
+    var accountsToReturn: [String] = []
     var errorToThrow: Error?
     var readTokenCallCount = 0
     var lastReadOnMainThread: Bool?
+    var writtenCredentials: [OAuthCredentials] = []
+    var writeErrorToThrow: Error?
 
-    func readToken() throws -> String {
+    func readCredentials() throws -> OAuthCredentials {
         readTokenCallCount += 1
         lastReadOnMainThread = Thread.isMainThread
         if let error = errorToThrow { throw error }
         guard let token = tokenToReturn else { throw KeychainError.notFound }
-        return token
+        return OAuthCredentials(accessToken: token, expiresAt: expiresAtToReturn, refreshToken: refreshTokenToReturn, source: sourceToReturn)
+    }
+
+    func listAccounts() -> [String] {
+        accountsToReturn
+    }
+
+    func writeCredentials(_ credentials: OAuthCredentials) throws {
+        if let error = writeErrorToThrow { throw error }
+        writtenCredentials.append(credentials)
+    }
+}
+
+final class MockProxyPasswordStore: ProxyPasswordStoreProtocol {
+    var passwords: [String: String] = [:]
+
+    func password(for proxy: ProxyConfiguration) -> String? {
+        passwords[proxy.account]
+    }
+
+    func setPassword(_ password: String?, for proxy: ProxyConfiguration) throws {
+        passwords[proxy.account] = password
     }
 }
 
 final class MockAPIService: APIServiceProtocol {
     var responseToReturn: UsageApiResponse?
     var errorToThrow: Error?
+    /// Thrown one per call, in order, before falling back to `errorToThrow`.
+    var queuedErrors: [Error] = []
     var lastTokenUsed: String?
+    var tokensUsed: [String] = []
+    var lastAllowingCached: Bool?
+    var planToReturn: PlanInfo?
+    var profileErrorToThrow: Error?
+    var profileTokensUsed: [String] = []
+    /// Simulates a slow server; `onFetchUsage` runs before the delay.
+    var fetchDelay: TimeInterval = 0
+    var onFetchUsage: (() -> Void)?
+    var fetchFinishedAt: Date?
+    var reachabilityStatus = 200
+    var reachabilityErrorToThrow: Error?
+
+    func probeReachability() async throws -> Int {
+        if let error = reachabilityErrorToThrow { throw error }
+        return reachabilityStatus
+    }
 
-    func fetchUsage(token: String) async throws -> UsageApiResponse {
+    func fetchProfile(token: String) async throws -> PlanInfo? {
+        profileTokensUsed.append(token)
+        if let error = profileErrorToThrow { throw error }
+        return planToReturn
+    }
+
+    func fetchUsage(token: String, allowingCached: Bool) async throws -> UsageApiResponse {
         lastTokenUsed = token
+        lastAllowingCached = allowingCached
+        tokensUsed.append(token)
+        onFetchUsage?()
+        if fetchDelay > 0 {
+            try await Task.sleep(nanoseconds: UInt64(fetchDelay * 1_000_000_000))
+        }
+        defer { fetchFinishedAt = Date() }
+        if !queuedErrors.isEmpty { throw queuedErrors.removeFirst() }
         if let error = errorToThrow { throw error }
         guard let response = responseToReturn else {
             throw APIError.serverError(500)
@@ -31,6 +90,21 @@ final class MockAPIService: APIServiceProtocol {
     }
 }
 
+final class MockTokenRefreshService: TokenRefreshServiceProtocol {
+    var credentialsToReturn: OAuthCredentials?
+    var errorToThrow: Error?
+    var refreshCallCount = 0
+    var lastRefreshTokenUsed: String?
+
+    func refresh(refreshToken: String) async throws -> OAuthCredentials {
+        refreshCallCount += 1
+        lastRefreshTokenUsed = refreshToken
+        if let error = errorToThrow { throw error }
+        guard let credentials = credentialsToReturn else { throw APIError.unauthorized }
+        return credentials
+    }
+}
+
 final class MockCodexAuthService: CodexAuthServiceProtocol {
     var credentialsToReturn: CodexAuthCredentials?
     var errorToThrow: Error?
@@ -67,10 +141,36 @@ final class MockStatsService: StatsServiceProtocol {
     var statsToReturn = TokenStats(todayTokens: 0, weekTokens: 0, todayMessages: 0, weekMessages: 0)
     var lastReadOnMainThread: Bool?
 
+    var historyToReturn: [DailyTokens] = []
+    var requestedHistoryDays: [Int] = []
+    var projectStatsToReturn: [ProjectUsage] = []
+    var statsCacheToReturn: StatsCache?
+    /// When set, `readStats` waits up to a second for it to be signalled.
+    var readGate: DispatchSemaphore?
+    var readGateOpened: Bool?
+    var readFinishedAt: Date?
+
     func readStats() -> TokenStats {
         lastReadOnMainThread = Thread.isMainThread
+        if let readGate {
+            readGateOpened = readGate.wait(timeout: .now() + 1) == .success
+        }
+        readFinishedAt = Date()
         return statsToReturn
     }
+
+    func tokenHistory(days: Int) -> [DailyTokens] {
+        requestedHistoryDays.append(days)
+        return historyToReturn
+    }
+
+    func projectStats() -> [ProjectUsage] {
+        projectStatsToReturn
+    }
+
+    func statsCache() -> StatsCache? {
+        statsCacheToReturn
+    }
 }
 
 final class MockSharedContainerService: SharedContainerServiceProtocol {
@@ -98,6 +198,63 @@ final class MockSharedContainerService: SharedContainerServiceProtocol {
     }
 }
 
+final class MockUsageHistoryService: UsageHistoryServiceProtocol {
+    var recorded: [UsageSnapshot] = []
+    var pruneCutoffs: [Date] = []
+    var seriesToReturn: [UsageHistoryPoint] = []
+    var seriesRequests: [(start: Date, end: Date, resolution: TimeInterval)] = []
+    var rollUpDates: [Date] = []
+    var dailyPeaksToReturn: [DailyPeak] = []
+    var errorToThrow: Error?
+
+    func record(_ snapshot: UsageSnapshot) throws {
+        if let error = errorToThrow { throw error }
+        recorded.append(snapshot)
+    }
+
+    func snapshots(from start: Date, to end: Date) throws -> [UsageSnapshot] {
+        if let error = errorToThrow { throw error }
+        return recorded.filter { $0.lastUpdated >= start && $0.lastUpdated <= end }
+    }
+
+    func series(from start: Date, to end: Date, resolution: TimeInterval) throws -> [UsageHistoryPoint] {
+        if let error = errorToThrow { throw error }
+        seriesRequests.append((start, end, resolution))
+        return seriesToReturn
+    }
+
+    func forEachPoint(from start: Date, to end: Date, _ body: (UsageHistoryPoint) throws -> Void) throws {
+        if let error = errorToThrow { throw error }
+        for point in seriesToReturn where point.recordedAt >= start && point.recordedAt <= end {
+            try body(point)
+        }
+    }
+
+    func rollUpDailyPeaks(now: Date, calendar: Calendar) throws -> Int {
+        if let error = errorToThrow { throw error }
+        rollUpDates.append(now)
+        return 0
+    }
+
+    func dailyPeaks(days: Int) throws -> [DailyPeak] {
+        if let error = errorToThrow { throw error }
+        return Array(dailyPeaksToReturn.suffix(days))
+    }
+
+    func prune(olderThan cutoff: Date) throws {
+        pruneCutoffs.append(cutoff)
+        recorded.removeAll { $0.lastUpdated < cutoff }
+    }
+}
+
+final class MockUsageNotifier: UsageNotifierProtocol {
+    var posted: [(title: String, body: String, identifier: String)] = []
+
+    func post(title: String, body: String, identifier: String) {
+        posted.append((title, body, identifier))
+    }
+}
+
 final class MockWidgetReloader {
     var reloadCount = 0
     func reload() { reloadCount += 1 }
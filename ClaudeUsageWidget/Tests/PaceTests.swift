@@ -260,4 +260,47 @@ final class PaceComputeTests: XCTestCase {
         let pace = computePace(metric: metric, windowDuration: -1, now: resetsAt.addingTimeInterval(-100))
         XCTAssertNil(pace)
     }
+
+    // MARK: - Burn projection
+
+    private func samples(_ percents: [Double], start: Date) -> [UsageSample] {
+        percents.enumerated().map { UsageSample(date: start.addingTimeInterval(Double($0.offset) * 3600), percent: $0.element) }
+    }
+
+    func testBurnProjectionFitsRateAndProjectsExhaustion() {
+        let start = resetsAt.addingTimeInterval(-5 * 3600)
+        let now = start.addingTimeInterval(2 * 3600)
+        let metric = UsageMetric(percent: 30, resetsAt: now.addingTimeInterval(3 * 3600))
+
+        let projection = computeBurnProjection(samples: samples([10, 20, 30], start: start), metric: metric, now: now)
+
+        XCTAssertEqual(projection?.ratePerHour ?? 0, 10, accuracy: 0.0001)
+        XCTAssertEqual(projection?.exhaustionAt?.timeIntervalSince(now) ?? 0, 7 * 3600, accuracy: 1)
+        XCTAssertEqual(projection?.projectedAtReset ?? 0, 60, accuracy: 0.0001)
+        XCTAssertEqual(metric.withBurnProjection(projection).projectedPercentAtReset ?? 0, 60, accuracy: 0.0001)
+    }
+
+    func testBurnProjectionDiscardsSamplesBeforeAReset() {
+        let start = resetsAt.addingTimeInterval(-10 * 3600)
+        let all = samples([80, 90, 5, 10, 15], start: start)
+        let metric = UsageMetric(percent: 15, resetsAt: resetsAt)
+
+        let projection = computeBurnProjection(samples: all, metric: metric, now: all.last!.date)
+        XCTAssertEqual(projection?.ratePerHour ?? 0, 5, accuracy: 0.0001)
+
+        XCTAssertNil(computeBurnProjection(samples: Array(all.prefix(4)), metric: metric, now: all[3].date),
+                     "Only two samples since the reset")
+    }
+
+    func testBurnProjectionWithFallingUsageHasNoExhaustion() {
+        let start = resetsAt.addingTimeInterval(-5 * 3600)
+        let metric = UsageMetric(percent: 48, resetsAt: resetsAt)
+
+        let projection = computeBurnProjection(samples: samples([50, 49, 48], start: start), metric: metric, now: start.addingTimeInterval(2 * 3600))
+
+        XCTAssertNotNil(projection)
+        XCTAssertNil(projection?.exhaustionAt)
+        XCTAssertEqual(projection?.projectedAtReset, 48, "Projection never goes below the current value")
+        XCTAssertFalse(metric.withBurnProjection(projection).willExhaustBeforeReset)
+    }
 }
@@ -0,0 +1,101 @@
+import XCTest
+@testable import ClaudeUsageWidget
+
+final class PricingTableTests: XCTestCase {
+    func testEstimateCostSumsKnownModelsAndListsUnknownOnes() {
+        let pricing = PricingTable(ratesPerMillion: ["claude-sonnet-4-5": 6.0, "claude-opus-4-1": 30.0])
+
+        let estimate = pricing.estimateCost([
+            "claude-sonnet-4-5": 2_000_000,
+            "claude-opus-4-1": 500_000,
+            "claude-haiku-4-5": 1_000_000,
+            "unknown": 10
+        ])
+
+        XCTAssertEqual(estimate.cost, 27.0, accuracy: 0.0001)
+        XCTAssertEqual(estimate.unknownModels, ["claude-haiku-4-5", "unknown"])
+    }
+
+    func testRatePrefersExactThenLongestPrefixMatch() {
+        let pricing = PricingTable(ratesPerMillion: ["claude-sonnet": 5.0, "claude-sonnet-4-5": 6.0])
+
+        XCTAssertEqual(pricing.rate(for: "claude-sonnet"), 5.0)
+        XCTAssertEqual(pricing.rate(for: "claude-sonnet-4-5-20250929"), 6.0)
+        XCTAssertEqual(pricing.rate(for: "claude-sonnet-3-7"), 5.0)
+        XCTAssertNil(pricing.rate(for: "claude-opus-4-1"))
+    }
+
+    func testEstimateCostPricesUnknownModelsAtDefaultRate() {
+        let pricing = PricingTable(ratesPerMillion: ["claude-opus-4-1": 30.0], defaultRate: 6.0)
+
+        let estimate = pricing.estimateCost(["claude-opus-4-1": 1_000_000, "mystery-model": 500_000])
+
+        XCTAssertEqual(estimate.cost, 33.0, accuracy: 0.0001)
+        XCTAssertEqual(estimate.unknownModels, ["mystery-model"])
+    }
+
+    func testBuiltInPricesMixedModelDay() {
+        let estimate = PricingTable.builtIn.estimateCost([
+            "claude-opus-4-1-20250805": 1_000_000,
+            "claude-opus-4-5-20251101": 1_000_000,
+            "claude-sonnet-4-5-20250929": 1_000_000,
+            "claude-haiku-4-5-20251001": 1_000_000
+        ])
+
+        // Blended 3:1 — Opus 4.1 $30, Opus 4.5 $10, Sonnet $6, Haiku $2 per million.
+        XCTAssertEqual(estimate.cost, 48.0, accuracy: 0.0001)
+        XCTAssertEqual(estimate.unknownModels, [])
+        XCTAssertEqual(PricingTable.builtIn.defaultRate, 6.0)
+    }
+
+    func testLoadLayersFileOverBuiltInAndIgnoresBadFiles() throws {
+        let tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        try FileManager.default.createDirectory(at: tmpDir, withIntermediateDirectories: true)
+        defer { try? FileManager.default.removeItem(at: tmpDir) }
+
+        let url = tmpDir.appendingPathComponent("pricing.json")
+        try #"{"claude-opus-4": 40, "my-finetune": 2, "default": 10}"#.write(to: url, atomically: true, encoding: .utf8)
+        let loaded = PricingTable.load(from: url)
+        XCTAssertEqual(loaded.rate(for: "claude-opus-4-1"), 40)
+        XCTAssertEqual(loaded.rate(for: "my-finetune"), 2)
+        XCTAssertEqual(loaded.rate(for: "claude-sonnet-4-5"), 6)
+        XCTAssertNil(loaded.rate(for: PricingTable.defaultRateKey))
+        XCTAssertEqual(loaded.defaultRate, 10)
+
+        try #"{"claude-opus-4-1": "thirty"}"#.write(to: url, atomically: true, encoding: .utf8)
+        XCTAssertEqual(PricingTable.load(from: url), .builtIn)
+        XCTAssertEqual(PricingTable.load(from: tmpDir.appendingPathComponent("missing.json")), .builtIn)
+    }
+
+    func testEncodingRoundTripsDefaultRate() throws {
+        let pricing = PricingTable(ratesPerMillion: ["claude-opus-4-1": 30], defaultRate: 6)
+
+        let decoded = try JSONDecoder().decode(PricingTable.self, from: JSONEncoder().encode(pricing))
+
+        XCTAssertEqual(decoded, pricing)
+    }
+
+    func testWithCostsFillsTodayAndWeek() {
+        let stats = TokenStats(
+            todayTokens: 1_000_000, weekTokens: 3_000_000, todayMessages: 1, weekMessages: 3,
+            todayTokensByModel: ["claude-opus-4-1": 1_000_000],
+            weekTokensByModel: ["claude-opus-4-1": 2_000_000, "mystery-model": 1_000_000]
+        )
+
+        let priced = stats.withCosts(from: PricingTable(ratesPerMillion: ["claude-opus-4-1": 30]))
+        XCTAssertEqual(priced.todayCost, 30)
+        XCTAssertEqual(priced.weekCost, 60)
+        XCTAssertEqual(priced.unknownModels, ["mystery-model"])
+
+        let unpriced = stats.withCosts(from: nil)
+        XCTAssertNil(unpriced.todayCost)
+        XCTAssertNil(unpriced.weekCost)
+        XCTAssertEqual(unpriced.unknownModels, [])
+    }
+
+    func testFormatCost() {
+        XCTAssertEqual(TokenStats.formatCost(0.5), "$0.50")
+        XCTAssertEqual(TokenStats.formatCost(12.346), "$12.35")
+        XCTAssertEqual(TokenStats.formatCost(250.4), "$250")
+    }
+}
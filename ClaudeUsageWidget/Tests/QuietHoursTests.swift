@@ -0,0 +1,62 @@
+import XCTest
+@testable import ClaudeUsageWidget
+
+final class QuietHoursTests: XCTestCase {
+    private var calendar: Calendar!
+
+    override func setUp() {
+        calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+    }
+
+    /// 2026-03-02 is a Monday (weekday 2).
+    private func date(_ iso: String) -> Date {
+        ISO8601DateFormatter().date(from: iso)!
+    }
+
+    func testParsesTwentyFourHourTimes() {
+        let quiet = QuietHours(start: "22:00", end: " 07:30 ")
+        XCTAssertEqual(quiet?.startMinute, 22 * 60)
+        XCTAssertEqual(quiet?.endMinute, 7 * 60 + 30)
+        XCTAssertEqual(quiet?.start, "22:00")
+        XCTAssertEqual(quiet?.end, "07:30")
+
+        XCTAssertNil(QuietHours(start: "24:00", end: "07:00"))
+        XCTAssertNil(QuietHours(start: "22:5", end: "07:00"))
+        XCTAssertNil(QuietHours(start: "10pm", end: "07:00"))
+        XCTAssertNil(QuietHours(start: "22:00", end: "07:00", days: [0]))
+    }
+
+    func testSameDayWindowIncludesStartButNotEnd() throws {
+        let quiet = try XCTUnwrap(QuietHours(start: "09:00", end: "17:00"))
+
+        XCTAssertTrue(quiet.contains(date("2026-03-02T09:00:00Z"), calendar: calendar))
+        XCTAssertTrue(quiet.contains(date("2026-03-02T16:59:00Z"), calendar: calendar))
+        XCTAssertFalse(quiet.contains(date("2026-03-02T17:00:00Z"), calendar: calendar))
+        XCTAssertFalse(quiet.contains(date("2026-03-02T08:59:00Z"), calendar: calendar))
+    }
+
+    func testWindowWrapsPastMidnight() throws {
+        let quiet = try XCTUnwrap(QuietHours(start: "22:00", end: "07:00"))
+
+        XCTAssertTrue(quiet.contains(date("2026-03-02T23:30:00Z"), calendar: calendar))
+        XCTAssertTrue(quiet.contains(date("2026-03-03T02:00:00Z"), calendar: calendar))
+        XCTAssertFalse(quiet.contains(date("2026-03-03T07:00:00Z"), calendar: calendar))
+        XCTAssertFalse(quiet.contains(date("2026-03-02T12:00:00Z"), calendar: calendar))
+    }
+
+    func testDaysApplyToTheDayTheWindowStarts() throws {
+        // Friday nights only.
+        let quiet = try XCTUnwrap(QuietHours(start: "22:00", end: "07:00", days: [6]))
+
+        XCTAssertTrue(quiet.contains(date("2026-03-06T23:00:00Z"), calendar: calendar), "Friday 23:00")
+        XCTAssertTrue(quiet.contains(date("2026-03-07T03:00:00Z"), calendar: calendar), "Saturday 03:00 is still Friday night")
+        XCTAssertFalse(quiet.contains(date("2026-03-07T23:00:00Z"), calendar: calendar), "Saturday night")
+        XCTAssertFalse(quiet.contains(date("2026-03-06T03:00:00Z"), calendar: calendar), "Thursday night")
+    }
+
+    func testEqualStartAndEndIsNeverQuiet() throws {
+        let quiet = try XCTUnwrap(QuietHours(start: "08:00", end: "08:00"))
+        XCTAssertFalse(quiet.contains(date("2026-03-02T08:00:00Z"), calendar: calendar))
+    }
+}
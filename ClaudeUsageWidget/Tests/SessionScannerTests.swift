@@ -0,0 +1,185 @@
+import XCTest
+@testable import ClaudeUsageWidget
+
+final class SessionScannerTests: XCTestCase {
+    private var tmpDir: URL!
+    private var projectsDir: URL!
+    private var stateURL: URL!
+    private var calendar: Calendar!
+    private let now = ISO8601DateFormatter().date(from: "2026-03-10T12:00:00Z")!
+
+    override func setUpWithError() throws {
+        tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        projectsDir = tmpDir.appendingPathComponent("projects")
+        stateURL = tmpDir.appendingPathComponent("scan-state.json")
+        try FileManager.default.createDirectory(at: projectsDir, withIntermediateDirectories: true)
+        calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+    }
+
+    override func tearDown() {
+        try? FileManager.default.removeItem(at: tmpDir)
+    }
+
+    func testSumsTokensPerProjectAndModel() throws {
+        try write([
+            entry(id: "m1", timestamp: "2026-03-10T09:00:00Z", model: "claude-opus", input: 100, output: 50, cacheCreation: 10, cacheRead: 5),
+            entry(id: "m2", timestamp: "2026-03-08T09:00:00Z", model: "claude-sonnet", input: 200, output: 20),
+            entry(id: "m2", timestamp: "2026-03-08T09:00:00Z", model: "claude-sonnet", input: 200, output: 20),
+            entry(id: "m3", timestamp: "2026-02-20T09:00:00Z", model: "claude-sonnet", input: 9999, output: 9999),
+        ], to: "-Users-me-alpha/session-1.jsonl")
+        try write([
+            entry(id: "m4", timestamp: "2026-03-10T10:00:00Z", model: "claude-sonnet", input: 1, output: 1),
+        ], to: "-Users-me-beta/session-2.jsonl")
+
+        let projects = makeScanner().scan(now: now, calendar: calendar)
+
+        XCTAssertEqual(projects.map(\.project), ["-Users-me-alpha", "-Users-me-beta"])
+        let alpha = projects[0]
+        XCTAssertEqual(alpha.today, TokenBreakdown(input: 100, output: 50, cacheCreation: 10, cacheRead: 5))
+        XCTAssertEqual(alpha.week.total, 385, "The duplicate and the entry older than a week aren't counted")
+        XCTAssertEqual(alpha.weekByModel["claude-sonnet"], TokenBreakdown(input: 200, output: 20))
+        XCTAssertEqual(alpha.todayByModel.keys.sorted(), ["claude-opus"])
+    }
+
+    func testCalendarWeekStartsOnItsFirstWeekday() throws {
+        // 2026-03-10 is a Tuesday, so a Monday week starts on the 9th.
+        try write([
+            entry(id: "m1", timestamp: "2026-03-10T09:00:00Z", model: "claude-opus", input: 100, output: 0),
+            entry(id: "m2", timestamp: "2026-03-09T09:00:00Z", model: "claude-opus", input: 20, output: 0),
+            entry(id: "m3", timestamp: "2026-03-08T09:00:00Z", model: "claude-opus", input: 3, output: 0),
+        ], to: "-Users-me-alpha/session.jsonl")
+        let scanner = makeScanner()
+
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar, week: .startsMonday).first?.week.total, 120)
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar, week: .startsSunday).first?.week.total, 123)
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar, week: .rolling).first?.week.total, 123,
+                       "Switching back to the rolling week keeps the earlier days")
+    }
+
+    func testOnlyNewLinesAreReadOnLaterScans() throws {
+        let path = "-Users-me-alpha/session.jsonl"
+        let first = entry(id: "m1", timestamp: "2026-03-10T09:00:00Z", model: "claude-opus", input: 100, output: 0)
+        try write([first], to: path)
+        XCTAssertEqual(makeScanner().scan(now: now, calendar: calendar).first?.today.total, 100)
+
+        // Blank out the first line in place; a rescan from zero would lose its tokens.
+        let fileURL = projectsDir.appendingPathComponent(path)
+        let handle = try FileHandle(forUpdating: fileURL)
+        try handle.write(contentsOf: Data(String(repeating: " ", count: first.utf8.count).utf8))
+        try handle.seekToEnd()
+        try handle.write(contentsOf: Data((entry(id: "m2", timestamp: "2026-03-10T10:00:00Z", model: "claude-opus", input: 5, output: 0) + "\n").utf8))
+        try handle.close()
+
+        let projects = makeScanner().scan(now: now, calendar: calendar)
+
+        XCTAssertEqual(projects.first?.today.total, 105, "A new scanner resumes from the saved offset")
+    }
+
+    func testIncompleteLastLineWaitsForTheNextScan() throws {
+        let path = "-Users-me-alpha/session.jsonl"
+        let complete = entry(id: "m1", timestamp: "2026-03-10T09:00:00Z", model: "claude-opus", input: 100, output: 0)
+        let pending = entry(id: "m2", timestamp: "2026-03-10T10:00:00Z", model: "claude-opus", input: 7, output: 0)
+        let fileURL = projectsDir.appendingPathComponent(path)
+        try FileManager.default.createDirectory(at: fileURL.deletingLastPathComponent(), withIntermediateDirectories: true)
+        try Data((complete + "\n" + pending.prefix(20)).utf8).write(to: fileURL)
+
+        let scanner = makeScanner()
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar).first?.today.total, 100)
+
+        try Data((complete + "\n" + pending + "\n").utf8).write(to: fileURL)
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar).first?.today.total, 107)
+    }
+
+    func testReplacedFileIsReadFromTheStart() throws {
+        let path = "-Users-me-alpha/session.jsonl"
+        try write([
+            entry(id: "m1", timestamp: "2026-03-10T09:00:00Z", model: "claude-opus", input: 100, output: 0),
+            entry(id: "m2", timestamp: "2026-03-10T09:30:00Z", model: "claude-opus", input: 100, output: 0),
+        ], to: path)
+        let scanner = makeScanner()
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar).first?.today.total, 200)
+
+        try write([
+            entry(id: "m3", timestamp: "2026-03-10T10:00:00Z", model: "claude-opus", input: 30, output: 0),
+        ], to: path)
+
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar).first?.today.total, 30)
+    }
+
+    func testLinesSplitAcrossReadChunksAreCountedOnce() throws {
+        try write([
+            entry(id: "m1", timestamp: "2026-03-10T09:00:00Z", model: "claude-opus", input: 100, output: 0),
+            entry(id: "m2", timestamp: "2026-03-10T09:30:00Z", model: "claude-opus", input: 20, output: 0),
+            entry(id: "m3", timestamp: "2026-03-10T10:00:00Z", model: "claude-opus", input: 3, output: 0),
+        ], to: "-Users-me-alpha/session.jsonl")
+        let scanner = SessionScanner(projectsDirectoryURL: projectsDir, stateFileURL: stateURL, readChunkSize: 64)
+
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar).first?.today.total, 123)
+    }
+
+    func testMessageKeysAreDroppedWithTheirDay() throws {
+        try write([
+            entry(id: "old-message", timestamp: "2026-03-04T09:00:00Z", model: "claude-opus", input: 5, output: 0),
+            entry(id: "m1", timestamp: "2026-03-10T09:00:00Z", model: "claude-opus", input: 100, output: 0),
+        ], to: "-Users-me-alpha/session.jsonl")
+        let scanner = makeScanner()
+
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar).first?.week.total, 105)
+        XCTAssertTrue(try String(contentsOf: stateURL, encoding: .utf8).contains("old-message"))
+
+        let nextWeek = now.addingTimeInterval(3 * 86400)
+        XCTAssertEqual(scanner.scan(now: nextWeek, calendar: calendar).first?.week.total, 100)
+        XCTAssertFalse(try String(contentsOf: stateURL, encoding: .utf8).contains("old-message"))
+    }
+
+    func testDayBoundaryZoneChangeRecountsDays() throws {
+        try write([
+            entry(id: "m1", timestamp: "2026-03-10T01:00:00Z", model: "claude-opus", input: 100, output: 0),
+        ], to: "-Users-me-alpha/session.jsonl")
+        let scanner = makeScanner()
+        XCTAssertEqual(scanner.scan(now: now, calendar: calendar).first?.today.total, 100)
+
+        // 01:00 UTC is still the evening before in Los Angeles.
+        var losAngeles = calendar!
+        losAngeles.timeZone = TimeZone(identifier: "America/Los_Angeles")!
+        XCTAssertEqual(scanner.scan(now: now, calendar: losAngeles).first?.today.total, 0)
+        XCTAssertEqual(scanner.scan(now: now, calendar: losAngeles).first?.week.total, 100)
+    }
+
+    func testCorruptStateIsDiscarded() throws {
+        try Data("not json".utf8).write(to: stateURL)
+        try write([
+            entry(id: "m1", timestamp: "2026-03-10T09:00:00Z", model: "claude-opus", input: 100, output: 0),
+        ], to: "-Users-me-alpha/session.jsonl")
+
+        XCTAssertEqual(makeScanner().scan(now: now, calendar: calendar).first?.today.total, 100)
+    }
+
+    // MARK: - Helpers
+
+    private func makeScanner() -> SessionScanner {
+        SessionScanner(projectsDirectoryURL: projectsDir, stateFileURL: stateURL)
+    }
+
+    /// Writes atomically, so rewriting a path produces a new file as Claude Code does.
+    private func write(_ lines: [String], to relativePath: String) throws {
+        let fileURL = projectsDir.appendingPathComponent(relativePath)
+        try FileManager.default.createDirectory(at: fileURL.deletingLastPathComponent(), withIntermediateDirectories: true)
+        try Data(lines.map { $0 + "\n" }.joined().utf8).write(to: fileURL, options: .atomic)
+    }
+
+    private func entry(
+        id: String,
+        timestamp: String,
+        model: String,
+        input: Int,
+        output: Int,
+        cacheCreation: Int = 0,
+        cacheRead: Int = 0
+    ) -> String {
+        """
+        {"timestamp":"\(timestamp)","requestId":"req-\(id)","message":{"id":"\(id)","model":"\(model)","usage":{"input_tokens":\(input),"output_tokens":\(output),"cache_creation_input_tokens":\(cacheCreation),"cache_read_input_tokens":\(cacheRead)}}}
+        """
+    }
+}
@@ -67,6 +67,35 @@ final class SharedContainerServiceTests: XCTestCase {
         XCTAssertEqual(settings, .allEnabled)
     }
 
+    func testPaceSettingsFileIsVersioned() throws {
+        try service.writePaceSettings(PaceSettings(enabledMetrics: [.fiveHour]))
+
+        let data = try Data(contentsOf: tempDir.appendingPathComponent("pace-settings.json"))
+        let json = try JSONSerialization.jsonObject(with: data) as? [String: Any]
+        XCTAssertEqual(json?["schemaVersion"] as? Int, PaceSettings.schemaVersion)
+    }
+
+    func testUnversionedPaceSettingsAreMigrated() throws {
+        let fileURL = tempDir.appendingPathComponent("pace-settings.json")
+        try #"{"enabledMetrics": ["fiveHour", "sevenDayHaiku"]}"#.write(to: fileURL, atomically: true, encoding: .utf8)
+
+        XCTAssertEqual(service.readPaceSettings(), PaceSettings(enabledMetrics: [.fiveHour]), "Unknown windows are dropped, the rest kept")
+        XCTAssertFalse(FileManager.default.fileExists(atPath: fileURL.appendingPathExtension("bak").path))
+
+        try service.writePaceSettings(service.readPaceSettings())
+        XCTAssertEqual(service.readPaceSettings(), PaceSettings(enabledMetrics: [.fiveHour]))
+    }
+
+    func testCorruptPaceSettingsAreBackedUp() throws {
+        let fileURL = tempDir.appendingPathComponent("pace-settings.json")
+        try "{\"enabledMetrics\": [".write(to: fileURL, atomically: true, encoding: .utf8)
+
+        XCTAssertEqual(service.readPaceSettings(), .allEnabled)
+
+        let backup = try String(contentsOf: fileURL.appendingPathExtension("bak"), encoding: .utf8)
+        XCTAssertEqual(backup, "{\"enabledMetrics\": [")
+    }
+
     func testWritePaceSettingsOverwritesPrevious() throws {
         let tempDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
         try FileManager.default.createDirectory(at: tempDir, withIntermediateDirectories: true)
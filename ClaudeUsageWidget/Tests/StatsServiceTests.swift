@@ -29,6 +29,173 @@ final class StatsServiceTests: XCTestCase {
         XCTAssertEqual(stats.weekTokens, 23000)      // 15000 + 8000 (twoWeeksAgo excluded)
         XCTAssertEqual(stats.todayMessages, 42)
         XCTAssertEqual(stats.weekMessages, 72)        // 42 + 30 (twoWeeksAgo excluded)
+        XCTAssertEqual(stats.todayTokensByModel, ["claude-sonnet": 10000, "claude-opus": 5000])
+        XCTAssertEqual(stats.weekTokensByModel, ["claude-sonnet": 18000, "claude-opus": 5000])
+    }
+
+    func testCalculateTokenStatsWeekIsSevenDaysAcrossMonthBoundary() {
+        var calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+        let now = calendar.date(from: DateComponents(year: 2026, month: 3, day: 3, hour: 12))!
+
+        let cache = StatsCache(
+            dailyActivity: [
+                DailyActivity(date: "2026-03-03", messageCount: 1, sessionCount: 1, toolCallCount: 0),
+                DailyActivity(date: "2026-02-25", messageCount: 100, sessionCount: 1, toolCallCount: 0)
+            ],
+            dailyModelTokens: [
+                DailyTokens(date: "2026-03-03", tokensByModel: ["claude-sonnet": 1]),
+                DailyTokens(date: "2026-03-01", tokensByModel: ["claude-sonnet": 10]),
+                DailyTokens(date: "2026-02-26", tokensByModel: ["claude-sonnet": 100]),   // 6 days ago: included
+                DailyTokens(date: "2026-02-25", tokensByModel: ["claude-sonnet": 1000]),  // 7 days ago: excluded
+                DailyTokens(date: "2026-03-04", tokensByModel: ["claude-sonnet": 10000]), // future: excluded
+                DailyTokens(date: "03/02/2026", tokensByModel: ["claude-sonnet": 100000]) // unparseable: skipped
+            ],
+            lastComputedDate: "2026-03-03"
+        )
+
+        let stats = StatsService.calculateTokenStats(from: cache, now: now, calendar: calendar)
+
+        XCTAssertEqual(stats.todayTokens, 1)
+        XCTAssertEqual(stats.weekTokens, 111)
+        XCTAssertEqual(stats.todayMessages, 1)
+        XCTAssertEqual(stats.weekMessages, 1)
+    }
+
+    func testCalculateTokenStatsIncludesYesterday() {
+        var calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+        let now = calendar.date(from: DateComponents(year: 2026, month: 3, day: 1, hour: 12))!
+        let cache = StatsCache(
+            dailyActivity: [DailyActivity(date: "2026-02-28", messageCount: 8, sessionCount: 1, toolCallCount: 0)],
+            dailyModelTokens: [
+                DailyTokens(date: "2026-03-01", tokensByModel: ["claude-sonnet": 150]),
+                DailyTokens(date: "2026-02-28", tokensByModel: ["claude-sonnet": 80, "claude-opus": 20])
+            ],
+            lastComputedDate: "2026-03-01"
+        )
+
+        let stats = StatsService.calculateTokenStats(from: cache, now: now, calendar: calendar)
+
+        XCTAssertEqual(stats.yesterdayTokens, 100)
+        XCTAssertEqual(stats.yesterdayMessages, 8)
+        XCTAssertEqual(stats.tokenDeltaPercent ?? 0, 50, accuracy: 0.0001)
+
+        let noYesterday = StatsService.calculateTokenStats(
+            from: StatsCache(dailyActivity: nil, dailyModelTokens: [cache.dailyModelTokens![0]], lastComputedDate: nil),
+            now: now,
+            calendar: calendar
+        )
+        XCTAssertNil(noYesterday.yesterdayTokens)
+        XCTAssertNil(noYesterday.yesterdayMessages)
+        XCTAssertNil(noYesterday.tokenDeltaPercent)
+    }
+
+    func testCalculateTokenStatsCalendarWeekAcrossMonthBoundary() {
+        var calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+        let now = calendar.date(from: DateComponents(year: 2026, month: 3, day: 1, hour: 12))! // a Sunday
+
+        let cache = StatsCache(
+            dailyActivity: nil,
+            dailyModelTokens: [
+                DailyTokens(date: "2026-03-01", tokensByModel: ["claude-sonnet": 1]),
+                DailyTokens(date: "2026-02-28", tokensByModel: ["claude-sonnet": 10]),
+                DailyTokens(date: "2026-02-23", tokensByModel: ["claude-sonnet": 100]),  // Monday
+                DailyTokens(date: "2026-02-22", tokensByModel: ["claude-sonnet": 1000]), // previous Sunday
+                DailyTokens(date: "2026-13-01", tokensByModel: ["claude-sonnet": 10000]) // malformed: skipped
+            ],
+            lastComputedDate: "2026-03-01"
+        )
+
+        let mondayWeek = StatsService.calculateTokenStats(from: cache, now: now, calendar: calendar, week: .startsMonday)
+        let sundayWeek = StatsService.calculateTokenStats(from: cache, now: now, calendar: calendar, week: .startsSunday)
+
+        XCTAssertEqual(mondayWeek.weekTokens, 111)
+        XCTAssertEqual(sundayWeek.weekTokens, 1, "A week starting today covers only today")
+        XCTAssertEqual(sundayWeek.todayTokens, 1)
+    }
+
+    func testTodayFollowsDayBoundaryTimeZoneNearMidnight() {
+        // 01:00 on the 11th in Kiritimati (UTC+14) is still the 10th in UTC.
+        let now = ISO8601DateFormatter().date(from: "2026-03-10T11:00:00Z")!
+        let cache = StatsCache(
+            dailyActivity: nil,
+            dailyModelTokens: [
+                DailyTokens(date: "2026-03-11", tokensByModel: ["claude-sonnet": 1]),
+                DailyTokens(date: "2026-03-10", tokensByModel: ["claude-sonnet": 10])
+            ],
+            lastComputedDate: "2026-03-11"
+        )
+
+        let kiritimati = StatsService.calendar(in: TimeZone(identifier: "Pacific/Kiritimati")!)
+        let utc = StatsService.calendar(in: TimeZone(identifier: "UTC")!)
+
+        XCTAssertEqual(StatsService.calculateTokenStats(from: cache, now: now, calendar: kiritimati).todayTokens, 1)
+        XCTAssertEqual(StatsService.calculateTokenStats(from: cache, now: now, calendar: utc).todayTokens, 10)
+        XCTAssertEqual(StatsService.endOfDay(now: now, calendar: kiritimati), ISO8601DateFormatter().date(from: "2026-03-11T10:00:00Z"))
+        XCTAssertEqual(StatsService.endOfDay(now: now, calendar: utc), ISO8601DateFormatter().date(from: "2026-03-11T00:00:00Z"))
+    }
+
+    func testStartOfWeekPerDefinition() {
+        var calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+        let formatter = StatsService.dayFormatter(calendar: calendar)
+        let now = calendar.date(from: DateComponents(year: 2026, month: 3, day: 4, hour: 23))! // a Wednesday
+
+        func start(_ week: WeekDefinition) -> String {
+            formatter.string(from: StatsService.startOfWeek(now: now, calendar: calendar, week: week))
+        }
+
+        XCTAssertEqual(start(.rolling), "2026-02-26")
+        XCTAssertEqual(start(.startsMonday), "2026-03-02")
+        XCTAssertEqual(start(.startsSunday), "2026-03-01")
+        XCTAssertEqual(StatsService.daysInWeekSoFar(now: now, calendar: calendar, week: .startsMonday), 3)
+    }
+
+    func testDaysAgoCountsCalendarDays() {
+        var calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+        let formatter = StatsService.dayFormatter(calendar: calendar)
+        let now = calendar.date(from: DateComponents(year: 2026, month: 3, day: 1, hour: 0, minute: 5))!
+
+        XCTAssertEqual(StatsService.daysAgo("2026-03-01", now: now, formatter: formatter, calendar: calendar), 0)
+        XCTAssertEqual(StatsService.daysAgo("2026-02-28", now: now, formatter: formatter, calendar: calendar), 1)
+        XCTAssertEqual(StatsService.daysAgo("2025-12-31", now: now, formatter: formatter, calendar: calendar), 60)
+        XCTAssertNil(StatsService.daysAgo("2026-02-30", now: now, formatter: formatter, calendar: calendar))
+        XCTAssertNil(StatsService.daysAgo("", now: now, formatter: formatter, calendar: calendar))
+    }
+
+    func testTokenHistoryFillsGapsAndSortsAscending() {
+        var calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+        let now = calendar.date(from: DateComponents(year: 2026, month: 3, day: 2, hour: 9))!
+
+        let cache = StatsCache(
+            dailyActivity: nil,
+            dailyModelTokens: [
+                DailyTokens(date: "2026-03-02", tokensByModel: ["claude-sonnet": 10]),
+                DailyTokens(date: "2026-02-27", tokensByModel: ["claude-opus": 5]),
+                DailyTokens(date: "2026-02-20", tokensByModel: ["claude-opus": 999]),
+                DailyTokens(date: "not-a-date", tokensByModel: ["claude-opus": 1])
+            ],
+            lastComputedDate: nil
+        )
+
+        let history = StatsService.tokenHistory(from: cache, days: 4, now: now, calendar: calendar)
+
+        XCTAssertEqual(history.map(\.date), ["2026-02-27", "2026-02-28", "2026-03-01", "2026-03-02"])
+        XCTAssertEqual(history.map(\.totalTokens), [5, 0, 0, 10])
+        XCTAssertEqual(StatsService.tokenHistory(from: cache, days: 0, now: now, calendar: calendar), [])
+    }
+
+    func testTokenHistoryFromMissingCacheIsAllZeros() {
+        let service = StatsService(statsFilePath: "/nonexistent/path/stats-cache.json")
+
+        let history = service.tokenHistory(days: 7)
+
+        XCTAssertEqual(history.count, 7)
+        XCTAssertTrue(history.allSatisfy { $0.totalTokens == 0 })
     }
 
     func testCalculateTokenStatsEmptyCache() {
@@ -65,6 +232,103 @@ final class StatsServiceTests: XCTestCase {
         XCTAssertEqual(stats.todayMessages, 10)
     }
 
+    func testReadStatsKeepsLastGoodCacheWhenFileIsTruncated() throws {
+        let tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        try FileManager.default.createDirectory(at: tmpDir, withIntermediateDirectories: true)
+        defer { try? FileManager.default.removeItem(at: tmpDir) }
+
+        let today = Self.dateString(daysAgo: 0)
+        let json = """
+        {"dailyModelTokens": [{"date": "\(today)", "tokensByModel": {"claude-sonnet": 3000}}]}
+        """
+        let filePath = tmpDir.appendingPathComponent("stats-cache.json")
+        try json.write(to: filePath, atomically: true, encoding: .utf8)
+
+        let service = StatsService(
+            statsFilePath: filePath.path,
+            sessionMetaDirectoryPath: tmpDir.path,
+            projectsDirectoryPath: tmpDir.appendingPathComponent("missing-projects").path,
+            partialReadRetryDelay: 0
+        )
+        XCTAssertEqual(service.readStats().todayTokens, 3000)
+
+        try String(json.prefix(30)).write(to: filePath, atomically: true, encoding: .utf8)
+
+        XCTAssertEqual(service.readStats().todayTokens, 3000)
+        XCTAssertEqual(service.tokenHistory(days: 1).last?.totalTokens, 3000)
+    }
+
+    func testReadStatsRetriesTruncatedFileOnce() throws {
+        let tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        try FileManager.default.createDirectory(at: tmpDir, withIntermediateDirectories: true)
+        defer { try? FileManager.default.removeItem(at: tmpDir) }
+
+        let today = Self.dateString(daysAgo: 0)
+        let json = """
+        {"dailyModelTokens": [{"date": "\(today)", "tokensByModel": {"claude-sonnet": 3000}}]}
+        """
+        let filePath = tmpDir.appendingPathComponent("stats-cache.json")
+        try String(json.prefix(30)).write(to: filePath, atomically: true, encoding: .utf8)
+        // Finish the write while the first read is waiting to retry.
+        DispatchQueue.global().asyncAfter(deadline: .now() + 0.05) {
+            try? json.write(to: filePath, atomically: true, encoding: .utf8)
+        }
+
+        let service = StatsService(
+            statsFilePath: filePath.path,
+            sessionMetaDirectoryPath: tmpDir.path,
+            projectsDirectoryPath: tmpDir.appendingPathComponent("missing-projects").path,
+            partialReadRetryDelay: 0.5
+        )
+
+        XCTAssertEqual(service.readStats().todayTokens, 3000)
+    }
+
+    func testStatsFileOverrideMovesAllSourcesToItsDirectory() throws {
+        let tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        let profileDir = tmpDir.appendingPathComponent("claude-work")
+        try FileManager.default.createDirectory(at: profileDir, withIntermediateDirectories: true)
+        defer { try? FileManager.default.removeItem(at: tmpDir) }
+
+        let today = Self.dateString(daysAgo: 0)
+        let overrideFile = profileDir.appendingPathComponent("stats-cache.json")
+        try """
+        {"dailyModelTokens": [{"date": "\(today)", "tokensByModel": {"claude-sonnet": 700}}]}
+        """.write(to: overrideFile, atomically: true, encoding: .utf8)
+
+        var override: URL?
+        let service = StatsService(
+            statsFilePath: tmpDir.appendingPathComponent("missing-cache.json").path,
+            sessionMetaDirectoryPath: tmpDir.path,
+            projectsDirectoryPath: tmpDir.appendingPathComponent("missing-projects").path,
+            statsFileOverride: { override }
+        )
+        XCTAssertEqual(service.readStats().todayTokens, 0)
+
+        override = overrideFile
+        XCTAssertEqual(service.readStats().todayTokens, 700)
+    }
+
+    func testStatsCacheOverrideReadsOnlyTheCacheFromItsPath() throws {
+        let tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        try FileManager.default.createDirectory(at: tmpDir, withIntermediateDirectories: true)
+        defer { try? FileManager.default.removeItem(at: tmpDir) }
+
+        let cacheFile = tmpDir.appendingPathComponent("stats-cache.json")
+        let service = StatsService(
+            statsFilePath: tmpDir.appendingPathComponent("default-cache.json").path,
+            sessionMetaDirectoryPath: tmpDir.path,
+            projectsDirectoryPath: tmpDir.appendingPathComponent("missing-projects").path,
+            statsCacheOverride: { cacheFile }
+        )
+        XCTAssertEqual(service.readStats().todayTokens, 0, "A missing custom path reads as an empty cache")
+
+        try """
+        {"dailyModelTokens": [{"date": "\(Self.dateString(daysAgo: 0))", "tokensByModel": {"claude-sonnet": 300}}]}
+        """.write(to: cacheFile, atomically: true, encoding: .utf8)
+        XCTAssertEqual(service.readStats().todayTokens, 300)
+    }
+
     func testReadStatsMissingFile() {
         let tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
         try? FileManager.default.createDirectory(at: tmpDir, withIntermediateDirectories: true)
@@ -247,6 +511,50 @@ final class StatsServiceTests: XCTestCase {
         XCTAssertEqual(stats.weekMessages, 3)
     }
 
+    func testReadStatsBreaksDownTranscriptTokensByModel() throws {
+        let tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        let projectsDir = tmpDir.appendingPathComponent("projects")
+        let sessionDir = projectsDir.appendingPathComponent("demo-project")
+        try FileManager.default.createDirectory(at: sessionDir, withIntermediateDirectories: true)
+        defer { try? FileManager.default.removeItem(at: tmpDir) }
+
+        try Self.writeTranscriptEntries(
+            to: sessionDir.appendingPathComponent("usage.jsonl"),
+            entries: [
+                .init(timestamp: Self.isoDate(daysAgo: 0, fractionalSeconds: false), requestId: "r1", messageId: "m1",
+                      inputTokens: 10, outputTokens: 20, cacheCreationInputTokens: 0, cacheReadInputTokens: 0,
+                      model: "claude-sonnet-4-5"),
+                .init(timestamp: Self.isoDate(daysAgo: 0, fractionalSeconds: false), requestId: "r2", messageId: "m2",
+                      inputTokens: 100, outputTokens: 0, cacheCreationInputTokens: 0, cacheReadInputTokens: 0,
+                      model: "claude-opus-4-1"),
+                .init(timestamp: Self.isoDate(daysAgo: 2, fractionalSeconds: false), requestId: "r3", messageId: "m3",
+                      inputTokens: 5, outputTokens: 5, cacheCreationInputTokens: 0, cacheReadInputTokens: 0,
+                      model: "claude-sonnet-4-5"),
+                .init(timestamp: Self.isoDate(daysAgo: 2, fractionalSeconds: false), requestId: "r4", messageId: "m4",
+                      inputTokens: 1, outputTokens: 1, cacheCreationInputTokens: 0, cacheReadInputTokens: 0)
+            ]
+        )
+
+        let pricingURL = tmpDir.appendingPathComponent("pricing.json")
+        try #"{"claude-opus-4-1": 30000}"#.write(to: pricingURL, atomically: true, encoding: .utf8)
+
+        let service = StatsService(
+            statsFilePath: tmpDir.appendingPathComponent("missing-cache.json").path,
+            sessionMetaDirectoryPath: tmpDir.appendingPathComponent("missing-session-meta").path,
+            projectsDirectoryPath: projectsDir.path,
+            pricingFileURL: pricingURL
+        )
+
+        let stats = service.readStats()
+
+        // The file overrides Opus; Sonnet keeps its built-in $6 per million.
+        XCTAssertEqual(stats.todayCost ?? 0, 3.0 + 30 * 6.0 / 1_000_000, accuracy: 0.000001)
+        XCTAssertEqual(stats.unknownModels, [StatsService.unknownModel])
+        XCTAssertEqual(stats.todayTokensByModel, ["claude-sonnet-4-5": 30, "claude-opus-4-1": 100])
+        XCTAssertEqual(stats.weekTokensByModel, ["claude-sonnet-4-5": 40, "claude-opus-4-1": 100, StatsService.unknownModel: 2])
+        XCTAssertEqual(stats.weekTokensByModel.values.reduce(0, +), stats.weekTokens)
+    }
+
     func testReadStatsReusesCachedTranscriptStatsWhenTranscriptFingerprintIsUnchanged() throws {
         let tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
         let projectsDir = tmpDir.appendingPathComponent("projects")
@@ -465,6 +773,9 @@ final class StatsServiceTests: XCTestCase {
             if let messageId = entry.messageId {
                 fields[1].append(",\"id\":\"\(messageId)\"")
             }
+            if let model = entry.model {
+                fields[1].append(",\"model\":\"\(model)\"")
+            }
             fields[1].append("}")
 
             if let requestId = entry.requestId {
@@ -486,4 +797,5 @@ private struct TranscriptFixture {
     let outputTokens: Int
     let cacheCreationInputTokens: Int
     let cacheReadInputTokens: Int
+    var model: String? = nil
 }
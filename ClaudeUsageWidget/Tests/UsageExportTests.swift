@@ -0,0 +1,160 @@
+import XCTest
+@testable import ClaudeUsageWidget
+
+final class UsageExportTests: XCTestCase {
+    private var tmpDir: URL!
+    private var calendar: Calendar!
+
+    override func setUpWithError() throws {
+        tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        try FileManager.default.createDirectory(at: tmpDir, withIntermediateDirectories: true)
+        calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+    }
+
+    override func tearDown() {
+        try? FileManager.default.removeItem(at: tmpDir)
+    }
+
+    private let cache = StatsCache(
+        dailyActivity: [
+            DailyActivity(date: "2026-02-28", messageCount: 99, sessionCount: 9, toolCallCount: 9),
+            DailyActivity(date: "2026-03-01", messageCount: 12, sessionCount: 2, toolCallCount: 5)
+        ],
+        dailyModelTokens: [
+            DailyTokens(date: "2026-03-01", tokensByModel: ["claude-opus": 100, "claude-sonnet": 50]),
+            DailyTokens(date: "2026-03-02", tokensByModel: ["claude-sonnet": 7]),
+            DailyTokens(date: "not-a-date", tokensByModel: ["claude-sonnet": 1000])
+        ],
+        lastComputedDate: "2026-03-02"
+    )
+
+    private func date(_ iso: String) -> Date {
+        ISO8601DateFormatter().date(from: iso)!
+    }
+
+    func testDaysMergesStatsAndHistoryPeaksWithinRange() throws {
+        let history = [
+            UsageHistoryPoint(recordedAt: date("2026-03-01T09:00:00Z"), fiveHourPercent: 40, sevenDayPercent: 10,
+                              sevenDaySonnetPercent: nil, sevenDayOpusPercent: nil, todayTokens: 0),
+            UsageHistoryPoint(recordedAt: date("2026-03-01T15:00:00Z"), fiveHourPercent: 85, sevenDayPercent: nil,
+                              sevenDaySonnetPercent: nil, sevenDayOpusPercent: nil, todayTokens: 0)
+        ]
+
+        let days = try UsageExport.days(
+            from: date("2026-03-01T00:00:00Z"),
+            to: date("2026-03-02T23:00:00Z"),
+            cache: cache,
+            history: history,
+            calendar: calendar
+        )
+
+        XCTAssertEqual(days.map(\.date), ["2026-03-01", "2026-03-02"])
+        XCTAssertEqual(days[0].messages, 12)
+        XCTAssertEqual(days[0].totalTokens, 150)
+        XCTAssertEqual(days[0].peakFiveHourPercent, 85)
+        XCTAssertEqual(days[0].peakSevenDayPercent, 10)
+        XCTAssertEqual(days[1].messages, 0)
+        XCTAssertNil(days[1].peakFiveHourPercent)
+    }
+
+    func testDaysRejectsReversedRange() {
+        XCTAssertThrowsError(try UsageExport.days(
+            from: date("2026-03-02T00:00:00Z"),
+            to: date("2026-03-01T00:00:00Z"),
+            cache: cache,
+            history: [],
+            calendar: calendar
+        )) { error in
+            XCTAssertEqual(error as? ExportError, .invalidRange)
+        }
+    }
+
+    func testCSVHasOneColumnPerModelAndATotal() throws {
+        let days = try UsageExport.days(from: date("2026-03-01T00:00:00Z"), to: date("2026-03-02T00:00:00Z"), cache: cache, history: [], calendar: calendar)
+        let url = tmpDir.appendingPathComponent("usage.csv")
+
+        let rows = try UsageExport.write(days, format: .csv, to: url)
+
+        XCTAssertEqual(rows, 2)
+        XCTAssertEqual(try String(contentsOf: url, encoding: .utf8), """
+        date,messages,sessions,tool_calls,claude-opus,claude-sonnet,total_tokens,peak_five_hour_percent,peak_seven_day_percent
+        2026-03-01,12,2,5,100,50,150,,
+        2026-03-02,0,0,0,0,7,7,,
+
+        """)
+    }
+
+    func testHistoryCSVHasOneLinePerRow() throws {
+        let url = tmpDir.appendingPathComponent("history.csv")
+        let points = [
+            UsageHistoryPoint(recordedAt: date("2026-03-01T09:00:00Z"), fiveHourPercent: 40, sevenDayPercent: nil,
+                              sevenDaySonnetPercent: nil, sevenDayOpusPercent: nil, todayTokens: 1200, weekTokens: 9000),
+            UsageHistoryPoint(recordedAt: date("2026-03-01T09:05:00Z"), fiveHourPercent: 41.5, sevenDayPercent: 12,
+                              sevenDaySonnetPercent: nil, sevenDayOpusPercent: nil, todayTokens: 1300)
+        ]
+
+        let rows = try UsageExport.writeHistory(to: url) { write in
+            try points.forEach(write)
+        }
+
+        XCTAssertEqual(rows, 2)
+        XCTAssertEqual(try String(contentsOf: url, encoding: .utf8), """
+        timestamp,five_hour_percent,seven_day_percent,today_tokens,week_tokens
+        2026-03-01T09:00:00Z,40.0,,1200,9000
+        2026-03-01T09:05:00Z,41.5,12.0,1300,
+
+        """)
+    }
+
+    func testHistoryCSVFailsForUnwritablePath() {
+        let url = tmpDir.appendingPathComponent("missing-dir/history.csv")
+
+        XCTAssertThrowsError(try UsageExport.writeHistory(to: url) { _ in }) { error in
+            guard case .writeFailed = error as? ExportError else {
+                return XCTFail("Expected writeFailed, got \(error)")
+            }
+        }
+    }
+
+    func testSnapshotJSONMasksCredentials() throws {
+        let url = tmpDir.appendingPathComponent("snapshot.json")
+        let snapshot = UsageSnapshot(
+            fiveHour: UsageMetric(percent: 42, resetsAt: date("2026-03-01T12:00:00Z")),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil, codex: nil,
+            tokenStats: .zero, lastUpdated: date("2026-03-01T10:00:00Z"), lastSuccessfulUpdate: nil,
+            error: "Request with my-secret failed: sk-ant-oat01-AbC_123"
+        )
+
+        XCTAssertTrue(try UsageExport.writeSnapshot(snapshot, secrets: ["my-secret"], to: url))
+
+        let text = try String(contentsOf: url, encoding: .utf8)
+        XCTAssertFalse(text.contains("my-secret"))
+        XCTAssertFalse(text.contains("AbC_123"))
+        XCTAssertTrue(text.contains("\n  "), "Pretty-printed")
+        let decoded = try UsageSnapshot.makeDecoder().decode(UsageSnapshot.self, from: Data(text.utf8))
+        XCTAssertEqual(decoded.fiveHour?.percent, 42)
+    }
+
+    func testSnapshotJSONUnchangedWithoutCredentials() throws {
+        let url = tmpDir.appendingPathComponent("snapshot.json")
+        let snapshot = UsageSnapshot(
+            fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil, codex: nil,
+            tokenStats: .zero, lastUpdated: date("2026-03-01T10:00:00Z"), lastSuccessfulUpdate: nil, error: nil
+        )
+
+        XCTAssertFalse(try UsageExport.writeSnapshot(snapshot, secrets: ["unused-token"], to: url))
+    }
+
+    func testJSONIsAnArrayOfDays() throws {
+        let days = try UsageExport.days(from: date("2026-03-01T00:00:00Z"), to: date("2026-03-02T00:00:00Z"), cache: cache, history: [], calendar: calendar)
+        let url = tmpDir.appendingPathComponent("usage.json")
+
+        XCTAssertEqual(try UsageExport.write(days, format: .json, to: url), 2)
+
+        let decoded = try JSONSerialization.jsonObject(with: Data(contentsOf: url)) as? [[String: Any]]
+        XCTAssertEqual(decoded?.count, 2)
+        XCTAssertEqual(decoded?.first?["totalTokens"] as? Int, 150)
+        XCTAssertEqual((decoded?.first?["tokensByModel"] as? [String: Int])?["claude-opus"], 100)
+    }
+}
@@ -0,0 +1,135 @@
+import SQLite3
+import XCTest
+@testable import ClaudeUsageWidget
+
+final class UsageHistoryServiceTests: XCTestCase {
+    private var tmpDir: URL!
+    private var service: UsageHistoryService!
+
+    override func setUpWithError() throws {
+        tmpDir = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        service = UsageHistoryService(databaseURL: tmpDir.appendingPathComponent("nested/history.db"))
+    }
+
+    override func tearDown() {
+        try? FileManager.default.removeItem(at: tmpDir)
+    }
+
+    private func makeSnapshot(at timestamp: TimeInterval, fiveHour: Double) -> UsageSnapshot {
+        UsageSnapshot(
+            fiveHour: UsageMetric(percent: fiveHour, resetsAt: Date(timeIntervalSince1970: timestamp + 3600)),
+            sevenDay: UsageMetric(percent: 20, resetsAt: Date(timeIntervalSince1970: timestamp + 86400)),
+            sevenDaySonnet: nil,
+            sevenDayOpus: nil,
+            tokenStats: TokenStats(todayTokens: 1200, weekTokens: 9000, todayMessages: 4, weekMessages: 30),
+            lastUpdated: Date(timeIntervalSince1970: timestamp),
+            lastSuccessfulUpdate: Date(timeIntervalSince1970: timestamp),
+            error: nil
+        )
+    }
+
+    func testRecordCreatesDatabaseAndRoundTrips() throws {
+        let snapshot = makeSnapshot(at: 1_790_000_000, fiveHour: 42)
+
+        try service.record(snapshot)
+        let results = try service.snapshots(from: .distantPast, to: .distantFuture)
+
+        XCTAssertEqual(results, [snapshot])
+        XCTAssertTrue(FileManager.default.fileExists(atPath: tmpDir.appendingPathComponent("nested/history.db").path))
+    }
+
+    func testSnapshotsFiltersByRangeInOrder() throws {
+        try service.record(makeSnapshot(at: 1_790_000_300, fiveHour: 30))
+        try service.record(makeSnapshot(at: 1_790_000_100, fiveHour: 10))
+        try service.record(makeSnapshot(at: 1_790_000_200, fiveHour: 20))
+        try service.record(makeSnapshot(at: 1_790_000_400, fiveHour: 40))
+
+        let results = try service.snapshots(
+            from: Date(timeIntervalSince1970: 1_790_000_100),
+            to: Date(timeIntervalSince1970: 1_790_000_300)
+        )
+
+        XCTAssertEqual(results.map { $0.fiveHour?.percent }, [10, 20, 30])
+    }
+
+    func testPruneDropsRowsOlderThanCutoff() throws {
+        try service.record(makeSnapshot(at: 1_790_000_000, fiveHour: 10))
+        try service.record(makeSnapshot(at: 1_790_100_000, fiveHour: 20))
+
+        try service.prune(olderThan: Date(timeIntervalSince1970: 1_790_050_000))
+
+        let results = try service.snapshots(from: .distantPast, to: .distantFuture)
+        XCTAssertEqual(results.map { $0.fiveHour?.percent }, [20])
+    }
+
+    func testEmptyDatabaseReturnsNoSnapshots() throws {
+        XCTAssertEqual(try service.snapshots(from: .distantPast, to: .distantFuture), [])
+    }
+
+    func testSeriesKeepsPeakPerBucket() throws {
+        let base: TimeInterval = 1_789_999_800 // a multiple of 600
+        try service.record(makeSnapshot(at: base, fiveHour: 10))
+        try service.record(makeSnapshot(at: base + 300, fiveHour: 35))
+        try service.record(makeSnapshot(at: base + 700, fiveHour: 20))
+
+        let points = try service.series(from: .distantPast, to: .distantFuture, resolution: 600)
+
+        XCTAssertEqual(points.map(\.recordedAt.timeIntervalSince1970), [base, base + 600])
+        XCTAssertEqual(points.map(\.fiveHourPercent), [35, 20])
+        XCTAssertEqual(points.first?.sevenDaySonnetPercent, nil)
+        XCTAssertEqual(points.first?.todayTokens, 1200)
+    }
+
+    func testForEachPointVisitsRawRowsInRange() throws {
+        try service.record(makeSnapshot(at: 1_790_000_200, fiveHour: 20))
+        try service.record(makeSnapshot(at: 1_790_000_100, fiveHour: 10))
+        try service.record(makeSnapshot(at: 1_790_000_900, fiveHour: 90))
+
+        var points: [UsageHistoryPoint] = []
+        try service.forEachPoint(from: .distantPast, to: Date(timeIntervalSince1970: 1_790_000_500)) { points.append($0) }
+
+        XCTAssertEqual(points.map(\.fiveHourPercent), [10, 20])
+        XCTAssertEqual(points.first?.weekTokens, 9000)
+    }
+
+    func testRollUpStoresPeaksForFinishedDaysOnly() throws {
+        var calendar = Calendar(identifier: .gregorian)
+        calendar.timeZone = TimeZone(identifier: "UTC")!
+        let day1 = ISO8601DateFormatter().date(from: "2026-03-01T08:00:00Z")!.timeIntervalSince1970
+        try service.record(makeSnapshot(at: day1, fiveHour: 40))
+        try service.record(makeSnapshot(at: day1 + 3600, fiveHour: 97))
+        // Nothing on 03-02; 03-03 is today and still in progress.
+        try service.record(makeSnapshot(at: day1 + 2 * 86400, fiveHour: 10))
+        let now = Date(timeIntervalSince1970: day1 + 2 * 86400 + 60)
+
+        XCTAssertEqual(try service.rollUpDailyPeaks(now: now, calendar: calendar), 1)
+        XCTAssertEqual(try service.rollUpDailyPeaks(now: now, calendar: calendar), 0, "Days already rolled up are skipped")
+
+        let peaks = try service.dailyPeaks(days: 30)
+        XCTAssertEqual(peaks, [
+            DailyPeak(day: "2026-03-01", fiveHourPercent: 97, sevenDayPercent: 20, sevenDaySonnetPercent: nil, sevenDayOpusPercent: nil)
+        ])
+
+        XCTAssertEqual(try service.rollUpDailyPeaks(now: now.addingTimeInterval(86400), calendar: calendar), 1)
+        XCTAssertEqual(try service.dailyPeaks(days: 30).map(\.day), ["2026-03-01", "2026-03-03"])
+        XCTAssertEqual(try service.dailyPeaks(days: 1).map(\.day), ["2026-03-03"])
+    }
+
+    func testUnversionedDatabaseIsMigratedInPlace() throws {
+        let url = tmpDir.appendingPathComponent("legacy.db")
+        try FileManager.default.createDirectory(at: tmpDir, withIntermediateDirectories: true)
+        var db: OpaquePointer?
+        XCTAssertEqual(sqlite3_open(url.path, &db), SQLITE_OK)
+        XCTAssertEqual(sqlite3_exec(db, UsageHistoryService.migrations[0], nil, nil, nil), SQLITE_OK)
+        XCTAssertEqual(UsageHistoryService.userVersion(db!), 0)
+        sqlite3_close(db)
+
+        let legacy = UsageHistoryService(databaseURL: url)
+        try legacy.record(makeSnapshot(at: 1_790_000_000, fiveHour: 10))
+
+        XCTAssertEqual(try legacy.snapshots(from: .distantPast, to: .distantFuture).count, 1)
+        XCTAssertEqual(sqlite3_open(url.path, &db), SQLITE_OK)
+        XCTAssertEqual(UsageHistoryService.userVersion(db!), UsageHistoryService.migrations.count)
+        sqlite3_close(db)
+    }
+}
@@ -5,33 +5,50 @@ final class UsageManagerTests: XCTestCase {
     var manager: UsageManager!
     var mockKeychain: MockKeychainService!
     var mockAPI: MockAPIService!
+    var mockTokenRefresh: MockTokenRefreshService!
     var mockStats: MockStatsService!
     var mockCodexAuth: MockCodexAuthService!
     var mockCodexAPI: MockCodexAPIService!
     var mockCodexStats: MockStatsService!
     var mockContainer: MockSharedContainerService!
+    var mockHistory: MockUsageHistoryService!
+    var mockNotifier: MockUsageNotifier!
+    var defaults: UserDefaults!
     var mockReloader: MockWidgetReloader!
+    var lowPowerMode = false
+    var now = Date()
 
     @MainActor
-    override func setUp() {
+    override func setUp() async throws {
         mockKeychain = MockKeychainService()
         mockAPI = MockAPIService()
+        mockTokenRefresh = MockTokenRefreshService()
         mockStats = MockStatsService()
         mockCodexAuth = MockCodexAuthService()
         mockCodexAPI = MockCodexAPIService()
         mockCodexStats = MockStatsService()
         mockContainer = MockSharedContainerService()
+        mockHistory = MockUsageHistoryService()
+        mockNotifier = MockUsageNotifier()
+        defaults = UserDefaults(suiteName: "UsageManagerTests-\(UUID().uuidString)")
         mockReloader = MockWidgetReloader()
         manager = UsageManager(
             keychainService: mockKeychain,
             apiService: mockAPI,
+            tokenRefreshService: mockTokenRefresh,
             statsService: mockStats,
             codexAuthService: mockCodexAuth,
             codexAPIService: mockCodexAPI,
             codexStatsService: mockCodexStats,
             containerService: mockContainer,
-            widgetReloader: mockReloader.reload
+            historyService: mockHistory,
+            notifier: mockNotifier,
+            settings: AppSettings(defaults: defaults),
+            widgetReloader: mockReloader.reload,
+            isLowPowerMode: { [weak self] in self?.lowPowerMode ?? false },
+            now: { [weak self] in self?.now ?? Date() }
         )
+        await manager.launchHistoryMaintenance?.value
     }
 
     @MainActor
@@ -57,6 +74,9 @@ final class UsageManagerTests: XCTestCase {
             codexAPIService: mockCodexAPI,
             codexStatsService: mockCodexStats,
             containerService: mockContainer,
+            historyService: mockHistory,
+            notifier: mockNotifier,
+            settings: AppSettings(defaults: defaults),
             widgetReloader: mockReloader.reload
         )
 
@@ -96,6 +116,233 @@ final class UsageManagerTests: XCTestCase {
         XCTAssertEqual(mockCodexAuth.lastReadOnMainThread, false)
     }
 
+    @MainActor
+    func testOnlyScheduledRefreshesMayReuseCachedResponse() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        await manager.refresh()
+        XCTAssertEqual(mockAPI.lastAllowingCached, true)
+
+        await manager.refresh(userInitiated: true)
+        XCTAssertEqual(mockAPI.lastAllowingCached, false)
+    }
+
+    @MainActor
+    func testStatsAreReadWhileSlowUsageRequestIsInFlight() async throws {
+        let fetchStarted = DispatchSemaphore(value: 0)
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        mockAPI.onFetchUsage = { fetchStarted.signal() }
+        mockAPI.fetchDelay = 0.5
+        mockStats.readGate = fetchStarted
+        mockStats.statsToReturn = TokenStats(todayTokens: 5000, weekTokens: 25000, todayMessages: 10, weekMessages: 50)
+
+        await manager.refresh()
+
+        XCTAssertEqual(mockStats.readGateOpened, true, "Stats read should overlap the usage request")
+        XCTAssertLessThan(try XCTUnwrap(mockStats.readFinishedAt), try XCTUnwrap(mockAPI.fetchFinishedAt))
+        XCTAssertEqual(manager.snapshot?.tokenStats.todayTokens, 5000)
+    }
+
+    @MainActor
+    func testPauseStopsScheduleAndResumeRefreshesImmediately() async throws {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        manager.startTimer(interval: 300)
+        XCTAssertNotNil(manager.nextScheduledRefresh)
+
+        manager.pauseRefresh()
+        XCTAssertTrue(manager.isRefreshPaused)
+        XCTAssertNil(manager.nextScheduledRefresh)
+
+        manager.startTimer(interval: 600)
+        XCTAssertNil(manager.nextScheduledRefresh, "Changing the interval while paused doesn't resume")
+
+        await manager.resumeRefresh()
+        XCTAssertFalse(manager.isRefreshPaused)
+        XCTAssertEqual(mockAPI.tokensUsed.count, 1)
+        let next = try XCTUnwrap(manager.nextScheduledRefresh)
+        XCTAssertGreaterThan(next.timeIntervalSinceNow, 500, "Resumes with the interval set while paused")
+        manager.stopTimer()
+    }
+
+    @MainActor
+    func testEcoModeStretchesIntervalWhileHiddenAndRefreshesOnShow() async throws {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        defaults.set(true, forKey: AppSettings.Key.ecoMode)
+        manager.startTimer(interval: 300)
+
+        XCTAssertEqual(manager.refreshCadence, .reduced)
+        XCTAssertGreaterThan(try XCTUnwrap(manager.nextScheduledRefresh).timeIntervalSinceNow, 1400)
+
+        await manager.setPopoverVisible(true)
+        XCTAssertEqual(manager.refreshCadence, .normal)
+        XCTAssertLessThanOrEqual(try XCTUnwrap(manager.nextScheduledRefresh).timeIntervalSinceNow, 300)
+        XCTAssertEqual(mockAPI.tokensUsed.count, 1, "Showing the popover after a slowed stretch refreshes at once")
+
+        lowPowerMode = true
+        manager.updateRefreshCadence()
+        XCTAssertEqual(manager.refreshCadence, .reduced, "Low Power Mode slows refreshes even while visible")
+        manager.stopTimer()
+    }
+
+    @MainActor
+    func testEcoModeOffKeepsNormalCadence() async {
+        manager.startTimer(interval: 300)
+        await manager.setPopoverVisible(false)
+        lowPowerMode = true
+        manager.updateRefreshCadence()
+
+        XCTAssertEqual(manager.refreshCadence, .normal)
+        manager.stopTimer()
+    }
+
+    @MainActor
+    func testWakeRefreshesOnceForRapidWakeEvents() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        let woke = Date()
+
+        let first = await manager.handleWake(now: woke)
+        let second = await manager.handleWake(now: woke.addingTimeInterval(5))
+        let later = await manager.handleWake(now: woke.addingTimeInterval(UsageManager.wakeDebounce + 1))
+
+        XCTAssertTrue(first)
+        XCTAssertFalse(second)
+        XCTAssertTrue(later)
+        XCTAssertEqual(mockAPI.tokensUsed.count, 2)
+    }
+
+    @MainActor
+    func testWakeRetriesCredentialReadThatFailedBeforeSleep() async {
+        mockKeychain.errorToThrow = KeychainError.needsPermission
+        await manager.refresh()
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "Timer refreshes wait out the credential backoff")
+
+        mockKeychain.errorToThrow = nil
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.handleWake()
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
+        XCTAssertNil(manager.snapshot?.error)
+    }
+
+    @MainActor
+    func testRefreshStartsStatsWatcherOnceDirectoryExists() async throws {
+        let directory = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        defer { try? FileManager.default.removeItem(at: directory) }
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        manager.startWatchingStats(fileURL: directory.appendingPathComponent("stats-cache.json"))
+        XCTAssertFalse(manager.isWatchingStats)
+
+        try FileManager.default.createDirectory(at: directory, withIntermediateDirectories: true)
+        await manager.refresh()
+
+        XCTAssertTrue(manager.isWatchingStats)
+        manager.stopWatchingStats()
+    }
+
+    @MainActor
+    func testDiagnosticsPassWhenEverythingIsAvailable() async throws {
+        let directory = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        try FileManager.default.createDirectory(at: directory, withIntermediateDirectories: true)
+        defer { try? FileManager.default.removeItem(at: directory) }
+        let statsFile = directory.appendingPathComponent("stats-cache.json")
+        try Data(#"{"dailyModelTokens":[{"date":"2026-01-01","tokensByModel":{"opus":10}}]}"#.utf8).write(to: statsFile)
+        mockKeychain.tokenToReturn = "sk-ant-secret"
+        mockAPI.reachabilityStatus = 404
+
+        let report = await manager.runDiagnostics(homeDirectory: directory, statsFileURL: statsFile)
+
+        XCTAssertTrue(report.allPassed, report.summary)
+        XCTAssertEqual(report.checks.map(\.name), [
+            Diagnostics.homeDirectoryCheck,
+            Diagnostics.statsCacheCheck,
+            Diagnostics.credentialsCheck,
+            Diagnostics.apiReachabilityCheck,
+        ])
+        XCTAssertFalse(report.summary.contains("sk-ant-secret"))
+        XCTAssertNil(manager.snapshot, "Diagnostics don't refresh usage")
+    }
+
+    @MainActor
+    func testDiagnosticsCheckTheConfiguredStatsCache() async {
+        let statsFile = FileManager.default.temporaryDirectory.appendingPathComponent("\(UUID().uuidString)/stats-cache.json")
+        defaults.set(statsFile.path, forKey: AppSettings.Key.statsCachePath)
+
+        let report = await manager.runDiagnostics()
+
+        let statsCheck = report.checks.first { $0.name == Diagnostics.statsCacheCheck }
+        XCTAssertEqual(statsCheck?.message, "Not found at \(statsFile.path)")
+    }
+
+    @MainActor
+    func testCheckCredentialsReadsWithoutCallingTheAPI() async {
+        mockKeychain.tokenToReturn = "sk-ant-secret"
+
+        let check = await manager.checkCredentials()
+
+        XCTAssertTrue(check.passed)
+        XCTAssertEqual(check.message, "Token found in Keychain, 13 characters")
+        XCTAssertTrue(mockAPI.tokensUsed.isEmpty)
+        XCTAssertNil(manager.snapshot)
+
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2, "The checked token isn't cached for refreshes")
+
+        mockKeychain.errorToThrow = KeychainError.notFound
+        let failed = await manager.checkCredentials()
+        XCTAssertFalse(failed.passed)
+    }
+
+    @MainActor
+    func testDiagnosticsReportEachFailure() async {
+        let missing = FileManager.default.temporaryDirectory.appendingPathComponent(UUID().uuidString)
+        mockKeychain.errorToThrow = KeychainError.notFound
+        mockAPI.reachabilityErrorToThrow = APIError.networkError("offline")
+
+        let report = await manager.runDiagnostics(
+            homeDirectory: missing,
+            statsFileURL: missing.appendingPathComponent("stats-cache.json")
+        )
+
+        XCTAssertFalse(report.allPassed)
+        XCTAssertEqual(report.checks.filter(\.passed), [])
+    }
+
+    @MainActor
+    func testDiagnosticsFlagUnparseableStatsCache() throws {
+        let file = FileManager.default.temporaryDirectory.appendingPathComponent("\(UUID().uuidString).json")
+        try Data("not json".utf8).write(to: file)
+        defer { try? FileManager.default.removeItem(at: file) }
+
+        let check = Diagnostics.checkStatsCache(at: file)
+
+        XCTAssertFalse(check.passed)
+        XCTAssertTrue(check.message.hasPrefix("Unreadable"))
+    }
+
+    @MainActor
+    func testManualRefreshRestartsSchedule() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        manager.startTimer(interval: 300)
+        let scheduled = manager.nextScheduledRefresh
+
+        try? await Task.sleep(nanoseconds: 50_000_000)
+        await manager.refresh(userInitiated: true)
+
+        XCTAssertGreaterThan(manager.nextScheduledRefresh ?? .distantPast, scheduled ?? .distantFuture)
+        manager.stopTimer()
+    }
+
     @MainActor
     func testFetchSuccessUpdatesSnapshot() async {
         mockKeychain.tokenToReturn = "test-token"
@@ -115,6 +362,84 @@ final class UsageManagerTests: XCTestCase {
         XCTAssertEqual(mockReloader.reloadCount, 1, "Widget should be reloaded on successful fetch")
     }
 
+    @MainActor
+    func testPlanIsFetchedOncePerToken() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        mockAPI.planToReturn = PlanInfo(name: "Max 5x", organization: "Acme")
+
+        await manager.refresh()
+        await manager.refresh()
+
+        XCTAssertEqual(manager.snapshot?.plan, PlanInfo(name: "Max 5x", organization: "Acme"))
+        XCTAssertEqual(mockAPI.profileTokensUsed, ["test-token"])
+
+        mockKeychain.tokenToReturn = "work-token"
+        await manager.selectAccount("work")
+        XCTAssertEqual(mockAPI.profileTokensUsed, ["test-token", "work-token"])
+    }
+
+    @MainActor
+    func testProfileFailureDoesNotFailRefresh() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 45.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+        mockAPI.profileErrorToThrow = APIError.serverError(502)
+
+        await manager.refresh()
+        XCTAssertNil(manager.snapshot?.error)
+        XCTAssertNil(manager.snapshot?.plan)
+        XCTAssertEqual(manager.snapshot?.fiveHour?.percent, 45.0)
+
+        mockAPI.profileErrorToThrow = nil
+        mockAPI.planToReturn = PlanInfo(name: "Pro", organization: nil)
+        await manager.refresh()
+        XCTAssertEqual(manager.snapshot?.plan?.name, "Pro", "Failed lookups are retried on the next refresh")
+    }
+
+    @MainActor
+    func testRefreshAndApplyCarryStatusThresholdsInSnapshot() async throws {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 80.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+        try AppSettings(defaults: defaults).setStatusThresholds(warning: 60, critical: 85)
+
+        await manager.refresh()
+        XCTAssertEqual(manager.snapshot?.statusThresholds, UsageStatusThresholds(warning: 60, critical: 85))
+        let fiveHour = try XCTUnwrap(manager.snapshot?.fiveHour)
+        XCTAssertEqual(manager.snapshot?.status(of: fiveHour), .warning)
+
+        try AppSettings(defaults: defaults).setStatusThresholds(warning: 50, critical: 75)
+        manager.applyStatusThresholds()
+        XCTAssertEqual(manager.snapshot?.status(of: fiveHour), .critical)
+        XCTAssertEqual(mockContainer.storedSnapshot?.statusThresholds?.critical, 75, "The widget sees the new thresholds")
+    }
+
+    @MainActor
+    func testDemoModeSkipsCredentialsAndMarksSnapshot() async {
+        defaults.set(true, forKey: AppSettings.Key.demoMode)
+
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 0)
+        XCTAssertTrue(mockAPI.tokensUsed.isEmpty)
+        XCTAssertEqual(manager.snapshot?.isDemo, true)
+        XCTAssertNil(manager.snapshot?.error)
+        XCTAssertNotNil(manager.snapshot?.fiveHour)
+        XCTAssertEqual(mockContainer.storedSnapshot?.isDemo, true, "The widget marks demo data too")
+        XCTAssertTrue(mockNotifier.posted.isEmpty)
+
+        defaults.set(false, forKey: AppSettings.Key.demoMode)
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.serverError(500)
+        await manager.refresh()
+        XCTAssertNil(manager.snapshot?.isDemo)
+        XCTAssertNil(manager.snapshot?.fiveHour, "Demo numbers aren't kept as last-known data")
+    }
+
     @MainActor
     func testFetchSuccessMergesCodexSnapshot() async {
         mockKeychain.tokenToReturn = "claude-token"
@@ -240,6 +565,15 @@ final class UsageManagerTests: XCTestCase {
         XCTAssertEqual(mockReloader.reloadCount, 0, "Widget should NOT be reloaded on keychain error")
     }
 
+    @MainActor
+    func testMissingCredentialsErrorListsSearchedLocations() async {
+        mockKeychain.errorToThrow = KeychainError.noCredentials(searched: ["Keychain item \"Claude Code-credentials\"", "/tmp/.credentials.json"])
+
+        await manager.refresh()
+
+        XCTAssertEqual(manager.snapshot?.error?.contains("Checked: Keychain item \"Claude Code-credentials\", /tmp/.credentials.json."), true)
+    }
+
     @MainActor
     func testAPIErrorSetsSnapshotErrorButKeepsStats() async {
         mockKeychain.tokenToReturn = "test-token"
@@ -260,7 +594,7 @@ final class UsageManagerTests: XCTestCase {
 
         await manager.refresh()
 
-        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "First refresh reads from keychain")
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2, "First refresh reads, then re-reads after the 401")
 
         // Second call should re-read from keychain (token cache was cleared)
         mockAPI.errorToThrow = nil
@@ -268,7 +602,7 @@ final class UsageManagerTests: XCTestCase {
 
         await manager.refresh()
 
-        XCTAssertEqual(mockKeychain.readTokenCallCount, 2, "Token cache was cleared, so keychain was re-read")
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 3, "Token cache was cleared, so keychain was re-read")
         XCTAssertNil(manager.snapshot?.error)
     }
 
@@ -293,97 +627,1216 @@ final class UsageManagerTests: XCTestCase {
     }
 
     @MainActor
-    func testContainerWriteFailureStillSetsSnapshot() async {
-        mockKeychain.tokenToReturn = "test-token"
-        mockAPI.responseToReturn = UsageApiResponse(
-            fiveHour: UsageWindow(utilization: 50.0, resetsAt: "2026-03-21T18:00:00Z"),
-            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
-        )
-        mockContainer.writeError = NSError(domain: "test", code: 1, userInfo: nil)
+    func testCredentialSourceIsPublishedAfterRead() async {
+        mockKeychain.tokenToReturn = "env-token"
+        mockKeychain.sourceToReturn = .environment
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
 
+        XCTAssertNil(manager.credentialSource)
         await manager.refresh()
 
-        // Snapshot should still be set despite write failure
-        XCTAssertNotNil(manager.snapshot)
-        XCTAssertEqual(manager.snapshot?.fiveHour?.percent, 50.0)
-        XCTAssertNil(manager.snapshot?.error)
-        // Widget should still be reloaded
-        XCTAssertEqual(mockReloader.reloadCount, 1)
+        XCTAssertEqual(manager.credentialSource, .environment)
     }
 
     @MainActor
-    func testTokenIsCachedBetweenRefreshes() async {
-        mockKeychain.tokenToReturn = "test-token"
+    func testReloadCredentialsReadsKeychainImmediately() async {
+        mockKeychain.tokenToReturn = "first-account"
         mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
-
         await manager.refresh()
-        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "First refresh reads from keychain")
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1)
 
-        await manager.refresh()
-        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "Second refresh uses cached token, no keychain read")
+        mockKeychain.tokenToReturn = "second-account"
+        let found = await manager.reloadCredentials()
+
+        XCTAssertTrue(found)
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
 
         await manager.refresh()
-        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "Third refresh still uses cached token")
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2, "Reloaded token is cached")
+        XCTAssertEqual(mockAPI.lastTokenUsed, "second-account")
     }
 
     @MainActor
-    func testIsLoadingDuringRefresh() async {
+    func testReloadCredentialsReportsMissingToken() async {
         mockKeychain.tokenToReturn = "test-token"
         mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh()
 
-        // Before refresh
-        XCTAssertFalse(manager.isLoading)
+        mockKeychain.tokenToReturn = nil
+        let found = await manager.reloadCredentials()
+
+        XCTAssertFalse(found)
+        XCTAssertNil(manager.credentialSource)
+    }
 
+    @MainActor
+    func testResetAuthStateClearsRateLimitPause() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.rateLimited(retryAfter: 600)
         await manager.refresh()
 
-        // After refresh
-        XCTAssertFalse(manager.isLoading)
+        manager.resetAuthState()
+        mockAPI.errorToThrow = nil
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh()
+
+        XCTAssertEqual(mockAPI.tokensUsed.count, 2)
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
+        XCTAssertNil(manager.snapshot?.error)
     }
 
-    // MARK: - iconTier integration
+    @MainActor
+    func testLoadAccountsPublishesKeychainAccounts() async {
+        mockKeychain.accountsToReturn = ["personal", "work"]
+
+        await manager.loadAccounts()
+
+        XCTAssertEqual(manager.availableAccounts, ["personal", "work"])
+    }
 
     @MainActor
-    func testIconTierStartsAsIdle() {
-        XCTAssertEqual(manager.iconTier, .idle)
+    func testSelectAccountClearsCacheAndRefetches() async {
+        mockKeychain.tokenToReturn = "personal-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1)
+
+        mockKeychain.tokenToReturn = "work-token"
+        await manager.selectAccount("work")
+
+        XCTAssertEqual(manager.selectedAccount, "work")
+        XCTAssertEqual(defaults.string(forKey: AppSettings.Key.selectedAccount), "work")
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
+        XCTAssertEqual(mockAPI.lastTokenUsed, "work-token")
     }
 
     @MainActor
-    func testIconTierUpdatesAfterSuccessfulRefresh() async {
+    func testApplyCredentialSettingsRereadsWithoutRelaunch() async {
+        mockKeychain.tokenToReturn = "keychain-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 30, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1)
+
+        mockKeychain.tokenToReturn = "file-token"
+        mockKeychain.sourceToReturn = .tokenFile
+        mockAPI.responseToReturn = nil
+        await manager.applyCredentialSettings()
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
+        XCTAssertEqual(mockAPI.lastTokenUsed, "file-token")
+        XCTAssertEqual(manager.credentialSource, .tokenFile)
+        XCTAssertNil(manager.snapshot?.fiveHour, "The old source's usage isn't kept after a failed fetch")
+    }
+
+    @MainActor
+    func testFetchUsageOnlySkipsStatsAndSnapshot() async throws {
         mockKeychain.tokenToReturn = "test-token"
         mockAPI.responseToReturn = UsageApiResponse(
-            fiveHour: UsageWindow(utilization: 75.0, resetsAt: "2026-03-21T18:00:00Z"),
+            fiveHour: UsageWindow(utilization: 30, resetsAt: "2026-03-21T18:00:00Z"),
             sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
         )
 
-        await manager.refresh()
+        let response = try await manager.fetchUsageOnly()
 
-        XCTAssertEqual(manager.iconTier, .high)
+        XCTAssertEqual(response.fiveHour?.utilization, 30)
+        XCTAssertNil(mockStats.lastReadOnMainThread, "Local stats aren't read")
+        XCTAssertEqual(mockAPI.profileTokensUsed, [])
+        XCTAssertNil(manager.snapshot)
     }
 
     @MainActor
-    func testIconTierIsIdleAfterError() async {
-        mockKeychain.errorToThrow = KeychainError.notFound
+    func testFetchUsageOnlyDropsRejectedToken() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.forbidden
+
+        do {
+            _ = try await manager.fetchUsageOnly()
+            XCTFail("Expected forbidden")
+        } catch {
+            XCTAssertEqual(error as? APIError, .forbidden)
+        }
+        mockAPI.errorToThrow = nil
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        _ = try? await manager.fetchUsageOnly()
 
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2, "The rejected token isn't reused")
+    }
+
+    @MainActor
+    func testSetActiveProfileSwitchesCredentials() async throws {
+        let profilesURL = FileManager.default.temporaryDirectory.appendingPathComponent("profiles-\(UUID().uuidString).json")
+        defer { try? FileManager.default.removeItem(at: profilesURL) }
+        try Data(#"[{"name": "Work", "keychainService": "Claude Code-credentials-work"}]"#.utf8).write(to: profilesURL)
+        let settings = AppSettings(defaults: defaults, profilesFileURL: profilesURL)
+        let manager = UsageManager(
+            keychainService: mockKeychain,
+            apiService: mockAPI,
+            statsService: mockStats,
+            codexAuthService: mockCodexAuth,
+            codexAPIService: mockCodexAPI,
+            codexStatsService: mockCodexStats,
+            containerService: mockContainer,
+            historyService: mockHistory,
+            notifier: mockNotifier,
+            settings: settings,
+            widgetReloader: mockReloader.reload
+        )
+        mockKeychain.tokenToReturn = "personal-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
         await manager.refresh()
 
-        XCTAssertEqual(manager.iconTier, .idle)
+        manager.loadProfiles()
+        mockKeychain.tokenToReturn = "work-token"
+        try await manager.setActiveProfile("Work")
+
+        XCTAssertEqual(manager.profiles.map(\.name), ["Work"])
+        XCTAssertEqual(manager.activeProfileName, "Work")
+        XCTAssertEqual(settings.keychainServiceName, "Claude Code-credentials-work")
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2, "The cached token is dropped")
+        XCTAssertEqual(mockAPI.lastTokenUsed, "work-token")
+
+        do {
+            try await manager.setActiveProfile("Missing")
+            XCTFail("Expected unknownProfile")
+        } catch {
+            XCTAssertEqual(error as? SettingsError, .unknownProfile("Missing"))
+        }
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
     }
 
     @MainActor
-    func testIconTierResetsToIdleAfterSuccessThenError() async {
-        mockKeychain.tokenToReturn = "test-token"
+    func testAccountSwitchDropsPreviousAccountsUsage() async {
+        mockKeychain.tokenToReturn = "personal-token"
         mockAPI.responseToReturn = UsageApiResponse(
-            fiveHour: UsageWindow(utilization: 85.0, resetsAt: "2026-03-21T18:00:00Z"),
+            fiveHour: UsageWindow(utilization: 45.0, resetsAt: "2026-03-21T18:00:00Z"),
             sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
         )
         await manager.refresh()
-        XCTAssertEqual(manager.iconTier, .high)
 
-        // Now error — but cached data is preserved, so iconTier stays based on cached usage
-        mockAPI.responseToReturn = nil
         mockAPI.errorToThrow = APIError.serverError(500)
         await manager.refresh()
-        XCTAssertEqual(manager.iconTier, .high, "iconTier should reflect cached data, not reset to idle on error")
+        XCTAssertEqual(manager.snapshot?.fiveHour?.percent, 45.0, "A failed refresh keeps last-known data")
+        XCTAssertNotNil(manager.snapshot?.staleSince)
+
+        mockKeychain.tokenToReturn = "work-token"
+        await manager.selectAccount("work")
+
+        XCTAssertNil(manager.snapshot?.fiveHour)
+        XCTAssertNil(manager.snapshot?.staleSince)
+        XCTAssertNotNil(manager.snapshot?.error)
+        XCTAssertNil(mockContainer.storedSnapshot?.fiveHour, "The widget must not keep showing the old account")
+    }
+
+    @MainActor
+    func testSelectingSameAccountDoesNotRefetch() async {
+        await manager.selectAccount(nil)
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 0)
+        XCTAssertTrue(mockAPI.tokensUsed.isEmpty)
+    }
+
+    @MainActor
+    func testUnauthorizedRenewsTokenAndRetriesOnce() async {
+        defaults.set(true, forKey: AppSettings.Key.renewRejectedTokens)
+        mockKeychain.tokenToReturn = "stale-token"
+        mockKeychain.refreshTokenToReturn = "refresh-token"
+        mockTokenRefresh.credentialsToReturn = OAuthCredentials(accessToken: "fresh-token", refreshToken: "rotated-refresh")
+        mockAPI.queuedErrors = [APIError.unauthorized]
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 33.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+
+        await manager.refresh()
+
+        XCTAssertEqual(mockTokenRefresh.refreshCallCount, 1)
+        XCTAssertEqual(mockTokenRefresh.lastRefreshTokenUsed, "refresh-token")
+        XCTAssertEqual(mockAPI.tokensUsed, ["stale-token", "fresh-token"])
+        XCTAssertEqual(manager.snapshot?.fiveHour?.percent, 33.0)
+        XCTAssertNil(manager.snapshot?.error)
+
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2, "Renewed token is cached for later refreshes")
+        XCTAssertEqual(mockAPI.lastTokenUsed, "fresh-token")
+    }
+
+    @MainActor
+    func testUnauthorizedRereadsStoreBeforeRenewing() async {
+        defaults.set(true, forKey: AppSettings.Key.renewRejectedTokens)
+        mockKeychain.tokenToReturn = "stale-token"
+        mockKeychain.refreshTokenToReturn = "refresh-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        mockAPI.onFetchUsage = { [mockKeychain] in mockKeychain?.tokenToReturn = "renewed-by-claude-code" }
+        mockAPI.queuedErrors = [APIError.unauthorized]
+
+        await manager.refresh()
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
+        XCTAssertEqual(mockAPI.tokensUsed, ["stale-token", "renewed-by-claude-code"])
+        XCTAssertEqual(mockTokenRefresh.refreshCallCount, 0, "Claude Code's refresh token isn't spent")
+        XCTAssertNil(manager.snapshot?.error)
+    }
+
+    @MainActor
+    func testRenewalIsOptInAndSavesRenewedTokens() async {
+        mockKeychain.tokenToReturn = "stale-token"
+        mockKeychain.refreshTokenToReturn = "refresh-token"
+        mockKeychain.sourceToReturn = .credentialsFile
+        mockTokenRefresh.credentialsToReturn = OAuthCredentials(accessToken: "fresh-token", refreshToken: "rotated-refresh")
+        mockAPI.queuedErrors = [APIError.unauthorized]
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        await manager.refresh()
+        XCTAssertEqual(mockTokenRefresh.refreshCallCount, 0, "Renewal is off by default")
+        XCTAssertEqual(manager.snapshot?.error, "Authentication failed. Token may have expired.")
+        XCTAssertTrue(mockKeychain.writtenCredentials.isEmpty)
+
+        defaults.set(true, forKey: AppSettings.Key.renewRejectedTokens)
+        manager.resetAuthState()
+        mockAPI.queuedErrors = [APIError.unauthorized]
+        await manager.refresh()
+        XCTAssertEqual(mockTokenRefresh.refreshCallCount, 1)
+        XCTAssertNil(manager.snapshot?.error)
+        XCTAssertEqual(mockKeychain.writtenCredentials.map(\.accessToken), ["fresh-token"])
+        XCTAssertEqual(mockKeychain.writtenCredentials.first?.refreshToken, "rotated-refresh")
+        XCTAssertEqual(mockKeychain.writtenCredentials.first?.source, .credentialsFile)
+    }
+
+    @MainActor
+    func testFailedTokenRenewalSurfacesUnauthorized() async {
+        defaults.set(true, forKey: AppSettings.Key.renewRejectedTokens)
+        mockKeychain.tokenToReturn = "stale-token"
+        mockKeychain.refreshTokenToReturn = "refresh-token"
+        mockTokenRefresh.errorToThrow = APIError.unauthorized
+        mockAPI.errorToThrow = APIError.unauthorized
+
+        await manager.refresh()
+
+        XCTAssertEqual(mockTokenRefresh.refreshCallCount, 1)
+        XCTAssertEqual(mockAPI.tokensUsed, ["stale-token"], "No retry without a renewed token")
+        XCTAssertEqual(manager.snapshot?.error, "Authentication failed. Token may have expired.")
+    }
+
+    @MainActor
+    func testUnauthorizedAfterRenewalIsNotRetriedAgain() async {
+        defaults.set(true, forKey: AppSettings.Key.renewRejectedTokens)
+        mockKeychain.tokenToReturn = "stale-token"
+        mockKeychain.refreshTokenToReturn = "refresh-token"
+        mockTokenRefresh.credentialsToReturn = OAuthCredentials(accessToken: "fresh-token")
+        mockAPI.errorToThrow = APIError.unauthorized
+
+        await manager.refresh()
+
+        XCTAssertEqual(mockTokenRefresh.refreshCallCount, 1)
+        XCTAssertEqual(mockAPI.tokensUsed, ["stale-token", "fresh-token"])
+        XCTAssertNotNil(manager.snapshot?.error)
+    }
+
+    @MainActor
+    func testUnauthorizedWithoutRefreshTokenSkipsRenewal() async {
+        defaults.set(true, forKey: AppSettings.Key.renewRejectedTokens)
+        mockKeychain.tokenToReturn = "stale-token"
+        mockAPI.errorToThrow = APIError.unauthorized
+
+        await manager.refresh()
+
+        XCTAssertEqual(mockTokenRefresh.refreshCallCount, 0)
+        XCTAssertEqual(mockAPI.tokensUsed, ["stale-token"])
+    }
+
+    @MainActor
+    func testRateLimitPausesAPICallsUntilRetryAfter() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.rateLimited(retryAfter: 300)
+
+        await manager.refresh()
+        XCTAssertEqual(manager.snapshot?.error, "Rate limited. Retrying in 5 min.")
+
+        mockAPI.errorToThrow = nil
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh()
+
+        XCTAssertEqual(mockAPI.tokensUsed.count, 1, "Second refresh waits out the Retry-After window")
+        XCTAssertNotNil(manager.snapshot?.error)
+    }
+
+    @MainActor
+    func testRateLimitWithoutRetryAfterDoesNotPause() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.rateLimited(retryAfter: nil)
+
+        await manager.refresh()
+        XCTAssertEqual(manager.snapshot?.error, "Rate limited. Will retry later.")
+
+        mockAPI.errorToThrow = nil
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh()
+
+        XCTAssertEqual(mockAPI.tokensUsed.count, 2)
+        XCTAssertNil(manager.snapshot?.error)
+    }
+
+    @MainActor
+    func testConsecutiveTransientFailuresBackOffAcrossRefreshes() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.networkError("offline")
+
+        await manager.refresh()
+        XCTAssertNil(manager.snapshot?.nextRetryAt, "A single failure retries on the next cycle")
+
+        await manager.refresh()
+        let retryAt = manager.snapshot?.nextRetryAt
+        XCTAssertNotNil(retryAt)
+        XCTAssertGreaterThanOrEqual(retryAt?.timeIntervalSinceNow ?? 0, RefreshBackoff.baseDelay - 1)
+        XCTAssertEqual(manager.snapshot?.errorKind, .network)
+
+        await manager.refresh()
+        XCTAssertEqual(mockAPI.tokensUsed.count, 2, "Third timer refresh waits out the backoff")
+        XCTAssertEqual(manager.snapshot?.error, "Network error: offline")
+        XCTAssertEqual(manager.snapshot?.nextRetryAt, retryAt)
+    }
+
+    @MainActor
+    func testUserInitiatedRefreshBypassesCycleBackoffAndSuccessResetsIt() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.serverError(503)
+        await manager.refresh()
+        await manager.refresh()
+        XCTAssertNotNil(manager.snapshot?.nextRetryAt)
+
+        mockAPI.errorToThrow = nil
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh(userInitiated: true)
+
+        XCTAssertEqual(mockAPI.tokensUsed.count, 3)
+        XCTAssertNil(manager.snapshot?.error)
+        XCTAssertNil(manager.snapshot?.nextRetryAt)
+    }
+
+    @MainActor
+    func testUserInitiatedRefreshOverridesRetryAfterWithWarning() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.rateLimited(retryAfter: 300)
+        await manager.refresh()
+        XCTAssertNotNil(manager.snapshot?.nextRetryAt)
+        XCTAssertNil(manager.manualRefreshWarning)
+
+        mockAPI.errorToThrow = nil
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh(userInitiated: true)
+
+        XCTAssertEqual(mockAPI.tokensUsed.count, 2)
+        XCTAssertNotNil(manager.manualRefreshWarning)
+        XCTAssertNil(manager.snapshot?.error)
+
+        await manager.refresh()
+        XCTAssertNil(manager.manualRefreshWarning)
+    }
+
+    @MainActor
+    func testRateLimitExposesNextRetryAtAndSuspendsTimerRefreshes() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.rateLimited(retryAfter: 120)
+
+        await manager.refresh()
+        let retryAt = manager.snapshot?.nextRetryAt
+        XCTAssertEqual(retryAt?.timeIntervalSinceNow ?? 0, 120, accuracy: 5)
+
+        await manager.refresh()
+        XCTAssertEqual(mockAPI.tokensUsed.count, 1)
+        XCTAssertEqual(manager.snapshot?.nextRetryAt, retryAt)
+        XCTAssertEqual(manager.snapshot?.errorKind, .rateLimited)
+    }
+
+    @MainActor
+    func testAuthFailuresDoNotStartCycleBackoff() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.forbidden
+        await manager.refresh()
+        await manager.refresh()
+
+        XCTAssertNil(manager.snapshot?.nextRetryAt)
+    }
+
+    @MainActor
+    func testInitPrunesHistoryToRetentionWindow() {
+        XCTAssertEqual(mockHistory.pruneCutoffs.count, 1)
+        let expected = Date().addingTimeInterval(-UsageHistoryService.defaultRetention)
+        XCTAssertEqual(mockHistory.pruneCutoffs[0].timeIntervalSince1970, expected.timeIntervalSince1970, accuracy: 5)
+    }
+
+    @MainActor
+    func testHistoryRetentionSettingControlsPrune() async {
+        defaults.set(7, forKey: AppSettings.Key.historyRetentionDays)
+        let history = MockUsageHistoryService()
+
+        let manager = UsageManager(
+            keychainService: mockKeychain,
+            apiService: mockAPI,
+            statsService: mockStats,
+            codexAuthService: mockCodexAuth,
+            codexAPIService: mockCodexAPI,
+            codexStatsService: mockCodexStats,
+            containerService: mockContainer,
+            historyService: history,
+            notifier: mockNotifier,
+            settings: AppSettings(defaults: defaults),
+            widgetReloader: mockReloader.reload
+        )
+        await manager.launchHistoryMaintenance?.value
+
+        let expected = Date().addingTimeInterval(-7 * 86400)
+        XCTAssertEqual(history.pruneCutoffs.first?.timeIntervalSince1970 ?? 0, expected.timeIntervalSince1970, accuracy: 5)
+    }
+
+    @MainActor
+    func testDailyPeaksRollUpAtLaunchAndOncePerDay() async {
+        XCTAssertEqual(mockHistory.rollUpDates.count, 1)
+
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 40.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+        await manager.refresh()
+
+        XCTAssertEqual(mockHistory.rollUpDates.count, 1, "Same day: nothing new to roll up")
+    }
+
+    @MainActor
+    func testUsageSeriesQueriesTrailingWindow() async {
+        let point = UsageHistoryPoint(
+            recordedAt: Date(timeIntervalSince1970: 1_790_000_000),
+            fiveHourPercent: 50, sevenDayPercent: 20, sevenDaySonnetPercent: nil, sevenDayOpusPercent: nil,
+            todayTokens: 100
+        )
+        mockHistory.seriesToReturn = [point]
+
+        let series = await manager.usageSeries(window: 3 * 86400, resolution: 1800)
+
+        XCTAssertEqual(series, [point])
+        let request = mockHistory.seriesRequests.first
+        XCTAssertEqual(request?.resolution, 1800)
+        XCTAssertEqual((request?.end.timeIntervalSince(request!.start)) ?? 0, 3 * 86400, accuracy: 0.001)
+    }
+
+    @MainActor
+    func testSuccessfulRefreshIsRecordedInHistory() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 40.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+
+        await manager.refresh()
+
+        XCTAssertEqual(mockHistory.recorded.count, 1)
+        XCTAssertEqual(mockHistory.recorded.first?.fiveHour?.percent, 40.0)
+
+        let history = await manager.usageHistory(from: .distantPast, to: .distantFuture)
+        XCTAssertEqual(history.count, 1)
+    }
+
+    @MainActor
+    func testRefreshTokenStatsUpdatesSnapshotWithoutCallingAPI() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 40.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+        await manager.refresh()
+        let reloadsBefore = mockReloader.reloadCount
+        let fresh = TokenStats(todayTokens: 4200, weekTokens: 9000, todayMessages: 3, weekMessages: 12)
+        mockStats.statsToReturn = fresh
+
+        await manager.refreshTokenStats()
+
+        XCTAssertEqual(manager.snapshot?.tokenStats, fresh)
+        XCTAssertEqual(manager.snapshot?.fiveHour?.percent, 40.0)
+        XCTAssertEqual(mockContainer.storedSnapshot?.tokenStats, fresh)
+        XCTAssertEqual(mockReloader.reloadCount, reloadsBefore + 1)
+        XCTAssertEqual(mockAPI.tokensUsed.count, 1)
+    }
+
+    @MainActor
+    func testRefreshTokenStatsSkipsUnchangedStats() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh()
+        let reloadsBefore = mockReloader.reloadCount
+
+        await manager.refreshTokenStats()
+
+        XCTAssertEqual(mockReloader.reloadCount, reloadsBefore)
+    }
+
+    @MainActor
+    func testRefreshAgainstConfiguredGatewayEndToEnd() async throws {
+        let settings = AppSettings(defaults: defaults)
+        let config = URLSessionConfiguration.ephemeral
+        config.protocolClasses = [MockURLProtocol.self]
+        let gatewayManager = UsageManager(
+            keychainService: mockKeychain,
+            apiService: APIService(session: URLSession(configuration: config), baseURL: { try settings.apiBaseURL }, retryPolicy: { .none }),
+            statsService: mockStats,
+            codexAuthService: mockCodexAuth,
+            codexAPIService: mockCodexAPI,
+            codexStatsService: mockCodexStats,
+            containerService: mockContainer,
+            historyService: mockHistory,
+            notifier: mockNotifier,
+            settings: settings,
+            widgetReloader: mockReloader.reload
+        )
+        mockKeychain.tokenToReturn = "gateway-token"
+        var requestedURL: URL?
+        var authorization: String?
+        MockURLProtocol.requestHandler = { request in
+            requestedURL = request.url
+            authorization = request.value(forHTTPHeaderField: "Authorization")
+            let response = HTTPURLResponse(url: request.url!, statusCode: 200, httpVersion: nil, headerFields: nil)!
+            return (response, #"{"five_hour": {"utilization": 33.0, "resets_at": "2026-03-21T18:00:00Z"}}"#.data(using: .utf8)!)
+        }
+
+        try gatewayManager.applyAPIBaseURL("https://gateway.corp/anthropic")
+        await gatewayManager.refresh()
+
+        XCTAssertEqual(requestedURL?.absoluteString, "https://gateway.corp/anthropic/api/oauth/usage")
+        XCTAssertEqual(authorization, "Bearer gateway-token")
+        XCTAssertEqual(gatewayManager.snapshot?.fiveHour?.percent, 33.0)
+        XCTAssertNil(gatewayManager.snapshot?.error)
+    }
+
+    @MainActor
+    func testApplyProxyRejectsInvalidURLWithoutStoringIt() {
+        XCTAssertThrowsError(try manager.applyProxy("not a proxy"))
+        XCTAssertNil(AppSettings(defaults: defaults).proxyURL)
+
+        XCTAssertNoThrow(try manager.applyProxy(""))
+    }
+
+    @MainActor
+    func testTokenHistoryReadsFromClaudeStats() async {
+        mockStats.historyToReturn = [DailyTokens(date: "2026-03-01", tokensByModel: ["claude-sonnet": 5])]
+
+        let history = await manager.tokenHistory(days: 14)
+
+        XCTAssertEqual(history, mockStats.historyToReturn)
+        XCTAssertEqual(mockStats.requestedHistoryDays, [14])
+        XCTAssertTrue(mockCodexStats.requestedHistoryDays.isEmpty)
+    }
+
+    @MainActor
+    func testFailedRefreshIsNotRecordedInHistory() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.serverError(500)
+
+        await manager.refresh()
+
+        XCTAssertTrue(mockHistory.recorded.isEmpty)
+    }
+
+    @MainActor
+    func testHistoryWriteFailureDoesNotAffectSnapshot() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockHistory.errorToThrow = UsageHistoryError.database("disk I/O error")
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 40.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+
+        await manager.refresh()
+
+        XCTAssertEqual(manager.snapshot?.fiveHour?.percent, 40.0)
+        XCTAssertNil(manager.snapshot?.error)
+    }
+
+    @MainActor
+    func testThresholdNotificationFiresOnRisingEdgeOnly() async {
+        defaults.set(80, forKey: AppSettings.Key.notifyThresholdPercent)
+        mockKeychain.tokenToReturn = "test-token"
+
+        for percent in [70.0, 85.0, 90.0] {
+            mockAPI.responseToReturn = UsageApiResponse(
+                fiveHour: UsageWindow(utilization: percent, resetsAt: "2026-03-21T18:00:00Z"),
+                sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+            )
+            await manager.refresh()
+        }
+
+        XCTAssertEqual(mockNotifier.posted.count, 1)
+        XCTAssertEqual(mockNotifier.posted.first?.title, "Claude usage at 85%")
+        XCTAssertTrue(mockNotifier.posted.first?.body.contains("passed 80%") ?? false)
+    }
+
+    @MainActor
+    func testThresholdNotificationRearmsAfterDroppingBelow() async {
+        defaults.set(80, forKey: AppSettings.Key.notifyThresholdPercent)
+        mockKeychain.tokenToReturn = "test-token"
+
+        for percent in [85.0, 5.0, 82.0] {
+            mockAPI.responseToReturn = UsageApiResponse(
+                fiveHour: UsageWindow(utilization: percent, resetsAt: "2026-03-21T18:00:00Z"),
+                sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+            )
+            await manager.refresh()
+        }
+
+        XCTAssertEqual(mockNotifier.posted.count, 2)
+    }
+
+    @MainActor
+    func testNoThresholdNotificationWhenDisabled() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 99.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+
+        await manager.refresh()
+
+        XCTAssertTrue(mockNotifier.posted.isEmpty)
+    }
+
+    @MainActor
+    func testEachThresholdAlertsOncePerResetCycle() async throws {
+        try AppSettings(defaults: defaults).setNotifyThresholds([95, 80])
+        mockKeychain.tokenToReturn = "test-token"
+
+        for percent in [70.0, 85.0, 90.0, 96.0, 99.0] {
+            mockAPI.responseToReturn = UsageApiResponse(
+                fiveHour: UsageWindow(utilization: percent, resetsAt: "2026-03-21T18:00:00Z"),
+                sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+            )
+            await manager.refresh()
+        }
+
+        XCTAssertEqual(mockNotifier.posted.map(\.title), ["Claude usage at 85%", "Claude usage at 96%"])
+        XCTAssertTrue(mockNotifier.posted.last?.body.contains("passed 95%") ?? false)
+    }
+
+    @MainActor
+    func testNewResetTimeRearmsThresholds() async {
+        defaults.set(80, forKey: AppSettings.Key.notifyThresholdPercent)
+        mockKeychain.tokenToReturn = "test-token"
+
+        for resetsAt in ["2026-03-21T18:00:00Z", "2026-03-21T23:00:00Z"] {
+            mockAPI.responseToReturn = UsageApiResponse(
+                fiveHour: UsageWindow(utilization: 90.0, resetsAt: resetsAt),
+                sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+            )
+            await manager.refresh()
+        }
+
+        XCTAssertEqual(mockNotifier.posted.count, 2)
+    }
+
+    @MainActor
+    func testThresholdAlertsOnlyForSelectedWindows() async {
+        defaults.set(80, forKey: AppSettings.Key.notifyThresholdPercent)
+        AppSettings(defaults: defaults).setNotifyWindows([.sevenDay])
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 90.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: UsageWindow(utilization: 85.0, resetsAt: "2026-03-25T18:00:00Z"),
+            sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+
+        await manager.refresh()
+
+        XCTAssertEqual(mockNotifier.posted.count, 1)
+        XCTAssertEqual(mockNotifier.posted.first?.identifier, "threshold-sevenDay")
+        XCTAssertTrue(mockNotifier.posted.first?.body.contains("weekly limit passed 80%") ?? false)
+    }
+
+    @MainActor
+    func testResetNotificationWhenResetTimeMovesForward() async {
+        AppSettings(defaults: defaults).setNotifyResetWindows([.fiveHour])
+        mockKeychain.tokenToReturn = "test-token"
+
+        for (percent, resetsAt) in [(90.0, "2026-03-21T18:00:00Z"), (2.0, "2026-03-21T23:00:00Z"), (4.0, "2026-03-21T23:00:00Z")] {
+            mockAPI.responseToReturn = UsageApiResponse(
+                fiveHour: UsageWindow(utilization: percent, resetsAt: resetsAt),
+                sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+            )
+            await manager.refresh()
+        }
+
+        XCTAssertEqual(mockNotifier.posted.map(\.title), ["Your 5-hour limit has reset"])
+        XCTAssertEqual(mockNotifier.posted.first?.identifier, "reset-fiveHour")
+    }
+
+    @MainActor
+    func testResetWhileNotRunningAlertsOnFirstRefresh() async {
+        AppSettings(defaults: defaults).setNotifyResetWindows([.fiveHour])
+        mockContainer.storedSnapshot = UsageSnapshot(
+            fiveHour: UsageMetric(percent: 95, resetsAt: Date(timeIntervalSinceNow: -3600)),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil, codex: nil,
+            tokenStats: .zero, lastUpdated: Date(), lastSuccessfulUpdate: Date(), error: nil
+        )
+        let manager = UsageManager(
+            keychainService: mockKeychain,
+            apiService: mockAPI,
+            statsService: mockStats,
+            codexAuthService: mockCodexAuth,
+            codexAPIService: mockCodexAPI,
+            codexStatsService: mockCodexStats,
+            containerService: mockContainer,
+            historyService: mockHistory,
+            notifier: mockNotifier,
+            settings: AppSettings(defaults: defaults),
+            widgetReloader: mockReloader.reload
+        )
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 1.0, resetsAt: "2026-03-21T23:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+
+        await manager.refresh()
+
+        XCTAssertEqual(mockNotifier.posted.map(\.title), ["Your 5-hour limit has reset"])
+    }
+
+    func testResetDetectionIsDebounced() {
+        let start = Date(timeIntervalSince1970: 1_000_000)
+        var state = UsageAlertState(resetsAt: start)
+
+        XCTAssertFalse(state.detectReset(metric: UsageMetric(percent: 90, resetsAt: start), now: start))
+        XCTAssertTrue(state.detectReset(metric: UsageMetric(percent: 1, resetsAt: start.addingTimeInterval(18000)), now: start))
+        _ = state.update(metric: UsageMetric(percent: 1, resetsAt: start.addingTimeInterval(18000)), thresholds: [])
+        _ = state.update(metric: UsageMetric(percent: 1, resetsAt: start), thresholds: [])
+        XCTAssertFalse(state.detectReset(metric: UsageMetric(percent: 1, resetsAt: start.addingTimeInterval(18000)), now: start.addingTimeInterval(60)))
+    }
+
+    /// Quiet hours from an hour ago to an hour from now, local time.
+    private func setQuietHoursAroundNow() throws {
+        let components = Calendar.current.dateComponents([.hour, .minute], from: Date())
+        let minute = (components.hour ?? 0) * 60 + (components.minute ?? 0)
+        func format(_ value: Int) -> String {
+            let wrapped = (value + 1440) % 1440
+            return String(format: "%02d:%02d", wrapped / 60, wrapped % 60)
+        }
+        try AppSettings(defaults: defaults).setQuietHours(start: format(minute - 60), end: format(minute + 60))
+    }
+
+    @MainActor
+    func testThresholdAlertHeldDuringQuietHoursIsDeliveredAfter() async throws {
+        defaults.set(80, forKey: AppSettings.Key.notifyThresholdPercent)
+        try setQuietHoursAroundNow()
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 85.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+
+        await manager.refresh()
+        XCTAssertTrue(mockNotifier.posted.isEmpty)
+
+        try AppSettings(defaults: defaults).setQuietHours(start: "", end: "")
+        await manager.refresh()
+        await manager.refresh()
+
+        XCTAssertEqual(mockNotifier.posted.count, 1)
+        XCTAssertTrue(mockNotifier.posted.first?.body.contains("passed 80%") ?? false)
+    }
+
+    @MainActor
+    func testHeldThresholdAlertDroppedWhenUsageFell() async throws {
+        defaults.set(80, forKey: AppSettings.Key.notifyThresholdPercent)
+        try setQuietHoursAroundNow()
+        mockKeychain.tokenToReturn = "test-token"
+
+        for (percent, quiet) in [(85.0, true), (40.0, false)] {
+            if !quiet {
+                try AppSettings(defaults: defaults).setQuietHours(start: "", end: "")
+            }
+            mockAPI.responseToReturn = UsageApiResponse(
+                fiveHour: UsageWindow(utilization: percent, resetsAt: "2026-03-21T18:00:00Z"),
+                sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+            )
+            await manager.refresh()
+        }
+
+        XCTAssertTrue(mockNotifier.posted.isEmpty)
+    }
+
+    @MainActor
+    func testSendTestNotification() {
+        manager.sendTestNotification()
+
+        XCTAssertEqual(mockNotifier.posted.count, 1)
+        XCTAssertEqual(mockNotifier.posted.first?.identifier, "threshold-test")
+    }
+
+    func testUsageAlertStateRearmsBelowMargin() {
+        let resetsAt = Date(timeIntervalSince1970: 1_000_000)
+        func metric(_ percent: Double) -> UsageMetric {
+            UsageMetric(percent: percent, resetsAt: resetsAt)
+        }
+        var state = UsageAlertState(resetsAt: resetsAt)
+
+        XCTAssertEqual(state.update(metric: metric(80), thresholds: [80]), [80])
+        XCTAssertEqual(state.update(metric: metric(95), thresholds: [80]), [])
+        XCTAssertEqual(state.update(metric: metric(77), thresholds: [80]), [])
+        XCTAssertEqual(state.update(metric: metric(81), thresholds: [80]), [])
+        XCTAssertEqual(state.update(metric: metric(74), thresholds: [80]), [])
+        XCTAssertEqual(state.update(metric: metric(81), thresholds: [80]), [80])
+    }
+
+    @MainActor
+    func testFailedCredentialReadBacksOffOnTimerRefreshes() async {
+        mockKeychain.errorToThrow = KeychainError.accessDenied
+
+        await manager.refresh()
+        await manager.refresh()
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "Keychain isn't retried within the backoff window")
+        XCTAssertEqual(manager.snapshot?.errorKind, .auth)
+        XCTAssertEqual(manager.snapshot?.error, "Keychain access denied. Click Grant access to be asked again.")
+    }
+
+    @MainActor
+    func testUserInitiatedRefreshBypassesCredentialBackoff() async {
+        mockKeychain.errorToThrow = KeychainError.notFound
+        await manager.refresh()
+
+        mockKeychain.errorToThrow = nil
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        await manager.refresh(userInitiated: true)
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
+        XCTAssertNil(manager.snapshot?.error)
+        XCTAssertNil(manager.snapshot?.errorKind)
+    }
+
+    @MainActor
+    func testCredentialBackoffExpires() async {
+        let shortBackoff = UsageManager(
+            keychainService: mockKeychain,
+            apiService: mockAPI,
+            tokenRefreshService: mockTokenRefresh,
+            statsService: mockStats,
+            codexAuthService: mockCodexAuth,
+            codexAPIService: mockCodexAPI,
+            codexStatsService: mockCodexStats,
+            containerService: mockContainer,
+            historyService: mockHistory,
+            notifier: mockNotifier,
+            settings: AppSettings(defaults: defaults),
+            widgetReloader: mockReloader.reload,
+            credentialRetryBackoff: 0
+        )
+        mockKeychain.errorToThrow = KeychainError.notFound
+
+        await shortBackoff.refresh()
+        await shortBackoff.refresh()
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
+    }
+
+    @MainActor
+    func testKeychainDenialStopsAutomaticReads() async {
+        mockKeychain.errorToThrow = KeychainError.accessDenied
+
+        await manager.refresh()
+        XCTAssertTrue(manager.keychainDenied)
+
+        await manager.refresh(userInitiated: true)
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "Denied Keychain isn't re-prompted by refreshes")
+        XCTAssertEqual(manager.snapshot?.errorKind, .auth)
+    }
+
+    @MainActor
+    func testRetryKeychainAccessClearsDenialAndRefetches() async {
+        mockKeychain.errorToThrow = KeychainError.accessDenied
+        await manager.refresh()
+
+        mockKeychain.errorToThrow = nil
+        mockKeychain.tokenToReturn = "granted-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 10.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+        let found = await manager.retryKeychainAccess()
+
+        XCTAssertTrue(found)
+        XCTAssertFalse(manager.keychainDenied)
+        XCTAssertEqual(mockAPI.lastTokenUsed, "granted-token")
+        XCTAssertNil(manager.snapshot?.error)
+    }
+
+    @MainActor
+    func testNeedsPermissionDoesNotMarkDenied() async {
+        mockKeychain.errorToThrow = KeychainError.needsPermission
+
+        await manager.refresh()
+
+        XCTAssertFalse(manager.keychainDenied)
+        XCTAssertEqual(manager.snapshot?.error, "Keychain access needs permission. Unlock your Mac and click Grant access.")
+    }
+
+    @MainActor
+    func testCodexErrorKindIsRecorded() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        mockContainer.storedSnapshot = UsageSnapshot(
+            fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil,
+            codex: ProviderUsageSnapshot(
+                fiveHour: UsageMetric(percent: 10, resetsAt: Date()),
+                sevenDay: nil, extraLabel: nil, extraMetric: nil,
+                tokenStats: .zero, lastUpdated: Date(), lastSuccessfulUpdate: Date(), error: nil
+            ),
+            tokenStats: .zero, lastUpdated: Date(), lastSuccessfulUpdate: Date(), error: nil
+        )
+        mockCodexAuth.credentialsToReturn = CodexAuthCredentials(accessToken: "codex-token", accountID: "account-123")
+        mockCodexAPI.errorToThrow = APIError.serverError(503)
+
+        await manager.refresh()
+
+        XCTAssertEqual(manager.snapshot?.codex?.errorKind, .server)
+        XCTAssertNil(manager.snapshot?.errorKind)
+    }
+
+    @MainActor
+    func testAPIErrorKindIsRecorded() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.errorToThrow = APIError.networkError("offline")
+
+        await manager.refresh()
+
+        XCTAssertEqual(manager.snapshot?.errorKind, .network)
+    }
+
+    @MainActor
+    func testContainerWriteFailureStillSetsSnapshot() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 50.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+        mockContainer.writeError = NSError(domain: "test", code: 1, userInfo: nil)
+
+        await manager.refresh()
+
+        // Snapshot should still be set despite write failure
+        XCTAssertNotNil(manager.snapshot)
+        XCTAssertEqual(manager.snapshot?.fiveHour?.percent, 50.0)
+        XCTAssertNil(manager.snapshot?.error)
+        // Widget should still be reloaded
+        XCTAssertEqual(mockReloader.reloadCount, 1)
+    }
+
+    @MainActor
+    func testTokenIsCachedBetweenRefreshes() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "First refresh reads from keychain")
+
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "Second refresh uses cached token, no keychain read")
+
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "Third refresh still uses cached token")
+    }
+
+    @MainActor
+    func testTokenIsReReadOnceItsTTLPasses() async {
+        defaults.set(1, forKey: AppSettings.Key.tokenTTLSeconds)
+        mockKeychain.tokenToReturn = "old-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        await manager.refresh()
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1, "Within the TTL the cached token is used")
+
+        mockKeychain.tokenToReturn = "new-token"
+        now = now.addingTimeInterval(1)
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2)
+        XCTAssertEqual(mockAPI.lastTokenUsed, "new-token")
+    }
+
+    @MainActor
+    func testUnsavedRenewalOutlivesTheTTL() async {
+        defaults.set(1, forKey: AppSettings.Key.tokenTTLSeconds)
+        defaults.set(true, forKey: AppSettings.Key.renewRejectedTokens)
+        mockKeychain.writeErrorToThrow = KeychainError.needsPermission
+        mockKeychain.tokenToReturn = "stale-token"
+        mockKeychain.refreshTokenToReturn = "refresh-token"
+        mockTokenRefresh.credentialsToReturn = OAuthCredentials(accessToken: "fresh-token", refreshToken: "rotated-refresh")
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+        mockAPI.queuedErrors = [APIError.unauthorized]
+        await manager.refresh()
+        XCTAssertEqual(mockAPI.lastTokenUsed, "fresh-token")
+
+        now = now.addingTimeInterval(1)
+        mockTokenRefresh.credentialsToReturn = OAuthCredentials(accessToken: "fresher-token", refreshToken: "rotated-again")
+        mockAPI.queuedErrors = [APIError.unauthorized]
+        await manager.refresh()
+
+        XCTAssertEqual(mockTokenRefresh.lastRefreshTokenUsed, "rotated-refresh", "The store's spent refresh token isn't reused")
+        XCTAssertEqual(mockAPI.tokensUsed, ["stale-token", "fresh-token", "fresh-token", "fresher-token"])
+        XCTAssertNil(manager.snapshot?.error)
+
+        mockAPI.queuedErrors = [APIError.unauthorized]
+        await manager.refresh()
+        XCTAssertEqual(mockTokenRefresh.lastRefreshTokenUsed, "rotated-again")
+
+        mockKeychain.tokenToReturn = "signed-in-again"
+        mockKeychain.refreshTokenToReturn = "new-login-refresh"
+        mockAPI.queuedErrors = [APIError.unauthorized]
+        await manager.refresh()
+        XCTAssertEqual(mockAPI.lastTokenUsed, "signed-in-again", "A new login in the store replaces the renewal")
+    }
+
+    @MainActor
+    func testZeroTokenTTLKeepsTokenUntilItExpires() async throws {
+        defaults.set(0, forKey: AppSettings.Key.tokenTTLSeconds)
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        await manager.refresh()
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1)
+        XCTAssertEqual(AppSettings(defaults: defaults).tokenTTL, 0)
+    }
+
+    @MainActor
+    func testExpiredCachedTokenIsReReadBeforeAPICall() async {
+        mockKeychain.tokenToReturn = "old-token"
+        mockKeychain.expiresAtToReturn = Date().addingTimeInterval(-10)
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        await manager.refresh()
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1)
+
+        mockKeychain.tokenToReturn = "new-token"
+        mockKeychain.expiresAtToReturn = Date().addingTimeInterval(3600)
+        await manager.refresh()
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2, "Expired token should trigger a keychain re-read")
+        XCTAssertEqual(mockAPI.lastTokenUsed, "new-token")
+    }
+
+    @MainActor
+    func testTokenExpiringWithinGraceWindowIsReRead() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockKeychain.expiresAtToReturn = Date().addingTimeInterval(30)
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        await manager.refresh()
+        await manager.refresh()
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 2, "Token inside the 60s grace window should not be reused")
+    }
+
+    @MainActor
+    func testUnexpiredTokenStaysCached() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockKeychain.expiresAtToReturn = Date().addingTimeInterval(3600)
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        await manager.refresh()
+        await manager.refresh()
+
+        XCTAssertEqual(mockKeychain.readTokenCallCount, 1)
+    }
+
+    @MainActor
+    func testIsLoadingDuringRefresh() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil)
+
+        // Before refresh
+        XCTAssertFalse(manager.isLoading)
+
+        await manager.refresh()
+
+        // After refresh
+        XCTAssertFalse(manager.isLoading)
+    }
+
+    // MARK: - iconTier integration
+
+    @MainActor
+    func testIconTierStartsAsIdle() {
+        XCTAssertEqual(manager.iconTier, .idle)
+    }
+
+    @MainActor
+    func testIconTierUpdatesAfterSuccessfulRefresh() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 75.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+
+        await manager.refresh()
+
+        XCTAssertEqual(manager.iconTier, .high)
+    }
+
+    @MainActor
+    func testIconTierShowsErrorAfterFailedRefresh() async {
+        mockKeychain.errorToThrow = KeychainError.notFound
+
+        await manager.refresh()
+
+        XCTAssertEqual(manager.iconTier, .error)
+    }
+
+    @MainActor
+    func testIconTierShowsErrorAfterSuccessThenError() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 85.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+        await manager.refresh()
+        XCTAssertEqual(manager.iconTier, .high)
+
+        // Cached usage is still shown in the popover, but the icon flags the failure
+        mockAPI.responseToReturn = nil
+        mockAPI.errorToThrow = APIError.serverError(500)
+        await manager.refresh()
+        XCTAssertEqual(manager.iconTier, .error)
+    }
+
+    @MainActor
+    func testIconTierGreysOutWhilePaused() async {
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 85.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+        await manager.refresh()
+
+        manager.pauseRefresh()
+        XCTAssertEqual(manager.iconTier, .paused)
+
+        await manager.resumeRefresh()
+        XCTAssertEqual(manager.iconTier, .high)
+    }
+
+    @MainActor
+    func testIconTierUsesConfiguredThresholds() async throws {
+        try AppSettings(defaults: defaults).setIconThresholds(moderate: 50, high: 80, critical: 95)
+        mockKeychain.tokenToReturn = "test-token"
+        mockAPI.responseToReturn = UsageApiResponse(
+            fiveHour: UsageWindow(utilization: 75.0, resetsAt: "2026-03-21T18:00:00Z"),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil
+        )
+
+        await manager.refresh()
+
+        XCTAssertEqual(manager.iconTier, .moderate)
     }
 
     @MainActor
@@ -663,6 +2116,9 @@ final class UsageManagerTests: XCTestCase {
             codexAPIService: mockCodexAPI,
             codexStatsService: mockCodexStats,
             containerService: mockContainer,
+            historyService: mockHistory,
+            notifier: mockNotifier,
+            settings: AppSettings(defaults: defaults),
             widgetReloader: mockReloader.reload
         )
 
@@ -20,6 +20,47 @@ final class UsageMetricTests: XCTestCase {
         let normal = UsageMetric(percent: 72.3, resetsAt: Date())
         XCTAssertEqual(normal.clampedPercent, 72.3)
     }
+
+    func testSecondsUntilResetClampsPassedWindowsToZero() {
+        let now = Date(timeIntervalSince1970: 1711000000)
+
+        XCTAssertEqual(UsageMetric(percent: 10, resetsAt: now.addingTimeInterval(90.7)).secondsUntilReset(now: now), 90)
+        XCTAssertEqual(UsageMetric(percent: 10, resetsAt: now.addingTimeInterval(-30)).secondsUntilReset(now: now), 0)
+        XCTAssertNil(UsageMetric(percent: 10, resetsAt: now, hasResetTime: false).secondsUntilReset(now: now))
+    }
+
+    func testRemainingPercentIsClampedHeadroom() {
+        XCTAssertEqual(UsageMetric(percent: 72.5, resetsAt: Date()).remainingPercent, 27.5)
+        XCTAssertEqual(UsageMetric(percent: 130, resetsAt: Date()).remainingPercent, 0)
+        XCTAssertEqual(UsageMetric(percent: -5, resetsAt: Date()).remainingPercent, 100)
+    }
+
+    func testHasResetPassedMatchesSecondsUntilReset() {
+        let now = Date(timeIntervalSince1970: 1711000000)
+
+        XCTAssertFalse(UsageMetric(percent: 10, resetsAt: now.addingTimeInterval(90)).hasResetPassed(now: now))
+        XCTAssertFalse(UsageMetric(percent: 10, resetsAt: now.addingTimeInterval(0.5)).hasResetPassed(now: now), "Not while under a second remains")
+        XCTAssertTrue(UsageMetric(percent: 10, resetsAt: now).hasResetPassed(now: now))
+        XCTAssertTrue(UsageMetric(percent: 10, resetsAt: now.addingTimeInterval(-30)).hasResetPassed(now: now))
+        XCTAssertFalse(UsageMetric(percent: 10, resetsAt: now, hasResetTime: false).hasResetPassed(now: now))
+    }
+
+    func testStatusBoundaries() {
+        let thresholds = UsageStatusThresholds.standard
+        XCTAssertEqual(UsageMetric(percent: 74.9, resetsAt: Date()).status(thresholds: thresholds), .ok)
+        XCTAssertEqual(UsageMetric(percent: 75, resetsAt: Date()).status(thresholds: thresholds), .warning)
+        XCTAssertEqual(UsageMetric(percent: 89.9, resetsAt: Date()).status(thresholds: thresholds), .warning)
+        XCTAssertEqual(UsageMetric(percent: 90, resetsAt: Date()).status(thresholds: thresholds), .critical)
+        XCTAssertEqual(UsageMetric(percent: 150, resetsAt: Date()).status(thresholds: thresholds), .critical)
+    }
+
+    func testDecodesMetricsCachedBeforeRawResetWasStored() throws {
+        let json = #"{"percent": 45.5, "resetsAt": "2024-03-21T05:46:40Z"}"#
+        let decoded = try UsageSnapshot.makeDecoder().decode(UsageMetric.self, from: Data(json.utf8))
+
+        XCTAssertNil(decoded.rawResetsAt)
+        XCTAssertTrue(decoded.hasResetTime)
+    }
 }
 
 final class TokenStatsTests: XCTestCase {
@@ -45,11 +86,37 @@ final class TokenStatsTests: XCTestCase {
         XCTAssertEqual(stats.formattedWeekTokens, "0")
     }
 
+    func testModelFamilyNormalizesVersionedIDs() {
+        XCTAssertEqual(TokenStats.modelFamily("claude-sonnet-4-5-20250929"), "sonnet")
+        XCTAssertEqual(TokenStats.modelFamily("claude-opus-4-1-20250805"), "opus")
+        XCTAssertEqual(TokenStats.modelFamily("claude-3-5-haiku-20241022"), "haiku")
+        XCTAssertEqual(TokenStats.modelFamily("Claude-Opus-4"), "opus")
+        XCTAssertEqual(TokenStats.modelFamily("gpt-5-codex"), "gpt-5-codex")
+        XCTAssertEqual(TokenStats.modelFamily(StatsService.unknownModel), StatsService.unknownModel)
+    }
+
+    func testTokensByFamilyMergesModelVersions() {
+        let stats = TokenStats(
+            todayTokens: 600, weekTokens: 1600, todayMessages: 1, weekMessages: 2,
+            todayTokensByModel: ["claude-sonnet-4-5-20250929": 500, "claude-haiku-4-5": 100],
+            weekTokensByModel: ["claude-sonnet-4-5-20250929": 500, "claude-sonnet-4-20250514": 600, "claude-opus-4-1": 400, "claude-haiku-4-5": 100]
+        )
+
+        XCTAssertEqual(stats.todayTokensByFamily, ["sonnet": 500, "haiku": 100])
+        XCTAssertEqual(stats.weekTokensByFamily, ["sonnet": 1100, "opus": 400, "haiku": 100])
+    }
+
     func testFormattedTokensBillions() {
         let stats = TokenStats(todayTokens: 2_600_000_000, weekTokens: 1_200_000_000, todayMessages: 3, weekMessages: 10)
         XCTAssertEqual(stats.formattedTodayTokens, "2.6B")
         XCTAssertEqual(stats.formattedWeekTokens, "1.2B")
     }
+
+    func testTokenDeltaPercentNeedsNonzeroYesterday() {
+        XCTAssertEqual(TokenStats(todayTokens: 50, weekTokens: 150, todayMessages: 1, weekMessages: 2, yesterdayTokens: 100).tokenDeltaPercent, -50)
+        XCTAssertNil(TokenStats(todayTokens: 50, weekTokens: 50, todayMessages: 1, weekMessages: 1, yesterdayTokens: 0).tokenDeltaPercent)
+        XCTAssertNil(TokenStats(todayTokens: 50, weekTokens: 50, todayMessages: 1, weekMessages: 1).tokenDeltaPercent)
+    }
 }
 
 final class UsageSnapshotTests: XCTestCase {
@@ -70,6 +137,50 @@ final class UsageSnapshotTests: XCTestCase {
         XCTAssertNil(decoded.sevenDaySonnet)
         XCTAssertEqual(decoded.sevenDayOpus?.percent, 88.0)
         XCTAssertNil(decoded.error)
+        XCTAssertNil(decoded.statusThresholds)
+        XCTAssertEqual(decoded.status(of: decoded.sevenDayOpus!), .warning, "Falls back to the standard thresholds")
+
+        let custom = snapshot.withStatusThresholds(UsageStatusThresholds(warning: 50, critical: 80))
+        let customDecoded = try UsageSnapshot.makeDecoder().decode(
+            UsageSnapshot.self, from: UsageSnapshot.makeEncoder().encode(custom)
+        )
+        XCTAssertEqual(customDecoded.statusThresholds, custom.statusThresholds)
+        XCTAssertEqual(customDecoded.status(of: customDecoded.sevenDayOpus!), .critical)
+    }
+
+    func testDisplayTimeUsesTheGivenZone() throws {
+        let date = Date(timeIntervalSince1970: 1711000000)
+        let locale = Locale(identifier: "en_US_POSIX")
+        let tokyo = try XCTUnwrap(TimeZone(identifier: "Asia/Tokyo"))
+
+        let text = UsageSnapshot.displayTime(date, in: tokyo, locale: locale)
+        XCTAssertTrue(text.contains("2:46"), text)
+        if TimeZone.current.identifier != tokyo.identifier {
+            XCTAssertTrue(text.hasSuffix(try XCTUnwrap(tokyo.abbreviation(for: date))), text)
+        }
+
+        let local = UsageSnapshot.displayTime(date, in: .current, locale: locale)
+        XCTAssertFalse(local.hasSuffix(TimeZone.current.abbreviation(for: date) ?? "-"), "No zone for the Mac's own")
+    }
+
+    func testReportsModelWindowsWhenEitherIsPresent() {
+        func snapshot(sonnet: UsageMetric?, opus: UsageMetric?) -> UsageSnapshot {
+            UsageSnapshot(
+                fiveHour: UsageMetric(percent: 10, resetsAt: Date()),
+                sevenDay: UsageMetric(percent: 20, resetsAt: Date()),
+                sevenDaySonnet: sonnet,
+                sevenDayOpus: opus,
+                tokenStats: .zero,
+                lastUpdated: Date(),
+                lastSuccessfulUpdate: Date(),
+                error: nil
+            )
+        }
+        let zero = UsageMetric(percent: 0, resetsAt: Date())
+
+        XCTAssertFalse(snapshot(sonnet: nil, opus: nil).reportsModelWindows)
+        XCTAssertTrue(snapshot(sonnet: zero, opus: nil).reportsModelWindows, "Zero usage still counts as reported")
+        XCTAssertTrue(snapshot(sonnet: nil, opus: zero).reportsModelWindows)
     }
 
     func testWithError() throws {
@@ -121,6 +232,108 @@ final class UsageSnapshotTests: XCTestCase {
         XCTAssertEqual(decoded.lastSuccessfulUpdate, date)
     }
 
+    func testErrorKindRoundTripsAndDefaultsToNil() throws {
+        let snapshot = UsageSnapshot(
+            fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil,
+            tokenStats: .zero,
+            lastUpdated: Date(timeIntervalSince1970: 1711000000),
+            lastSuccessfulUpdate: nil,
+            error: "Authentication failed.",
+            errorKind: .auth
+        )
+        let data = try UsageSnapshot.makeEncoder().encode(snapshot)
+        XCTAssertEqual(try UsageSnapshot.makeDecoder().decode(UsageSnapshot.self, from: data).errorKind, .auth)
+
+        let legacy = """
+        {
+            "tokenStats": {"todayTokens":0,"weekTokens":0,"todayMessages":0,"weekMessages":0},
+            "lastUpdated": "2024-03-21T12:00:00Z",
+            "error": "Server error (500)."
+        }
+        """.data(using: .utf8)!
+        XCTAssertNil(try UsageSnapshot.makeDecoder().decode(UsageSnapshot.self, from: legacy).errorKind)
+    }
+
+    func testStaleSinceIsSetWhileAnErrorIsShownOverOldData() {
+        let fetched = Date(timeIntervalSince1970: 1711000000)
+        let snapshot = UsageSnapshot(
+            fiveHour: UsageMetric(percent: 10, resetsAt: fetched),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil,
+            tokenStats: .zero,
+            lastUpdated: fetched,
+            lastSuccessfulUpdate: fetched,
+            error: nil
+        )
+
+        XCTAssertNil(snapshot.staleSince)
+        XCTAssertEqual(snapshot.withError("Server error (500).").staleSince, fetched)
+        XCTAssertNil(snapshot.staleAge(now: fetched.addingTimeInterval(60)))
+        XCTAssertEqual(snapshot.withError("Server error (500).").staleAge(now: fetched.addingTimeInterval(90)), 90)
+        XCTAssertNil(snapshot.withError("Server error (500).").withoutClaudeUsage().staleSince)
+    }
+
+    func testPlanRoundTripsAndSurvivesErrors() throws {
+        let snapshot = UsageSnapshot(
+            fiveHour: UsageMetric(percent: 10, resetsAt: Date(timeIntervalSince1970: 1711000000)),
+            sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil,
+            tokenStats: .zero,
+            lastUpdated: Date(timeIntervalSince1970: 1711000000),
+            lastSuccessfulUpdate: nil,
+            error: nil,
+            plan: PlanInfo(name: "Max 20x", organization: "Acme")
+        )
+        let data = try UsageSnapshot.makeEncoder().encode(snapshot)
+
+        XCTAssertEqual(try UsageSnapshot.makeDecoder().decode(UsageSnapshot.self, from: data).plan, snapshot.plan)
+        XCTAssertEqual(snapshot.withError("Server error (500).").plan, snapshot.plan)
+    }
+
+    func testErrorKindClassification() {
+        XCTAssertEqual(UsageErrorKind(KeychainError.notFound), .auth)
+        XCTAssertEqual(UsageErrorKind(KeychainError.accessDenied), .auth)
+        XCTAssertEqual(UsageErrorKind(APIError.unauthorized), .auth)
+        XCTAssertEqual(UsageErrorKind(APIError.forbidden), .auth)
+        XCTAssertEqual(UsageErrorKind(APIError.rateLimited(retryAfter: 30)), .rateLimited)
+        XCTAssertEqual(UsageErrorKind(APIError.networkError("offline")), .network)
+        XCTAssertEqual(UsageErrorKind(APIError.serverError(502)), .server)
+        XCTAssertEqual(UsageErrorKind(APIError.decodingError("bad")), .parse)
+        XCTAssertEqual(UsageErrorKind(CodexAuthError.notConfigured), .auth)
+        XCTAssertEqual(UsageErrorKind(NSError(domain: "test", code: 1)), .other)
+    }
+
+    func testRetryStatusCountsDownToNextRetry() {
+        let now = Date(timeIntervalSince1970: 1_000_000)
+        let snapshot = UsageSnapshot(
+            fiveHour: nil, sevenDay: nil, sevenDaySonnet: nil, sevenDayOpus: nil,
+            tokenStats: .zero, lastUpdated: now, lastSuccessfulUpdate: nil,
+            error: "Network error: offline", errorKind: .network,
+            nextRetryAt: now.addingTimeInterval(44.2)
+        )
+
+        XCTAssertEqual(snapshot.retryStatus(now: now), "Retrying in 45s")
+        XCTAssertEqual(snapshot.retryStatus(now: now.addingTimeInterval(-120)), "Retrying in 3 min")
+        XCTAssertNil(snapshot.retryStatus(now: now.addingTimeInterval(60)))
+    }
+
+    func testTokenStatsDecodesWithoutModelBreakdown() throws {
+        let json = #"{"todayTokens":10,"weekTokens":20,"todayMessages":1,"weekMessages":2}"#.data(using: .utf8)!
+        let stats = try JSONDecoder().decode(TokenStats.self, from: json)
+
+        XCTAssertEqual(stats.weekTokens, 20)
+        XCTAssertEqual(stats.todayTokensByModel, [:])
+        XCTAssertEqual(stats.weekTokensByModel, [:])
+    }
+
+    func testTokenStatsModelBreakdownRoundTrips() throws {
+        let stats = TokenStats(
+            todayTokens: 10, weekTokens: 20, todayMessages: 1, weekMessages: 2,
+            todayTokensByModel: ["claude-opus": 10], weekTokensByModel: ["claude-opus": 15, "claude-sonnet": 5]
+        )
+        let decoded = try JSONDecoder().decode(TokenStats.self, from: JSONEncoder().encode(stats))
+
+        XCTAssertEqual(decoded, stats)
+    }
+
     func testLastSuccessfulUpdateNilWhenMissing() throws {
         // Simulate decoding old data that lacks lastSuccessfulUpdate
         let json = """
@@ -31,13 +31,13 @@ struct UsageTimelineProvider: TimelineProvider {
 
     func getSnapshot(in context: Context, completion: @escaping (UsageTimelineEntry) -> Void) {
         let debug = DebugLogger.shared
-        debug.log("getSnapshot called (isPreview: \(context.isPreview))", source: "Widget")
+        debug.log("getSnapshot called (isPreview: \(context.isPreview))", level: .debug, source: "Widget")
         debug.dumpContainerDiagnostics(source: "Widget-getSnapshot")
 
         let container = SharedContainerService()
         let snapshot = container.readSnapshot()
         let paceSettings = container.readPaceSettings()
-        debug.log("getSnapshot result: \(snapshot != nil ? "got data" : "nil → using placeholder")", source: "Widget")
+        debug.log("getSnapshot result: \(snapshot != nil ? "got data" : "nil → using placeholder")", level: .debug, source: "Widget")
 
         let isPlaceholder = snapshot == nil
         let snapshotData = snapshot ?? placeholder(in: context).snapshot
@@ -47,7 +47,7 @@ struct UsageTimelineProvider: TimelineProvider {
 
     func getTimeline(in context: Context, completion: @escaping (Timeline<UsageTimelineEntry>) -> Void) {
         let debug = DebugLogger.shared
-        debug.log("getTimeline called", source: "Widget")
+        debug.log("getTimeline called", level: .debug, source: "Widget")
         debug.dumpContainerDiagnostics(source: "Widget-getTimeline")
 
         let container = SharedContainerService()
@@ -55,13 +55,13 @@ struct UsageTimelineProvider: TimelineProvider {
         let paceSettings = container.readPaceSettings()
 
         let entries = UsageTimelineEntry.buildTimeline(from: snapshot, paceSettings: paceSettings)
-        debug.log("getTimeline: \(entries.count) entries, snapshot=\(snapshot != nil ? "present" : "nil")", source: "Widget")
+        debug.log("getTimeline: \(entries.count) entries, snapshot=\(snapshot != nil ? "present" : "nil")", level: .debug, source: "Widget")
 
         let policy: TimelineReloadPolicy = snapshot == nil
             ? .after(Date().addingTimeInterval(5 * 60))
             : .atEnd
 
-        debug.log("getTimeline reload policy: \(snapshot == nil ? "retry in 5min" : "atEnd")", source: "Widget")
+        debug.log("getTimeline reload policy: \(snapshot == nil ? "retry in 5min" : "atEnd")", level: .debug, source: "Widget")
         completion(Timeline(entries: entries, policy: policy))
     }
 }
@@ -24,7 +24,8 @@ struct LargeWidgetView: View {
                             extraMetric: snapshot.sevenDayOpus ?? snapshot.sevenDaySonnet,
                             primaryPace: paceByMetric[.fiveHour],
                             weeklyPace: paceByMetric[.sevenDay],
-                            extraPace: snapshot.sevenDayOpus != nil ? paceByMetric[.sevenDayOpus] : paceByMetric[.sevenDaySonnet]
+                            extraPace: snapshot.sevenDayOpus != nil ? paceByMetric[.sevenDayOpus] : paceByMetric[.sevenDaySonnet],
+                            thresholds: snapshot.statusThresholds ?? .standard
                         )
 
                         WidgetProviderSection(
@@ -52,7 +53,9 @@ struct LargeWidgetView: View {
 
                     Spacer()
 
-                    if snapshot.error != nil {
+                    if snapshot.isDemo == true {
+                        WidgetDemoIndicator()
+                    } else if snapshot.error != nil {
                         WidgetErrorIndicator(snapshot: snapshot)
                     } else if snapshot.isStale {
                         HStack(spacing: 2) {
@@ -75,7 +78,8 @@ struct LargeWidgetView: View {
                             label: "5-Hour Window",
                             percent: fiveHour.percent,
                             resetsAt: fiveHour.resetsAt,
-                            paceInfo: paceByMetric[.fiveHour]
+                            paceInfo: paceByMetric[.fiveHour],
+                            thresholds: snapshot.statusThresholds ?? .standard
                         )
                     }
                     if let sevenDay = snapshot.sevenDay {
@@ -83,7 +87,8 @@ struct LargeWidgetView: View {
                             label: "Weekly (All)",
                             percent: sevenDay.percent,
                             resetsAt: sevenDay.resetsAt,
-                            paceInfo: paceByMetric[.sevenDay]
+                            paceInfo: paceByMetric[.sevenDay],
+                            thresholds: snapshot.statusThresholds ?? .standard
                         )
                     }
                     if let sonnet = snapshot.sevenDaySonnet {
@@ -91,7 +96,8 @@ struct LargeWidgetView: View {
                             label: "Weekly (Sonnet)",
                             percent: sonnet.percent,
                             resetsAt: sonnet.resetsAt,
-                            paceInfo: paceByMetric[.sevenDaySonnet]
+                            paceInfo: paceByMetric[.sevenDaySonnet],
+                            thresholds: snapshot.statusThresholds ?? .standard
                         )
                     }
                     if let opus = snapshot.sevenDayOpus {
@@ -100,7 +106,8 @@ struct LargeWidgetView: View {
                             percent: opus.percent,
                             resetsAt: opus.resetsAt,
                             isOpus: true,
-                            paceInfo: paceByMetric[.sevenDayOpus]
+                            paceInfo: paceByMetric[.sevenDayOpus],
+                            thresholds: snapshot.statusThresholds ?? .standard
                         )
                     }
 
@@ -126,7 +133,9 @@ struct LargeWidgetView: View {
 
                     Spacer()
 
-                    if snapshot.error != nil {
+                    if snapshot.isDemo == true {
+                        WidgetDemoIndicator()
+                    } else if snapshot.error != nil {
                         WidgetErrorIndicator(snapshot: snapshot)
                     } else if snapshot.isStale {
                         HStack(spacing: 2) {
@@ -157,6 +166,7 @@ private struct WidgetProviderSection: View {
     let extraPace: PaceInfo?
     var fillGradient: LinearGradient? = nil
     var trackColor: Color = Color.white.opacity(0.12)
+    var thresholds: UsageStatusThresholds = .standard
 
     var body: some View {
         VStack(alignment: .leading, spacing: 8) {
@@ -171,7 +181,8 @@ private struct WidgetProviderSection: View {
                     resetsAt: primaryMetric.resetsAt,
                     paceInfo: primaryPace,
                     fillGradient: fillGradient,
-                    trackColor: trackColor
+                    trackColor: trackColor,
+                    thresholds: thresholds
                 )
             }
 
@@ -182,7 +193,8 @@ private struct WidgetProviderSection: View {
                     resetsAt: weeklyMetric.resetsAt,
                     paceInfo: weeklyPace,
                     fillGradient: fillGradient,
-                    trackColor: trackColor
+                    trackColor: trackColor,
+                    thresholds: thresholds
                 )
             }
 
@@ -193,7 +205,8 @@ private struct WidgetProviderSection: View {
                     resetsAt: extraMetric.resetsAt,
                     paceInfo: extraPace,
                     fillGradient: fillGradient,
-                    trackColor: trackColor
+                    trackColor: trackColor,
+                    thresholds: thresholds
                 )
             }
         }
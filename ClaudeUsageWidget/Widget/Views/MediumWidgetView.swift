@@ -14,7 +14,8 @@ struct MediumWidgetView: View {
                         fiveHour: snapshot.fiveHour,
                         weekly: snapshot.sevenDay,
                         fiveHourPace: paceByMetric[.fiveHour],
-                        weeklyPace: paceByMetric[.sevenDay]
+                        weeklyPace: paceByMetric[.sevenDay],
+                        thresholds: snapshot.statusThresholds ?? .standard
                     )
 
                     WidgetProviderColumn(
@@ -39,7 +40,8 @@ struct MediumWidgetView: View {
                                 label: "5-Hour",
                                 percent: fiveHour.percent,
                                 resetsAt: fiveHour.resetsAt,
-                                paceInfo: paceByMetric[.fiveHour]
+                                paceInfo: paceByMetric[.fiveHour],
+                                thresholds: snapshot.statusThresholds ?? .standard
                             )
                         }
 
@@ -55,7 +57,8 @@ struct MediumWidgetView: View {
                                 label: "Weekly",
                                 percent: sevenDay.percent,
                                 resetsAt: sevenDay.resetsAt,
-                                paceInfo: paceByMetric[.sevenDay]
+                                paceInfo: paceByMetric[.sevenDay],
+                                thresholds: snapshot.statusThresholds ?? .standard
                             )
                         }
 
@@ -71,7 +74,9 @@ struct MediumWidgetView: View {
 
     private var statusFooter: some View {
         Group {
-            if snapshot.error != nil {
+            if snapshot.isDemo == true {
+                WidgetDemoIndicator()
+            } else if snapshot.error != nil {
                 WidgetErrorIndicator(snapshot: snapshot)
             } else if snapshot.isStale {
                 HStack(spacing: 2) {
@@ -94,6 +99,7 @@ private struct WidgetProviderColumn: View {
     let weeklyPace: PaceInfo?
     var fillGradient: LinearGradient? = nil
     var trackColor: Color = Color.white.opacity(0.12)
+    var thresholds: UsageStatusThresholds = .standard
 
     var body: some View {
         VStack(alignment: .leading, spacing: 8) {
@@ -108,7 +114,8 @@ private struct WidgetProviderColumn: View {
                     resetsAt: fiveHour.resetsAt,
                     paceInfo: fiveHourPace,
                     fillGradient: fillGradient,
-                    trackColor: trackColor
+                    trackColor: trackColor,
+                    thresholds: thresholds
                 )
             }
 
@@ -119,7 +126,8 @@ private struct WidgetProviderColumn: View {
                     resetsAt: weekly.resetsAt,
                     paceInfo: weeklyPace,
                     fillGradient: fillGradient,
-                    trackColor: trackColor
+                    trackColor: trackColor,
+                    thresholds: thresholds
                 )
             }
 
@@ -17,7 +17,8 @@ struct SmallWidgetView: View {
                         label: "Claude",
                         percent: fiveHour.percent,
                         resetsAt: fiveHour.resetsAt,
-                        paceInfo: paceByMetric[.fiveHour]
+                        paceInfo: paceByMetric[.fiveHour],
+                        thresholds: snapshot.statusThresholds ?? .standard
                     )
                 }
 
@@ -36,7 +37,8 @@ struct SmallWidgetView: View {
                     label: "5-Hour",
                     percent: fiveHour.percent,
                     resetsAt: fiveHour.resetsAt,
-                    paceInfo: paceByMetric[.fiveHour]
+                    paceInfo: paceByMetric[.fiveHour],
+                    thresholds: snapshot.statusThresholds ?? .standard
                 )
             } else {
                 Text("No data")
@@ -46,7 +48,9 @@ struct SmallWidgetView: View {
 
             Spacer()
 
-            if snapshot.error != nil {
+            if snapshot.isDemo == true {
+                WidgetDemoIndicator()
+            } else if snapshot.error != nil {
                 WidgetErrorIndicator(snapshot: snapshot)
             } else if snapshot.isStale {
                 staleIndicator
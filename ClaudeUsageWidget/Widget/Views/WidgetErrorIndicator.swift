@@ -16,3 +16,12 @@ struct WidgetErrorIndicator: View {
         }
     }
 }
+
+/// Marks made-up numbers from demo mode so they're never read as real usage.
+struct WidgetDemoIndicator: View {
+    var body: some View {
+        Text("DEMO")
+            .font(.system(size: 8, weight: .semibold))
+            .foregroundStyle(AnthropicColors.tan)
+    }
+}
@@ -8,6 +8,7 @@ struct WidgetUsageBar: View {
     var paceInfo: PaceInfo? = nil
     var fillGradient: LinearGradient? = nil
     var trackColor: Color = Color.white.opacity(0.12)
+    var thresholds: UsageStatusThresholds = .standard
 
     var body: some View {
         VStack(alignment: .leading, spacing: 3) {
@@ -68,15 +69,10 @@ struct WidgetUsageBar: View {
         if let fillGradient {
             return fillGradient
         }
-        if percent >= 90 {
-            return AnthropicColors.dangerGradient
-        } else if percent >= 70 {
-            return AnthropicColors.warningGradient
-        } else if isOpus {
-            return AnthropicColors.opusGradient
-        } else {
-            return AnthropicColors.normalGradient
-        }
+        return AnthropicColors.statusGradient(
+            for: UsageStatus.from(percent: min(max(percent, 0), 100), thresholds: thresholds),
+            isOpus: isOpus
+        )
     }
 
 }